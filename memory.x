@@ -1,9 +1,15 @@
 MEMORY {
-    BOOT2 : ORIGIN = 0x10000000, LENGTH = 0x100
-    FLASH : ORIGIN = 0x10000100, LENGTH = 2048K - 0x100
-    RAM   : ORIGIN = 0x20000000, LENGTH = 256K
+    BOOT2   : ORIGIN = 0x10000000, LENGTH = 0x100
+    FLASH   : ORIGIN = 0x10000100, LENGTH = 2048K - 0x100 - 12K
+    /* The last two sectors of the flash keep the low score and the sector below them keeps
+       the calibration, see flash.rs */
+    STORAGE : ORIGIN = 0x10000000 + 2048K - 12K, LENGTH = 12K
+    RAM     : ORIGIN = 0x20000000, LENGTH = 256K
 }
 
+__storage_start = ORIGIN(STORAGE);
+__storage_end = ORIGIN(STORAGE) + LENGTH(STORAGE);
+
 EXTERN(BOOT2_FIRMWARE)
 
 SECTIONS {
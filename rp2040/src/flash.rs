@@ -0,0 +1,170 @@
+//!
+//! This file implements the Storage that keeps the low score and the calibration of the
+//! SmallBall game (see persistence.rs) on the flash of the RP2040, in sectors of the STORAGE
+//! region that memory.x reserves at the end of the flash. The flash is erased and programmed
+//! with the functions of the boot ROM. The flash cannot be read while they run, so they are
+//! called from RAM with interrupts disabled, and the copy of the second stage boot loader is
+//! run afterwards to bring back fast execute-in-place (XIP) access to the flash.
+//!
+
+use core::ops::Range;
+use core::ptr::{addr_of, read_volatile};
+use rp2040_hal::rom_data;
+use smallball::persistence::Storage;
+
+// the address the flash is mapped to by the XIP block
+const XIP_BASE: usize = 0x1000_0000;
+
+// the size in bytes of a sector, the smallest part of the flash that can be erased
+const SECTOR_SIZE: usize = 4096;
+
+// the size in bytes of a page, the smallest part of the flash that can be programmed
+const PAGE_SIZE: usize = 256;
+
+// the size in bytes of the second stage boot loader at the start of the flash
+const BOOT2_SIZE: usize = 256;
+
+// the size of the blocks erased with the block erase command of the flash chip, and the
+// command, which the boot ROM uses where the range to erase allows it
+const BLOCK_SIZE: u32 = 1 << 16;
+const BLOCK_ERASE_COMMAND: u8 = 0xd8;
+
+extern "C" {
+    // the start and end of the STORAGE region defined in memory.x
+    static __storage_start: u8;
+    static __storage_end: u8;
+}
+
+/// The flash functions of the boot ROM, looked up before the flash is taken out of XIP mode.
+struct RomFunctions {
+    connect_internal_flash: unsafe extern "C" fn(),
+    flash_exit_xip: unsafe extern "C" fn(),
+    flash_range_erase: unsafe extern "C" fn(u32, usize, u32, u8),
+    flash_range_program: unsafe extern "C" fn(u32, *const u8, usize),
+    flash_flush_cache: unsafe extern "C" fn(),
+}
+
+/// Sectors of the STORAGE region of the flash.
+pub struct FlashStorage {
+    /// the offset in bytes of the sectors from the start of the flash
+    start: usize,
+    /// the number of sectors
+    sectors: usize,
+    /// the flash functions of the boot ROM
+    functions: RomFunctions,
+    /// a copy in RAM of the second stage boot loader
+    boot2: [u32; BOOT2_SIZE / 4],
+}
+
+impl FlashStorage {
+    /// Return the given sectors of the STORAGE region of the flash. The sectors of two
+    /// FlashStorages must not overlap, and only the first core should run while they erase
+    /// or write the flash.
+    /// # Arguments
+    /// * `sectors` - the indices of the sectors in the STORAGE region
+    pub fn new(sectors: Range<usize>) -> Self {
+        let (start, end) = unsafe {
+            (
+                addr_of!(__storage_start) as usize,
+                addr_of!(__storage_end) as usize,
+            )
+        };
+        let mut boot2 = [0; BOOT2_SIZE / 4];
+        for (index, word) in boot2.iter_mut().enumerate() {
+            *word = unsafe { read_volatile((XIP_BASE as *const u32).add(index)) };
+        }
+        assert!(sectors.end <= (end - start) / SECTOR_SIZE);
+        FlashStorage {
+            start: start - XIP_BASE + sectors.start * SECTOR_SIZE,
+            sectors: sectors.len(),
+            functions: RomFunctions {
+                connect_internal_flash: rom_data::connect_internal_flash::ptr(),
+                flash_exit_xip: rom_data::flash_exit_xip::ptr(),
+                flash_range_erase: rom_data::flash_range_erase::ptr(),
+                flash_range_program: rom_data::flash_range_program::ptr(),
+                flash_flush_cache: rom_data::flash_flush_cache::ptr(),
+            },
+            boot2,
+        }
+    }
+
+    /// Erase or program a range of the flash with interrupts disabled.
+    /// # Arguments
+    /// * `offset` - the offset in bytes of the range from the start of the flash
+    /// * `data` - the data to program, or null to erase the range
+    /// * `count` - the size in bytes of the range
+    fn run(&self, offset: u32, data: *const u8, count: usize) {
+        cortex_m::interrupt::free(|_| unsafe {
+            flash_operation(&self.functions, self.boot2.as_ptr(), offset, data, count);
+        });
+    }
+}
+
+impl Storage for FlashStorage {
+    fn sector_size(&self) -> usize {
+        SECTOR_SIZE
+    }
+
+    fn sectors(&self) -> usize {
+        self.sectors
+    }
+
+    fn read(&mut self, offset: usize, bytes: &mut [u8]) {
+        let address = XIP_BASE + self.start + offset;
+        for (index, byte) in bytes.iter_mut().enumerate() {
+            *byte = unsafe { read_volatile((address + index) as *const u8) };
+        }
+    }
+
+    fn write(&mut self, offset: usize, bytes: &[u8]) {
+        // program whole pages, leaving the bytes outside the given bytes as they are by
+        // programming them with the value of erased flash
+        let mut offset = self.start + offset;
+        let mut bytes = bytes;
+        while !bytes.is_empty() {
+            let start = offset % PAGE_SIZE;
+            let count = bytes.len().min(PAGE_SIZE - start);
+            let mut page = [0xff; PAGE_SIZE];
+            page[start..start + count].copy_from_slice(&bytes[..count]);
+            self.run((offset - start) as u32, page.as_ptr(), PAGE_SIZE);
+            offset += count;
+            bytes = &bytes[count..];
+        }
+    }
+
+    fn erase(&mut self, sector: usize) {
+        let offset = self.start + sector * SECTOR_SIZE;
+        self.run(offset as u32, core::ptr::null(), SECTOR_SIZE);
+    }
+}
+
+/// Erase or program a range of the flash. The function runs from RAM since the flash
+/// cannot be read until it is done, so it must only call the given functions.
+/// # Arguments
+/// * `functions` - the flash functions of the boot ROM
+/// * `boot2` - the copy in RAM of the second stage boot loader
+/// * `offset` - the offset in bytes of the range from the start of the flash
+/// * `data` - the data to program, or null to erase the range
+/// * `count` - the size in bytes of the range
+#[inline(never)]
+#[link_section = ".data.ram_func"]
+unsafe fn flash_operation(
+    functions: &RomFunctions,
+    boot2: *const u32,
+    offset: u32,
+    data: *const u8,
+    count: usize,
+) {
+    (functions.connect_internal_flash)();
+    (functions.flash_exit_xip)();
+    if data.is_null() {
+        (functions.flash_range_erase)(offset, count, BLOCK_SIZE, BLOCK_ERASE_COMMAND);
+    } else {
+        (functions.flash_range_program)(offset, data, count);
+    }
+    (functions.flash_flush_cache)();
+
+    // run the boot loader in thumb mode to set up XIP again
+    let boot2: unsafe extern "C" fn() = core::mem::transmute(boot2 as usize + 1);
+    boot2();
+}
@@ -0,0 +1,65 @@
+//!
+//! This file implements the InputSource of the SmallBall game (see input.rs) for an MPU6050
+//! IMU. The readings of its accelerometer and gyroscope are fused into the tilt of the
+//! controller by a TiltFilter (see fusion.rs), the IMU has no buttons. A failed reading,
+//! such as when the cable is pulled out during a game, yields an invalid input rather than
+//! stopping the game.
+//!
+
+use core::fmt::Debug;
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+use mpu6050::Mpu6050;
+use rp2040_hal::Timer;
+use smallball::fusion::{ImuSample, TiltFilter};
+use smallball::input::{ControlInput, InputSource};
+
+/// A controller that is an MPU6050 IMU.
+pub struct MpuInput<'a, I, F> {
+    /// the driver of the IMU, initialised
+    mpu: Mpu6050<I>,
+    /// the filter that fuses the readings of the IMU into its tilt
+    filter: F,
+    /// the timer that measures the time between the readings
+    timer: &'a Timer,
+    /// the timer reading in microseconds of the last successful reading
+    last_us: u64,
+}
+
+impl<'a, I, F> MpuInput<'a, I, F> {
+    /// Return the controller that reads the given IMU.
+    /// # Arguments
+    /// * `mpu` - the driver of the IMU, initialised
+    /// * `filter` - the filter that fuses the readings of the IMU into its tilt
+    /// * `timer` - the timer that measures the time between the readings
+    pub fn new(mpu: Mpu6050<I>, filter: F, timer: &'a Timer) -> Self {
+        MpuInput {
+            mpu,
+            filter,
+            timer,
+            last_us: timer.get_counter(),
+        }
+    }
+}
+
+impl<I, E, F> InputSource for MpuInput<'_, I, F>
+where
+    I: Write<Error = E> + WriteRead<Error = E>,
+    E: Debug,
+    F: TiltFilter,
+{
+    fn read(&mut self) -> ControlInput {
+        let (acc, gyro) = match (self.mpu.get_acc(), self.mpu.get_gyro()) {
+            (Ok(acc), Ok(gyro)) => (acc, gyro),
+            _ => return ControlInput::invalid(),
+        };
+
+        // measure the time since the last successful reading in whole milliseconds, rounding
+        // the timer readings rather than their difference so that no time is lost
+        let now_us = self.timer.get_counter();
+        let elapsed_ms = (now_us / 1000 - self.last_us / 1000) as u32;
+        self.last_us = now_us;
+
+        let sample = ImuSample::new([acc[0], acc[1], acc[2]], [gyro[0], gyro[1], gyro[2]]);
+        ControlInput::new(self.filter.update(&sample, elapsed_ms), 0)
+    }
+}
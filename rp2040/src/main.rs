@@ -2,42 +2,59 @@
 //! This file implements the game SmallBall (see smallball.rs for details) on an
 //! Adafruit Feather RP2040 connected to an SSD1306 OLED display and an MPU6050
 //! 6-axis IMU via STEMMA QT cables. The pitch and roll measurements from the IMU
-//! are the game control inputs.  
+//! are the game control inputs (see imu.rs) and the screens of the game are drawn by its
+//! renderer (see render.rs).  A second MPU6050 strapped to the alternate address
+//! 0x69 can be connected to the same bus for a two-player race. The size of the display
+//! is selected with the `display-128x64` (default) or `display-128x32` cargo feature. The
+//! low score is kept in the flash (see flash.rs), so it survives a power cycle. The best
+//! scores of the session are shown in a high score table between the intro screens, and
+//! the game over screen of a single player game is followed by the time each goal was
+//! reached, compared with the best game. The mpus are calibrated to the pose they are held
+//! in at the first start, or when a moderate tilt is held during the intro, and the offsets
+//! are kept in the flash too.
 //!
 
 #![no_std]
 #![no_main]
 
+mod flash;
+mod imu;
+
 use adafruit_feather_rp2040::XOSC_CRYSTAL_FREQ;
-use core::fmt::Write;
 use cortex_m_rt::entry;
-use embedded_graphics::pixelcolor::BinaryColor;
-use embedded_graphics::{
-    mono_font::{ascii::FONT_6X10, MonoTextStyleBuilder},
-    prelude::{Primitive, Size},
-    primitives::{Circle, PrimitiveStyleBuilder, Rectangle},
-    text::{Baseline, Text},
-    Drawable,
-};
+use embedded_graphics::prelude::Size;
 use embedded_hal::digital::v2::OutputPin;
 use embedded_time::{fixed_point::FixedPoint, rate::Extensions};
+use flash::FlashStorage;
 use hal::{pac, Clock};
-use heapless::String;
+use imu::MpuInput;
 use mpu6050::Mpu6050;
 use panic_halt as _;
 use rp2040_hal as hal;
 use smallball::config::{
-    DELAY_MS, FULL_SCREEN_OUTLINE_SIZE, FULL_SCREEN_OUTLINE_TOP_LET, GAME_NAME, GAME_NAME_LOCATION,
-    GAME_OVER_LOCATION, GAME_OVER_LOW_SCORE_LOCATION, GAME_OVER_SCORE_LOCATION, GAME_OVER_TEXT,
-    LOW_SCORE_TEXT, SCORE_LOCATION, SCORE_TEXT, SPLASH_SCREEN_SHAPE_LOCATIONS,
-    SPLASH_SCREEN_SHAPE_SIZE,
+    GameConfig, FUSION_TIME_CONSTANT_MS, GAME_VARIANT, LED_BLINK_MS, MAX_PLAYERS,
 };
-use smallball::smallball::{Mode, State};
+use smallball::fusion::ComplementaryFilter;
+use smallball::input::{CalibratedInput, ControlInput, InputSource};
+use smallball::layout::Layout;
+use smallball::persistence::{CalibrationStore, ScoreLog};
+use smallball::render::Renderer;
+use smallball::smallball::{GameEvent, Mode, State, Tilt};
 use ssd1306::{
-    mode::DisplayConfig, rotation::DisplayRotation, size::DisplaySize128x64, I2CDisplayInterface,
-    Ssd1306,
+    mode::DisplayConfig, rotation::DisplayRotation, size::DisplaySize, I2CDisplayInterface, Ssd1306,
 };
 
+#[cfg(all(feature = "display-128x32", feature = "display-128x64"))]
+compile_error!("select only one of the display-128x32 and display-128x64 features");
+#[cfg(not(any(feature = "display-128x32", feature = "display-128x64")))]
+compile_error!("select one of the display-128x32 and display-128x64 features");
+
+// The size of the display, selected with a cargo feature
+#[cfg(feature = "display-128x32")]
+use ssd1306::size::DisplaySize128x32 as Panel;
+#[cfg(feature = "display-128x64")]
+use ssd1306::size::DisplaySize128x64 as Panel;
+
 #[entry]
 fn main() -> ! {
     let mut pac = pac::Peripherals::take().unwrap();
@@ -59,7 +76,7 @@ fn main() -> ! {
     .ok()
     .unwrap();
 
-    // Configure delay to be used for waiting
+    // Configure delay to be used for waiting while the mpu drivers initialise the sensors
     let mut delay = cortex_m::delay::Delay::new(core.SYST, clocks.system_clock.freq().integer());
 
     // The single-cycle I/O block controls our GPIO pins
@@ -89,172 +106,145 @@ fn main() -> ! {
         clocks.peripheral_clock,
     );
 
-    // We are using two drivers, one for the display and one for the mpu. Since each driver
-    // needs to "own" the I2C bus peripheral, we are using the shared_bus crate to easily
-    // allow us to share the I2C bus between the two drivers.
+    // We are using drivers for the display and for each mpu. Since each driver needs to
+    // "own" the I2C bus peripheral, we are using the shared_bus crate to easily allow us to
+    // share the I2C bus between the drivers.
     let bus = shared_bus::BusManagerSimple::new(i2c);
 
     // Configure the display driver
     let interface = I2CDisplayInterface::new_alternate_address(bus.acquire_i2c());
-    let mut display = Ssd1306::new(interface, DisplaySize128x64, DisplayRotation::Rotate0)
-        .into_buffered_graphics_mode();
+    let mut display =
+        Ssd1306::new(interface, Panel, DisplayRotation::Rotate0).into_buffered_graphics_mode();
     display.init().unwrap();
 
     // Configure the mpu driver
     let mut mpu = Mpu6050::new(bus.acquire_i2c());
     mpu.init(&mut delay).unwrap();
 
-    // Set the text style for drawing text to the display
-    let text_style = MonoTextStyleBuilder::new()
-        .font(&FONT_6X10)
-        .text_color(BinaryColor::On)
-        .build();
-
-    // Set the style for drawing shapes
-    let style = PrimitiveStyleBuilder::new()
-        .stroke_width(1)
-        .stroke_color(BinaryColor::On)
-        .build();
+    // Configure the driver of the mpu of the second player at the alternate address, the
+    // game is a two-player race if that mpu is connected
+    let mut mpu2 = Mpu6050::new_with_addr(bus.acquire_i2c(), 0x69);
+    let players = if mpu2.init(&mut delay).is_ok() { 2 } else { 1 };
+
+    // Configure the timer that measures the time between game updates
+    let timer = hal::Timer::new(pac.TIMER, &mut pac.RESETS);
+    let mut last_update_us = timer.get_counter();
+
+    // load the offsets of the last calibration of the mpus from the sector of the flash below
+    // the low score, so that the low score keeps the sectors it had before the calibration
+    let mut calibration = CalibrationStore::new(FlashStorage::new(0..1));
+    let mut offsets = calibration.offsets().unwrap_or_default();
+
+    // the mpu of each player is the controller of their ball, with its readings smoothed
+    // by a complementary filter and corrected by the offset of the player
+    let mut controllers = (
+        CalibratedInput::new(
+            MpuInput::new(
+                mpu,
+                ComplementaryFilter::new(FUSION_TIME_CONSTANT_MS),
+                &timer,
+            ),
+            offsets[0],
+        ),
+        CalibratedInput::new(
+            MpuInput::new(
+                mpu2,
+                ComplementaryFilter::new(FUSION_TIME_CONSTANT_MS),
+                &timer,
+            ),
+            offsets[1],
+        ),
+    );
 
     // get the led pin for blinking
     let mut led_pin = pins.gpio13.into_push_pull_output();
 
-    // initialize the SmallBall game state
-    let mut state = State::new();
+    // Collect a seed from the random bit of the ring oscillator, so that the goals are
+    // placed differently every time the game is started
+    let mut seed = 0u32;
+    for _ in 0..32 {
+        seed = (seed << 1) | pac.ROSC.randombit.read().randombit().bit() as u32;
+    }
+
+    // lay the SmallBall game out on the display and initialize the game state
+    let layout = Layout::new(Size::new(Panel::WIDTH as u32, Panel::HEIGHT as u32));
+    let config = GameConfig::builder().with_layout(layout).build().unwrap();
+
+    // load the low score of earlier games from the flash
+    let mut scores = ScoreLog::new(FlashStorage::new(1..3));
+
+    let mut state = State::new_with_config(config)
+        .with_seed(seed)
+        .with_variant(GAME_VARIANT)
+        .with_players(players)
+        .with_low_score(scores.best().unwrap_or(i32::MAX));
+
+    // calibrate the mpus at startup if they have never been calibrated
+    if calibration.offsets().is_none() {
+        state.calibrate();
+    }
+
+    // the renderer of the screens of the game, which animates the events of the game
+    let mut renderer = Renderer::default();
 
     loop {
+        // draw the screen of the current mode of the game
         display.clear();
+        renderer.render(&state, &mut display).unwrap();
+        display.flush().unwrap();
+
+        // get the control input of each player for the SmallBall game
+        let mut inputs = [ControlInput::invalid(); MAX_PLAYERS];
+        inputs[0] = controllers.0.read();
+        if players > 1 {
+            inputs[1] = controllers.1.read();
+        }
 
-        match state.mode() {
-            Mode::Intro => {
-                // draw screen outline
-                Rectangle::new(FULL_SCREEN_OUTLINE_TOP_LET, FULL_SCREEN_OUTLINE_SIZE)
-                    .into_styled(style)
-                    .draw(&mut display)
-                    .unwrap();
-
-                // draw Small Ball text
-                Text::with_baseline(GAME_NAME, GAME_NAME_LOCATION, text_style, Baseline::Top)
-                    .draw(&mut display)
-                    .unwrap();
-
-                // draw a square
-                Rectangle::new(SPLASH_SCREEN_SHAPE_LOCATIONS[0], SPLASH_SCREEN_SHAPE_SIZE)
-                    .into_styled(style)
-                    .draw(&mut display)
-                    .unwrap();
-
-                // draw a circle
-                Circle::new(
-                    SPLASH_SCREEN_SHAPE_LOCATIONS[1],
-                    SPLASH_SCREEN_SHAPE_SIZE.width,
-                )
-                .into_styled(style)
-                .draw(&mut display)
-                .unwrap();
-
-                // draw a square
-                Rectangle::new(SPLASH_SCREEN_SHAPE_LOCATIONS[2], SPLASH_SCREEN_SHAPE_SIZE)
-                    .into_styled(style)
-                    .draw(&mut display)
-                    .unwrap();
-
-                display.flush().unwrap();
-                delay.delay_ms(DELAY_MS);
-            }
-            Mode::Play => {
-                // draw the screen outline
-                Rectangle::new(state.screen_outline_top_left(), state.screen_outline_size())
-                    .into_styled(style)
-                    .draw(&mut display)
-                    .unwrap();
-
-                // draw the goals that are alive
-                for goal in state.goals_alive() {
-                    Rectangle::new(goal.location(), Size::new_equal(goal.size()))
-                        .into_styled(style)
-                        .draw(&mut display)
-                        .unwrap();
-                }
+        // measure the time since the last update in whole milliseconds, rounding the
+        // timer readings rather than their difference so that no time is lost
+        let now_us = timer.get_counter();
+        let elapsed_ms = (now_us / 1000 - last_update_us / 1000) as u32;
+        last_update_us = now_us;
+
+        // blink the LED without blocking the game loop
+        if now_us / 1000 / LED_BLINK_MS as u64 % 2 == 0 {
+            led_pin.set_high().unwrap();
+        } else {
+            led_pin.set_low().unwrap();
+        }
 
-                // draw the ball
-                Circle::new(state.ball().location(), state.ball().size())
-                    .into_styled(style)
-                    .draw(&mut display)
-                    .unwrap();
-
-                // draw the score
-                let mut score_text = String::<20>::from(SCORE_TEXT);
-                write!(score_text, "{}", state.score()).unwrap();
-                Text::with_baseline(
-                    score_text.as_str(),
-                    SCORE_LOCATION,
-                    text_style,
-                    Baseline::Top,
-                )
-                .draw(&mut display)
-                .unwrap();
-
-                display.flush().unwrap();
-            }
-            Mode::Over => {
-                // draw screen outline
-                Rectangle::new(FULL_SCREEN_OUTLINE_TOP_LET, FULL_SCREEN_OUTLINE_SIZE)
-                    .into_styled(style)
-                    .draw(&mut display)
-                    .unwrap();
-
-                // draw Game Over text
-                Text::with_baseline(
-                    GAME_OVER_TEXT,
-                    GAME_OVER_LOCATION,
-                    text_style,
-                    Baseline::Top,
-                )
-                .draw(&mut display)
-                .unwrap();
-
-                // draw the score
-                let mut score_text = String::<20>::from(SCORE_TEXT);
-                write!(score_text, "{}", state.score()).unwrap();
-                Text::with_baseline(
-                    score_text.as_str(),
-                    GAME_OVER_SCORE_LOCATION,
-                    text_style,
-                    Baseline::Top,
-                )
-                .draw(&mut display)
-                .unwrap();
-
-                // draw the high score
-                let mut score_text = String::<20>::from(LOW_SCORE_TEXT);
-                write!(score_text, "{}", state.low_score()).unwrap();
-                Text::with_baseline(
-                    score_text.as_str(),
-                    GAME_OVER_LOW_SCORE_LOCATION,
-                    text_style,
-                    Baseline::Top,
-                )
-                .draw(&mut display)
-                .unwrap();
-
-                display.flush().unwrap();
-                delay.delay_ms(DELAY_MS);
+        // update the state of the game based on the elapsed time and the latest control inputs
+        state.update_inputs(elapsed_ms, &inputs[..players]);
+        renderer.update(&state, elapsed_ms);
+
+        // keep a new low score in the flash and keep and apply the offsets of a calibration
+        for event in state.events() {
+            match event {
+                GameEvent::GameOver {
+                    score,
+                    new_record: true,
+                } => {
+                    scores.record(*score);
+                }
+                GameEvent::ModeChanged {
+                    mode: Mode::Calibrate,
+                } => {
+                    // measure the tilt of the mpus without the offsets of the last calibration
+                    controllers.0.set_offset(Tilt::default());
+                    controllers.1.set_offset(Tilt::default());
+                }
+                GameEvent::Calibrated => {
+                    offsets = state.offsets();
+                    calibration.save(offsets);
+                }
+                GameEvent::ModeChanged { mode: Mode::Intro } => {
+                    // apply the offsets of a calibration that has just been kept, or go back
+                    // to the last ones if it measured nothing
+                    controllers.0.set_offset(offsets[0]);
+                    controllers.1.set_offset(offsets[1]);
+                }
+                _ => {}
             }
         }
-
-        // blink the LED
-        led_pin.set_high().unwrap();
-        delay.delay_ms(10);
-        led_pin.set_low().unwrap();
-        delay.delay_ms(10);
-
-        // get mpu control input for the SmallBall game
-        let acc_angles = mpu.get_acc_angles().unwrap();
-        let roll = acc_angles.get(0).unwrap();
-        let pitch = acc_angles.get(1).unwrap();
-
-        // update the state of the game based on the latest control inputs
-        state.update(pitch, roll);
     }
 }
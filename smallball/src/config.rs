@@ -1,19 +1,97 @@
 //!
 //! In this file the configuration for the SmallBall game is defined.   The version
-//! of SmallBall defined below is configured for a screen of size 128x64 and
+//! of SmallBall defined below is designed for a screen of size 128x64 and
 //! relies on user control input from an mpu sensor's pitch and roll measurements.
+//! The constants are the default GameConfig, a GameConfig with other values can be
+//! built at runtime, for example to scale the game to a display of another size.
 //!
 
-use embedded_graphics::prelude::{Point, Size};
+use crate::{
+    layout::Layout,
+    level::{max_goals, total_goals, Level},
+    math::{contains_rectangle, intersects, intersects_rectangle, scale},
+    shaping::{InputPreset, InputShaping},
+    smallball::{GameVariant, Hazard, HazardEffect, Obstacle},
+};
+use embedded_graphics::{
+    prelude::{Point, Size},
+    primitives::Rectangle,
+};
+use heapless::Vec;
 
-// Delay time between game modes in milliseconds.
-pub const DELAY_MS: u32 = 3000;
+// The variant of the game that is played.
+pub const GAME_VARIANT: GameVariant = GameVariant::Free;
+
+// The time in milliseconds the intro screen is shown before the game starts.
+pub const INTRO_MS: u32 = 3000;
+
+// The time in milliseconds the next level screen is shown before the level starts.
+pub const NEXT_LEVEL_MS: u32 = 3000;
+
+// The time in milliseconds the game over screen is shown before a new game starts.
+pub const OVER_MS: u32 = 6000;
+
+// The time in milliseconds the first page of the game over screen is shown, the score and
+// the low score are shown first and the splits of the goals for the rest of the screen.
+pub const OVER_PAGE_MS: u32 = 3000;
+
+// The time in milliseconds the high score table is shown before the intro is shown again.
+pub const HIGH_SCORES_MS: u32 = 3000;
+
+// The time in milliseconds the initials of a new high score can be entered before they are
+// taken as they are.
+pub const INITIALS_MS: u32 = 30000;
+
+// The tilt angle of an mpu sensor that skips the intro, next level and game over screens.
+pub const SKIP_ANGLE: f32 = 0.8;
+
+// The time in milliseconds a screen is shown before it can be skipped by a tilt.
+pub const SKIP_MIN_MS: u32 = 500;
+
+// The time in milliseconds a calibration of the mpu sensors lasts, and the time at its
+// start the players are given to bring their mpu sensor to rest before it is measured.
+pub const CALIBRATE_MS: u32 = 3000;
+pub const CALIBRATE_SETTLE_MS: u32 = 1000;
+
+// The tilt angle of an mpu sensor, below the skip angle, that starts a calibration when it
+// is held during the intro for the given time in milliseconds.
+pub const CALIBRATE_ANGLE: f32 = 0.4;
+pub const CALIBRATE_HOLD_MS: u32 = 1500;
+
+// The time in milliseconds the LED stays on and stays off when it blinks.
+pub const LED_BLINK_MS: u32 = 10;
+
+// The tilt angle of an mpu sensor that moves on the letter of the initials that is chosen,
+// when rolled, or confirms it, when pitched.
+pub const INITIALS_ANGLE: f32 = 0.3;
+
+// The time in milliseconds a roll is held before the letter of the initials moves on again.
+pub const INITIALS_REPEAT_MS: u32 = 300;
+
+// The number of scores in the high score table.
+pub const HIGH_SCORES: usize = 10;
+
+// The number of letters of the initials of a player in the high score table.
+pub const INITIALS: usize = 3;
+
+// The maximum number of players, each player controls their own ball with their own mpu.
+pub const MAX_PLAYERS: usize = 2;
+
+// The size of the screen the game is designed for, the layout of the game is scaled from
+// this size to the size of the display.
+pub const REFERENCE_SCREEN_SIZE: Size = Size::new(128, 64);
+
+// The height of the header above the game space, where the score is drawn during game play.
+pub const HEADER_HEIGHT: u32 = 10;
 
 // The top left point for the rectangle that outlines the entire screen.
 pub const FULL_SCREEN_OUTLINE_TOP_LET: Point = Point::new(0, 0);
 
 // The size of the rectangle that outlines the entire screen.
-pub const FULL_SCREEN_OUTLINE_SIZE: Size = Size::new(127, 63);
+pub const FULL_SCREEN_OUTLINE_SIZE: Size = Size::new(
+    REFERENCE_SCREEN_SIZE.width - 1,
+    REFERENCE_SCREEN_SIZE.height - 1,
+);
 
 // The name of the game displayed on the splash screen.
 pub const GAME_NAME: &str = "Small Ball";
@@ -49,23 +127,161 @@ pub const LOW_SCORE_TEXT: &str = "low score: ";
 // the location of the low score text during game over
 pub const GAME_OVER_LOW_SCORE_LOCATION: Point = Point::new(2, 40);
 
-// the boundaries of the game space
+// The text to draw before the player number on the game over screen of a race
+pub const PLAYER_TEXT: &str = "P";
+
+// The text to draw after the number of goals a player visited in a race
+pub const GOALS_TEXT: &str = " goals ";
+
+// the locations of the result of each player on the game over screen of a race
+pub const GAME_OVER_PLAYER_LOCATIONS: [Point; MAX_PLAYERS] =
+    [GAME_OVER_SCORE_LOCATION, GAME_OVER_LOW_SCORE_LOCATION];
+
+// The text to draw above the high score table
+pub const HIGH_SCORES_TEXT: &str = "High Scores";
+
+// The location of the high score text
+pub const HIGH_SCORES_LOCATION: Point = Point::new(31, 2);
+
+// The locations of the scores in the high score table, drawn with the small font in two
+// columns from the best score
+pub const HIGH_SCORE_LOCATIONS: [Point; HIGH_SCORES] = [
+    Point::new(2, 14),
+    Point::new(2, 24),
+    Point::new(2, 34),
+    Point::new(2, 44),
+    Point::new(2, 54),
+    Point::new(66, 14),
+    Point::new(66, 24),
+    Point::new(66, 34),
+    Point::new(66, 44),
+    Point::new(66, 54),
+];
+
+// The text to draw above the initials of a new high score
+pub const ENTER_INITIALS_TEXT: &str = "New High Score";
+
+// The location of the enter initials text
+pub const ENTER_INITIALS_LOCATION: Point = Point::new(22, 2);
+
+// The location of the first letter of the initials of a new high score
+pub const INITIALS_LOCATION: Point = Point::new(49, 26);
+
+// The distance between the letters of the initials of a new high score
+pub const INITIALS_SPACING: i32 = 12;
+
+// The offset from the top left of a letter of the initials to the line that marks the
+// letter that is chosen, and the size of the line
+pub const INITIALS_CURSOR_OFFSET: Point = Point::new(0, 11);
+pub const INITIALS_CURSOR_SIZE: Size = Size::new(6, 1);
+
+// The text to draw above the splits on the game over screen
+pub const SPLITS_TEXT: &str = "Splits";
+
+// The location of the splits text
+pub const SPLITS_LOCATION: Point = Point::new(46, 2);
+
+// The text to draw before the number of a goal in the splits
+pub const SPLIT_TEXT: &str = "G";
+
+// The locations of the splits on the game over screen, drawn with the small font in three
+// columns from the first goal
+pub const SPLIT_LOCATIONS: [Point; MAX_SPLITS] = [
+    Point::new(2, 14),
+    Point::new(2, 24),
+    Point::new(2, 34),
+    Point::new(2, 44),
+    Point::new(2, 54),
+    Point::new(44, 14),
+    Point::new(44, 24),
+    Point::new(44, 34),
+    Point::new(44, 44),
+    Point::new(44, 54),
+    Point::new(86, 14),
+    Point::new(86, 24),
+    Point::new(86, 34),
+    Point::new(86, 44),
+    Point::new(86, 54),
+];
+
+// The text to draw above the progress of a calibration
+pub const CALIBRATE_TEXT: &str = "Calibrating";
+
+// The location of the calibrate text
+pub const CALIBRATE_LOCATION: Point = Point::new(31, 2);
+
+// The location and size of the outline of the bar that fills up as a calibration goes on
+pub const CALIBRATE_BAR_LOCATION: Point = Point::new(14, 20);
+pub const CALIBRATE_BAR_SIZE: Size = Size::new(100, 8);
+
+// The locations of the measured offsets of each player, drawn with the small font
+pub const CALIBRATE_OFFSET_LOCATIONS: [Point; MAX_PLAYERS] =
+    [Point::new(14, 36), Point::new(14, 46)];
+
+// The text to draw before the level number between levels
+pub const LEVEL_TEXT: &str = "Level ";
+
+// The location of the level text between levels
+pub const LEVEL_LOCATION: Point = Point::new(43, 16);
+
+// The text to draw for the par score of the next level
+pub const PAR_TEXT: &str = "par: ";
+
+// the location of the par score text between levels
+pub const PAR_LOCATION: Point = Point::new(2, 40);
+
+// the boundaries of the top left of the ball in the game space, below the header and inside
+// the screen outline
 pub const X_MIN: i32 = 0;
-pub const X_MAX: i32 = 118;
-pub const Y_MIN: i32 = 10;
-pub const Y_MAX: i32 = 56;
+pub const X_MAX: i32 = REFERENCE_SCREEN_SIZE.width as i32 - 2 - BALL_SIZE as i32;
+pub const Y_MIN: i32 = HEADER_HEIGHT as i32;
+pub const Y_MAX: i32 = REFERENCE_SCREEN_SIZE.height as i32 - BALL_SIZE as i32;
 
 // the top left coordinate of the screen outline during game play
-pub const SCREEN_OUTLINE_TOP_LET: Point = Point::new(0, 9);
+pub const SCREEN_OUTLINE_TOP_LET: Point = Point::new(0, HEADER_HEIGHT as i32 - 1);
 
 // the size of the screen outline during game play
-pub const SCREEN_OUTLINE_SIZE: Size = Size::new(127, 55);
+pub const SCREEN_OUTLINE_SIZE: Size = Size::new(
+    REFERENCE_SCREEN_SIZE.width - 1,
+    REFERENCE_SCREEN_SIZE.height - HEADER_HEIGHT + 1,
+);
+
+// the pitch/roll angle threshold, below which the tilt is ignored and the ball is not accelerated
+pub const ANGLE_THRESHOLD: f32 = 0.1;
+
+// the preset of the shaping of the pitch/roll angles before they accelerate the ball, the
+// classic preset only ignores the tilt below the angle threshold
+pub const INPUT_PRESET: InputPreset = InputPreset::Classic;
+
+// the time constant in milliseconds of the complementary filter of the mpu readings, the
+// tilt follows the gyroscope over shorter times and the accelerometer over longer times
+pub const FUSION_TIME_CONSTANT_MS: u32 = 500;
+
+// the proportional and integral gains of the Mahony filter of the mpu readings, the
+// integral gain cancels the bias of the gyroscope over time
+pub const MAHONY_KP: f32 = 1.0;
+pub const MAHONY_KI: f32 = 0.05;
+
+// the duration of each step of the ball physics in milliseconds, the ball moves in fixed
+// steps so that its motion does not depend on how often the game is updated
+pub const PHYSICS_STEP_MS: u32 = 20;
 
-// the pitch/roll angle threshold, above which the ball is moved in the corresponding direction
-pub const ANGLE_THRESHOLD: f32 = 0.6;
+// the acceleration of the ball each physics step, in pixels per step squared, per radian
+// of pitch/roll response of the input shaping
+pub const BALL_ACCELERATION: f32 = 0.1;
 
-// the distance the ball moves each loop if pitch/roll angle is above threshold
-pub const BALL_DELTA: i32 = 2;
+// the fraction of the ball's velocity that is lost to friction each physics step
+pub const BALL_FRICTION: f32 = 0.025;
+
+// the maximum speed of the ball in pixels per physics step
+pub const BALL_MAX_SPEED: f32 = 1.5;
+
+// the speed in pixels per physics step below which a coasting ball comes to rest
+pub const BALL_STOP_SPEED: f32 = 0.02;
+
+// the fraction of the ball's speed that is kept when it bounces off a wall, 0.0 stops the
+// ball on the wall and 1.0 is a perfectly elastic bounce
+pub const WALL_RESTITUTION: f32 = 0.5;
 
 // the initial location of each goal
 pub const GOAL_LOCATIONS: [Point; 4] = [
@@ -78,8 +294,1038 @@ pub const GOAL_LOCATIONS: [Point; 4] = [
 // the initial location of the ball
 pub const BALL_LOCATION: Point = Point::new(88, 20);
 
+// the score penalty in milliseconds for falling into a hole that costs points
+pub const HAZARD_PENALTY: i32 = 5000;
+
+// the size of each hole
+pub const HAZARD_SIZE: u32 = 8;
+
+// the time in milliseconds of the animation of the ball falling into a hole
+pub const FALL_ANIMATION_MS: u32 = 160;
+
+// the levels of the game, played in order, the first level uses the goal and ball
+// locations above and later levels add obstacles and holes
+pub const LEVELS: [Level; 3] = [
+    Level::new(&GOAL_LOCATIONS, BALL_LOCATION, None),
+    Level::new(
+        &[
+            Point::new(110, 12),
+            Point::new(10, 30),
+            Point::new(70, 50),
+            Point::new(100, 30),
+        ],
+        Point::new(55, 30),
+        Some(20000),
+    )
+    .with_obstacles(&[Obstacle::new(Point::new(80, 10), Size::new(3, 30))])
+    .with_hazards(&[Hazard::new(
+        Point::new(30, 44),
+        HAZARD_SIZE,
+        HazardEffect::Reset,
+    )]),
+    Level::new(
+        &[
+            Point::new(2, 12),
+            Point::new(110, 54),
+            Point::new(110, 12),
+            Point::new(2, 54),
+        ],
+        Point::new(58, 32),
+        Some(25000),
+    )
+    .with_obstacles(&[
+        Obstacle::new(Point::new(20, 28), Size::new(30, 3)),
+        Obstacle::new(Point::new(78, 40), Size::new(30, 3)),
+    ])
+    .with_hazards(&[
+        Hazard::new(
+            Point::new(56, 12),
+            HAZARD_SIZE,
+            HazardEffect::Penalty(HAZARD_PENALTY),
+        ),
+        Hazard::new(Point::new(56, 48), HAZARD_SIZE, HazardEffect::Reset),
+    ]),
+];
+
 /// The size of each goal
 pub const GOAL_SIZE: u32 = 8;
 
+// the maximum number of goals in a level
+pub const MAX_GOALS: usize = 4;
+
+// the maximum number of obstacles in a level
+pub const MAX_OBSTACLES: usize = 4;
+
+// the maximum number of holes in a level
+pub const MAX_HAZARDS: usize = 4;
+
+// the maximum number of game events raised by a single update of a game with the default
+// goal capacity, see max_events
+pub const MAX_EVENTS: usize = max_events(MAX_GOALS);
+
+/// Return the maximum number of game events raised by a single update of a game with the
+/// given goal capacity: a mode change, the end of the game, a new high score or the end of a
+/// calibration, the mode change of a calibration started after the update, every goal and,
+/// for every player, each side of their ball and every hole. The function is const so that
+/// the event capacity of a game can be checked at compile time.
+/// # Arguments
+/// * `goals` - the goal capacity of the game
+pub const fn max_events(goals: usize) -> usize {
+    3 + goals + (4 + MAX_HAZARDS) * MAX_PLAYERS
+}
+
+// the levels above fit the default goal capacity, checked at compile time
+const _: () = assert!(
+    max_goals(&LEVELS) <= MAX_GOALS,
+    "a level has more than MAX_GOALS goals"
+);
+
+// the maximum number of splits of a run, the goals reached after that are not split
+pub const MAX_SPLITS: usize = 15;
+
+// every goal of the levels above is split, checked at compile time
+const _: () = assert!(
+    total_goals(&LEVELS) <= MAX_SPLITS,
+    "the levels have more than MAX_SPLITS goals"
+);
+
+// the offset from the top left of a goal to its number, drawn for the goals that are not
+// yet the target in the sequence variant of the game
+pub const GOAL_NUMBER_OFFSET: Point = Point::new(2, 1);
+
+// the number of random locations tried for each goal when the goals are placed from a seed
+// before falling back to the first free location from the location in the level definition
+pub const GOAL_PLACEMENT_ATTEMPTS: u32 = 100;
+
 // The size of the ball
 pub const BALL_SIZE: u32 = 8;
+
+/// The configuration of a game of SmallBall. The default configuration is made of the
+/// constants above, other configurations are made with a GameConfigBuilder, which checks
+/// that the game can be played before the configuration is used. The levels are designed
+/// for the level area and scaled to the play area. The goal capacity `N` is the most goals
+/// a level can have and the event capacity `E` is the most events an update of the game can
+/// raise, at least max_events(N), which is checked at compile time. A game for more than
+/// MAX_GOALS goals is built with `GameConfigBuilder::<N, { max_events(N) }>::new()`.
+#[derive(Clone, Copy, Debug)]
+pub struct GameConfig<const N: usize = MAX_GOALS, const E: usize = MAX_EVENTS> {
+    /// the levels of the game, played in order
+    pub(crate) levels: &'static [Level],
+    /// the area of the screen the levels are designed for
+    pub(crate) level_area: Rectangle,
+    /// the area of the screen the ball moves in
+    pub(crate) play_area: Rectangle,
+    /// the outline of the game space drawn during game play
+    pub(crate) screen_outline: Rectangle,
+    /// the size of the ball
+    pub(crate) ball_size: u32,
+    /// the size of each goal
+    pub(crate) goal_size: u32,
+    /// the shaping of the pitch/roll angles before they accelerate the ball
+    pub(crate) input_shaping: InputShaping,
+    /// the duration of each step of the ball physics in milliseconds
+    pub(crate) physics_step_ms: u32,
+    /// the acceleration of the ball each physics step per radian of tilt
+    pub(crate) ball_acceleration: f32,
+    /// the fraction of the velocity of the ball that is lost to friction each physics step
+    pub(crate) ball_friction: f32,
+    /// the maximum speed of the ball in pixels per physics step
+    pub(crate) ball_max_speed: f32,
+    /// the speed in pixels per physics step below which a coasting ball comes to rest
+    pub(crate) ball_stop_speed: f32,
+    /// the fraction of the speed of the ball that is kept when it bounces off a wall
+    pub(crate) wall_restitution: f32,
+    /// the time in milliseconds the intro screen is shown
+    pub(crate) intro_ms: u32,
+    /// the time in milliseconds the next level screen is shown
+    pub(crate) next_level_ms: u32,
+    /// the time in milliseconds the game over screen is shown
+    pub(crate) over_ms: u32,
+    /// the time in milliseconds the first page of the game over screen is shown
+    pub(crate) over_page_ms: u32,
+    /// the time in milliseconds the high score table is shown
+    pub(crate) high_scores_ms: u32,
+    /// the time in milliseconds the initials of a new high score can be entered
+    pub(crate) initials_ms: u32,
+    /// the tilt angle that moves on or confirms the letter of the initials that is chosen
+    pub(crate) initials_angle: f32,
+    /// the time in milliseconds a roll is held before the letter moves on again
+    pub(crate) initials_repeat_ms: u32,
+    /// the time in milliseconds a calibration lasts
+    pub(crate) calibrate_ms: u32,
+    /// the time in milliseconds at the start of a calibration before the tilt is measured
+    pub(crate) calibrate_settle_ms: u32,
+    /// the tilt angle that starts a calibration when it is held during the intro
+    pub(crate) calibrate_angle: f32,
+    /// the time in milliseconds the calibrate angle is held to start a calibration
+    pub(crate) calibrate_hold_ms: u32,
+    /// the tilt angle that skips the intro, next level and game over screens
+    pub(crate) skip_angle: f32,
+    /// the time in milliseconds a screen is shown before it can be skipped
+    pub(crate) skip_min_ms: u32,
+}
+
+impl GameConfig {
+    /// Return a builder for a configuration that starts from the default configuration.
+    pub fn builder() -> GameConfigBuilder {
+        GameConfigBuilder::new()
+    }
+}
+
+impl<const N: usize, const E: usize> GameConfig<N, E> {
+    /// The event capacity fits every event an update of a game with the goal capacity can
+    /// raise, checked at compile time when a configuration is made.
+    const EVENTS_FIT: () = assert!(
+        E >= max_events(N),
+        "the event capacity is smaller than max_events of the goal capacity"
+    );
+
+    /// Return the configuration made of the constants above.
+    fn from_constants() -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::EVENTS_FIT;
+        let layout = Layout::new(REFERENCE_SCREEN_SIZE);
+        GameConfig {
+            levels: &LEVELS,
+            level_area: layout.play_area(),
+            play_area: layout.play_area(),
+            screen_outline: layout.screen_outline(),
+            ball_size: BALL_SIZE,
+            goal_size: GOAL_SIZE,
+            input_shaping: INPUT_PRESET.shaping(),
+            physics_step_ms: PHYSICS_STEP_MS,
+            ball_acceleration: BALL_ACCELERATION,
+            ball_friction: BALL_FRICTION,
+            ball_max_speed: BALL_MAX_SPEED,
+            ball_stop_speed: BALL_STOP_SPEED,
+            wall_restitution: WALL_RESTITUTION,
+            intro_ms: INTRO_MS,
+            next_level_ms: NEXT_LEVEL_MS,
+            over_ms: OVER_MS,
+            over_page_ms: OVER_PAGE_MS,
+            high_scores_ms: HIGH_SCORES_MS,
+            initials_ms: INITIALS_MS,
+            initials_angle: INITIALS_ANGLE,
+            initials_repeat_ms: INITIALS_REPEAT_MS,
+            calibrate_ms: CALIBRATE_MS,
+            calibrate_settle_ms: CALIBRATE_SETTLE_MS,
+            calibrate_angle: CALIBRATE_ANGLE,
+            calibrate_hold_ms: CALIBRATE_HOLD_MS,
+            skip_angle: SKIP_ANGLE,
+            skip_min_ms: SKIP_MIN_MS,
+        }
+    }
+
+    /// Return the levels of the game.
+    pub fn levels(&self) -> &'static [Level] {
+        self.levels
+    }
+
+    /// Return the area of the screen the ball moves in.
+    pub fn play_area(&self) -> Rectangle {
+        self.play_area
+    }
+
+    /// Return the time in milliseconds the first page of the game over screen is shown,
+    /// before the splits are shown.
+    pub fn over_page_ms(&self) -> u32 {
+        self.over_page_ms
+    }
+
+    /// Return the time in milliseconds a calibration lasts.
+    pub fn calibrate_ms(&self) -> u32 {
+        self.calibrate_ms
+    }
+
+    /// Return the smallest x coordinate of the top left of the ball.
+    pub fn x_min(&self) -> i32 {
+        self.play_area.top_left.x
+    }
+
+    /// Return the largest x coordinate of the top left of the ball.
+    pub fn x_max(&self) -> i32 {
+        self.play_area.top_left.x + self.play_area.size.width as i32 - self.ball_size as i32
+    }
+
+    /// Return the smallest y coordinate of the top left of the ball.
+    pub fn y_min(&self) -> i32 {
+        self.play_area.top_left.y
+    }
+
+    /// Return the largest y coordinate of the top left of the ball.
+    pub fn y_max(&self) -> i32 {
+        self.play_area.top_left.y + self.play_area.size.height as i32 - self.ball_size as i32
+    }
+
+    /// Return the location in the play area of an object of the given size, such as a goal,
+    /// from its location in the level area. An object inside the level area stays inside
+    /// the play area.
+    /// # Arguments
+    /// * `location` - the location of the top left of the object in the level area
+    /// * `size` - the size of the object, which is not scaled
+    pub(crate) fn scale_location(&self, location: Point, size: u32) -> Point {
+        let from = self.level_area;
+        let to = self.play_area;
+        Point::new(
+            scale(
+                location.x,
+                from.top_left.x,
+                from.size.width.saturating_sub(size),
+                to.top_left.x,
+                to.size.width.saturating_sub(size),
+            ),
+            scale(
+                location.y,
+                from.top_left.y,
+                from.size.height.saturating_sub(size),
+                to.top_left.y,
+                to.size.height.saturating_sub(size),
+            ),
+        )
+    }
+
+    /// Return the given obstacle of a level scaled from the level area to the play area.
+    /// # Arguments
+    /// * `obstacle` - the obstacle in the level area
+    pub(crate) fn scale_obstacle(&self, obstacle: &Obstacle) -> Obstacle {
+        let from = self.level_area.size;
+        let to = self.play_area.size;
+        let size = obstacle.size();
+        Obstacle::new(
+            self.scale_location(obstacle.location(), 0),
+            Size::new(
+                (scale(size.width as i32, 0, from.width, 0, to.width) as u32).max(1),
+                (scale(size.height as i32, 0, from.height, 0, to.height) as u32).max(1),
+            ),
+        )
+    }
+
+    /// Return the given hole of a level scaled from the level area to the play area. The
+    /// hole keeps its size.
+    /// # Arguments
+    /// * `hazard` - the hole in the level area
+    pub(crate) fn scale_hazard(&self, hazard: &Hazard) -> Hazard {
+        Hazard::new(
+            self.scale_location(hazard.location(), hazard.size()),
+            hazard.size(),
+            hazard.effect(),
+        )
+    }
+
+    /// Return whether a goal at the given location in the play area overlaps one of the
+    /// given goals, the initial location of the ball or an obstacle or a hole of the given
+    /// level. The level is scaled to the play area first, as a level that is squeezed onto a
+    /// smaller display can overlap where it does not in the level area.
+    /// # Arguments
+    /// * `level` - the level the goal is placed in
+    /// * `goals` - the locations of the goals that are already placed in the play area
+    /// * `location` - the location of the goal in the play area
+    pub(crate) fn goal_overlaps(&self, level: &Level, goals: &[Point], location: Point) -> bool {
+        let size = self.goal_size;
+        let ball = self.scale_location(level.ball(), self.ball_size);
+        let on_ball = intersects(location, size, ball, self.ball_size);
+        let on_goal = goals
+            .iter()
+            .any(|goal| intersects(location, size, *goal, size));
+        let on_hazard = level.hazards().iter().any(|hazard| {
+            let hazard = self.scale_hazard(hazard);
+            intersects(location, size, hazard.location(), hazard.size())
+        });
+        let on_obstacle = level.obstacles().iter().any(|obstacle| {
+            let obstacle = self.scale_obstacle(obstacle);
+            intersects_rectangle(
+                location,
+                Size::new_equal(size),
+                obstacle.location(),
+                obstacle.size(),
+            )
+        });
+        on_ball || on_goal || on_hazard || on_obstacle
+    }
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        GameConfig::from_constants()
+    }
+}
+
+/// The reason a game configuration is rejected.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ConfigError {
+    /// There are no levels to play
+    NoLevels,
+    /// A level has no goals to visit
+    NoGoals { level: usize },
+    /// A level has more goals than the goal capacity of the configuration
+    TooManyGoals { level: usize },
+    /// A level has more than MAX_OBSTACLES obstacles
+    TooManyObstacles { level: usize },
+    /// A level has more than MAX_HAZARDS holes
+    TooManyHazards { level: usize },
+    /// A goal of a level is not entirely inside the level area
+    GoalOutOfBounds { level: usize, goal: usize },
+    /// A goal of a level overlaps an earlier goal, the initial ball location, an obstacle or
+    /// a hole of the level once the level is scaled to the play area
+    GoalOverlaps { level: usize, goal: usize },
+    /// The initial ball location of a level is not entirely inside the level area
+    BallOutOfBounds { level: usize },
+    /// The ball is larger than the play area
+    BallTooLarge,
+    /// A goal is larger than the play area
+    GoalTooLarge,
+    /// The physics step takes no time
+    ZeroPhysicsStep,
+    /// The acceleration of the ball is not a finite number of at least 0.0
+    InvalidAcceleration,
+    /// The friction is not between 0.0 and 1.0
+    InvalidFriction,
+    /// The wall restitution is not between 0.0 and 1.0
+    InvalidRestitution,
+    /// The maximum speed of the ball is not positive
+    InvalidMaxSpeed,
+    /// The stop speed of the ball is not a finite number of at least 0.0
+    InvalidStopSpeed,
+    /// The input shaping does not yield a finite response that grows with the tilt
+    InvalidInputShaping,
+    /// The first page of the game over screen lasts as long as the whole screen, so that
+    /// the splits are never shown
+    OverPageTooLong,
+    /// A calibration settles for as long as it lasts, so that no tilt is measured
+    CalibrationTooShort,
+    /// The angle of the calibration gesture is not smaller than the skip angle, so that the
+    /// gesture cannot be held without skipping the intro
+    CalibrateAngleTooLarge,
+}
+
+/// Builds a GameConfig with the goal capacity `N` and the event capacity `E`, starting from
+/// the default configuration.
+#[derive(Clone, Copy, Debug)]
+pub struct GameConfigBuilder<const N: usize = MAX_GOALS, const E: usize = MAX_EVENTS> {
+    /// the configuration that is built
+    config: GameConfig<N, E>,
+}
+
+impl<const N: usize, const E: usize> GameConfigBuilder<N, E> {
+    /// Return a new builder that starts from the default configuration.
+    pub fn new() -> Self {
+        GameConfigBuilder {
+            config: GameConfig::from_constants(),
+        }
+    }
+
+    /// Return this builder with the given levels.
+    /// # Arguments
+    /// * `levels` - the levels of the game, played in order
+    pub fn with_levels(mut self, levels: &'static [Level]) -> Self {
+        self.config.levels = levels;
+        self
+    }
+
+    /// Return this builder with the given level area.
+    /// # Arguments
+    /// * `level_area` - the area of the screen the levels are designed for
+    pub fn with_level_area(mut self, level_area: Rectangle) -> Self {
+        self.config.level_area = level_area;
+        self
+    }
+
+    /// Return this builder with the given play area.
+    /// # Arguments
+    /// * `play_area` - the area of the screen the ball moves in
+    pub fn with_play_area(mut self, play_area: Rectangle) -> Self {
+        self.config.play_area = play_area;
+        self
+    }
+
+    /// Return this builder with the play area and the screen outline of the given layout.
+    /// # Arguments
+    /// * `layout` - the layout of the game on the display
+    pub fn with_layout(self, layout: Layout) -> Self {
+        self.with_play_area(layout.play_area())
+            .with_screen_outline(layout.screen_outline())
+    }
+
+    /// Return this builder with the given screen outline.
+    /// # Arguments
+    /// * `screen_outline` - the outline of the game space drawn during game play
+    pub fn with_screen_outline(mut self, screen_outline: Rectangle) -> Self {
+        self.config.screen_outline = screen_outline;
+        self
+    }
+
+    /// Return this builder with the given ball size.
+    /// # Arguments
+    /// * `ball_size` - the size of the ball
+    pub fn with_ball_size(mut self, ball_size: u32) -> Self {
+        self.config.ball_size = ball_size;
+        self
+    }
+
+    /// Return this builder with the given goal size.
+    /// # Arguments
+    /// * `goal_size` - the size of each goal
+    pub fn with_goal_size(mut self, goal_size: u32) -> Self {
+        self.config.goal_size = goal_size;
+        self
+    }
+
+    /// Return this builder with the given angle threshold.
+    /// # Arguments
+    /// * `angle_threshold` - the pitch/roll angle threshold, below which the tilt is ignored
+    pub fn with_angle_threshold(mut self, angle_threshold: f32) -> Self {
+        self.config.input_shaping = self.config.input_shaping.with_deadzone(angle_threshold);
+        self
+    }
+
+    /// Return this builder with the given input shaping.
+    /// # Arguments
+    /// * `input_shaping` - the shaping of the pitch/roll angles before they accelerate the
+    ///   ball, such as the shaping of an InputPreset
+    pub fn with_input_shaping(mut self, input_shaping: InputShaping) -> Self {
+        self.config.input_shaping = input_shaping;
+        self
+    }
+
+    /// Return this builder with the given physics step.
+    /// # Arguments
+    /// * `physics_step_ms` - the duration of each step of the ball physics in milliseconds
+    pub fn with_physics_step_ms(mut self, physics_step_ms: u32) -> Self {
+        self.config.physics_step_ms = physics_step_ms;
+        self
+    }
+
+    /// Return this builder with the given ball acceleration.
+    /// # Arguments
+    /// * `ball_acceleration` - the acceleration of the ball each physics step per radian of
+    ///   tilt above the angle threshold
+    pub fn with_ball_acceleration(mut self, ball_acceleration: f32) -> Self {
+        self.config.ball_acceleration = ball_acceleration;
+        self
+    }
+
+    /// Return this builder with the given ball friction.
+    /// # Arguments
+    /// * `ball_friction` - the fraction of the velocity of the ball that is lost to friction
+    ///   each physics step
+    pub fn with_ball_friction(mut self, ball_friction: f32) -> Self {
+        self.config.ball_friction = ball_friction;
+        self
+    }
+
+    /// Return this builder with the given maximum ball speed.
+    /// # Arguments
+    /// * `ball_max_speed` - the maximum speed of the ball in pixels per physics step
+    pub fn with_ball_max_speed(mut self, ball_max_speed: f32) -> Self {
+        self.config.ball_max_speed = ball_max_speed;
+        self
+    }
+
+    /// Return this builder with the given ball stop speed.
+    /// # Arguments
+    /// * `ball_stop_speed` - the speed in pixels per physics step below which a coasting
+    ///   ball comes to rest
+    pub fn with_ball_stop_speed(mut self, ball_stop_speed: f32) -> Self {
+        self.config.ball_stop_speed = ball_stop_speed;
+        self
+    }
+
+    /// Return this builder with the given wall restitution.
+    /// # Arguments
+    /// * `wall_restitution` - the fraction of the speed of the ball that is kept when it
+    ///   bounces off a wall
+    pub fn with_wall_restitution(mut self, wall_restitution: f32) -> Self {
+        self.config.wall_restitution = wall_restitution;
+        self
+    }
+
+    /// Return this builder with the given screen times.
+    /// # Arguments
+    /// * `intro_ms` - the time in milliseconds the intro screen is shown
+    /// * `next_level_ms` - the time in milliseconds the next level screen is shown
+    /// * `over_ms` - the time in milliseconds the game over screen is shown
+    pub fn with_screen_times(mut self, intro_ms: u32, next_level_ms: u32, over_ms: u32) -> Self {
+        self.config.intro_ms = intro_ms;
+        self.config.next_level_ms = next_level_ms;
+        self.config.over_ms = over_ms;
+        self
+    }
+
+    /// Return this builder with the given time of the first page of the game over screen.
+    /// # Arguments
+    /// * `over_page_ms` - the time in milliseconds the score and the low score are shown on
+    ///   the game over screen before the splits, shorter than the game over screen
+    pub fn with_over_page_ms(mut self, over_page_ms: u32) -> Self {
+        self.config.over_page_ms = over_page_ms;
+        self
+    }
+
+    /// Return this builder with the given high score screen times.
+    /// # Arguments
+    /// * `high_scores_ms` - the time in milliseconds the high score table is shown
+    /// * `initials_ms` - the time in milliseconds the initials of a new high score can be
+    ///   entered
+    pub fn with_high_score_times(mut self, high_scores_ms: u32, initials_ms: u32) -> Self {
+        self.config.high_scores_ms = high_scores_ms;
+        self.config.initials_ms = initials_ms;
+        self
+    }
+
+    /// Return this builder with the given way to enter initials.
+    /// # Arguments
+    /// * `initials_angle` - the tilt angle that moves on or confirms the letter of the
+    ///   initials that is chosen
+    /// * `initials_repeat_ms` - the time in milliseconds a roll is held before the letter
+    ///   moves on again
+    pub fn with_initials_input(mut self, initials_angle: f32, initials_repeat_ms: u32) -> Self {
+        self.config.initials_angle = initials_angle;
+        self.config.initials_repeat_ms = initials_repeat_ms;
+        self
+    }
+
+    /// Return this builder with the given calibration times.
+    /// # Arguments
+    /// * `calibrate_ms` - the time in milliseconds a calibration lasts
+    /// * `calibrate_settle_ms` - the time in milliseconds at the start of a calibration
+    ///   before the tilt is measured
+    pub fn with_calibration_times(mut self, calibrate_ms: u32, calibrate_settle_ms: u32) -> Self {
+        self.config.calibrate_ms = calibrate_ms;
+        self.config.calibrate_settle_ms = calibrate_settle_ms;
+        self
+    }
+
+    /// Return this builder with the given gesture to start a calibration.
+    /// # Arguments
+    /// * `calibrate_angle` - the tilt angle that starts a calibration when it is held
+    ///   during the intro
+    /// * `calibrate_hold_ms` - the time in milliseconds the angle is held
+    pub fn with_calibration_gesture(
+        mut self,
+        calibrate_angle: f32,
+        calibrate_hold_ms: u32,
+    ) -> Self {
+        self.config.calibrate_angle = calibrate_angle;
+        self.config.calibrate_hold_ms = calibrate_hold_ms;
+        self
+    }
+
+    /// Return this builder with the given way to skip the screens.
+    /// # Arguments
+    /// * `skip_angle` - the tilt angle that skips the intro, next level and game over screens
+    /// * `skip_min_ms` - the time in milliseconds a screen is shown before it can be skipped
+    pub fn with_skip(mut self, skip_angle: f32, skip_min_ms: u32) -> Self {
+        self.config.skip_angle = skip_angle;
+        self.config.skip_min_ms = skip_min_ms;
+        self
+    }
+
+    /// Return the configuration if the game can be played with it, otherwise return the
+    /// first reason it cannot.
+    pub fn build(self) -> Result<GameConfig<N, E>, ConfigError> {
+        let config = self.config;
+        let area = config.play_area;
+        let level_area = config.level_area;
+        if config.physics_step_ms == 0 {
+            return Err(ConfigError::ZeroPhysicsStep);
+        }
+        if !config.ball_acceleration.is_finite() || config.ball_acceleration < 0.0 {
+            return Err(ConfigError::InvalidAcceleration);
+        }
+        if !(0.0..=1.0).contains(&config.ball_friction) {
+            return Err(ConfigError::InvalidFriction);
+        }
+        if !(0.0..=1.0).contains(&config.wall_restitution) {
+            return Err(ConfigError::InvalidRestitution);
+        }
+        if config.ball_max_speed.is_nan() || config.ball_max_speed <= 0.0 {
+            return Err(ConfigError::InvalidMaxSpeed);
+        }
+        if !config.ball_stop_speed.is_finite() || config.ball_stop_speed < 0.0 {
+            return Err(ConfigError::InvalidStopSpeed);
+        }
+        if !config.input_shaping.is_valid() {
+            return Err(ConfigError::InvalidInputShaping);
+        }
+        if config.over_page_ms >= config.over_ms {
+            return Err(ConfigError::OverPageTooLong);
+        }
+        if config.calibrate_settle_ms >= config.calibrate_ms {
+            return Err(ConfigError::CalibrationTooShort);
+        }
+        let (calibrate_angle, skip_angle) = (config.calibrate_angle, config.skip_angle);
+        if calibrate_angle.is_nan() || skip_angle.is_nan() || calibrate_angle >= skip_angle {
+            return Err(ConfigError::CalibrateAngleTooLarge);
+        }
+        if config.ball_size > area.size.width || config.ball_size > area.size.height {
+            return Err(ConfigError::BallTooLarge);
+        }
+        if config.goal_size > area.size.width || config.goal_size > area.size.height {
+            return Err(ConfigError::GoalTooLarge);
+        }
+        if config.levels.is_empty() {
+            return Err(ConfigError::NoLevels);
+        }
+        let ball_size = Size::new_equal(config.ball_size);
+        let goal_size = Size::new_equal(config.goal_size);
+        for (level, definition) in config.levels.iter().enumerate() {
+            if definition.goals().is_empty() {
+                return Err(ConfigError::NoGoals { level });
+            }
+            if definition.goals().len() > N {
+                return Err(ConfigError::TooManyGoals { level });
+            }
+            if definition.obstacles().len() > MAX_OBSTACLES {
+                return Err(ConfigError::TooManyObstacles { level });
+            }
+            if definition.hazards().len() > MAX_HAZARDS {
+                return Err(ConfigError::TooManyHazards { level });
+            }
+            for (goal, location) in definition.goals().iter().enumerate() {
+                if !contains_rectangle(level_area.top_left, level_area.size, *location, goal_size) {
+                    return Err(ConfigError::GoalOutOfBounds { level, goal });
+                }
+            }
+            let ball = definition.ball();
+            if !contains_rectangle(level_area.top_left, level_area.size, ball, ball_size) {
+                return Err(ConfigError::BallOutOfBounds { level });
+            }
+            let mut goals: Vec<Point, N> = Vec::new();
+            for (goal, location) in definition.goals().iter().enumerate() {
+                let location = config.scale_location(*location, config.goal_size);
+                if config.goal_overlaps(definition, &goals, location) {
+                    return Err(ConfigError::GoalOverlaps { level, goal });
+                }
+                // there is room for every goal, the number of goals has been checked
+                goals.push(location).unwrap();
+            }
+        }
+        Ok(config)
+    }
+}
+
+impl<const N: usize, const E: usize> Default for GameConfigBuilder<N, E> {
+    fn default() -> Self {
+        GameConfigBuilder::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        max_events, ConfigError, GameConfig, GameConfigBuilder, BALL_SIZE, CALIBRATE_HOLD_MS,
+        CALIBRATE_SETTLE_MS, INTRO_MS, LEVELS, NEXT_LEVEL_MS, OVER_PAGE_MS, SKIP_ANGLE,
+        SKIP_MIN_MS, X_MAX, X_MIN, Y_MAX, Y_MIN,
+    };
+    use crate::{
+        layout::Layout,
+        level::Level,
+        math::contains_rectangle,
+        shaping::{InputShaping, ResponseCurve},
+        smallball::{Hazard, HazardEffect, Obstacle},
+    };
+    use embedded_graphics::{
+        prelude::{Point, Size},
+        primitives::Rectangle,
+    };
+
+    #[test]
+    fn default_config_test() {
+        // GIVEN the default configuration
+        let config = GameConfig::builder().build().unwrap();
+
+        // THEN it is made of the constants
+        assert_eq!(config.levels().len(), LEVELS.len());
+        assert_eq!(config.x_min(), X_MIN);
+        assert_eq!(config.x_max(), X_MAX);
+        assert_eq!(config.y_min(), Y_MIN);
+        assert_eq!(config.y_max(), Y_MAX);
+        assert_eq!(config.ball_size, BALL_SIZE);
+    }
+
+    #[test]
+    fn config_builder_test() {
+        // GIVEN a level that fits a small play area
+        const GOALS: [Point; 1] = [Point::new(100, 50)];
+        const LEVEL: [Level; 1] = [Level::new(&GOALS, Point::new(10, 10), None)];
+
+        // WHEN a configuration is built with it
+        let config = GameConfig::builder()
+            .with_levels(&LEVEL)
+            .with_play_area(Rectangle::new(Point::new(5, 5), Size::new(30, 20)))
+            .with_ball_size(4)
+            .with_goal_size(4)
+            .with_ball_max_speed(3.0)
+            .build()
+            .unwrap();
+
+        // THEN the configuration has the given values
+        assert_eq!(config.levels().len(), 1);
+        assert_eq!(config.x_min(), 5);
+        assert_eq!(config.x_max(), 31);
+        assert_eq!(config.y_min(), 5);
+        assert_eq!(config.y_max(), 21);
+        assert_eq!(config.ball_max_speed, 3.0);
+    }
+
+    #[test]
+    fn config_with_layout_test() {
+        for size in [Size::new(128, 32), Size::new(128, 64), Size::new(128, 128)] {
+            // GIVEN the default levels on a display of the given size
+            let layout = Layout::new(size);
+            let config = GameConfig::builder().with_layout(layout).build().unwrap();
+            let area = layout.play_area();
+            let inside =
+                |location, size| contains_rectangle(area.top_left, area.size, location, size);
+
+            // THEN every part of every level is scaled into the play area
+            let goal_size = Size::new_equal(config.goal_size);
+            let ball_size = Size::new_equal(config.ball_size);
+            for level in config.levels() {
+                for goal in level.goals() {
+                    assert!(inside(
+                        config.scale_location(*goal, config.goal_size),
+                        goal_size
+                    ));
+                }
+                let ball = config.scale_location(level.ball(), config.ball_size);
+                assert!(inside(ball, ball_size));
+                for obstacle in level.obstacles() {
+                    let obstacle = config.scale_obstacle(obstacle);
+                    assert!(inside(obstacle.location(), obstacle.size()));
+                }
+                for hazard in level.hazards() {
+                    let hazard = config.scale_hazard(hazard);
+                    assert!(inside(hazard.location(), Size::new_equal(hazard.size())));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn goal_capacity_test() {
+        // GIVEN a level with more goals than the default goal capacity
+        const GOALS: [Point; 6] = [
+            Point::new(10, 12),
+            Point::new(30, 12),
+            Point::new(50, 12),
+            Point::new(70, 12),
+            Point::new(90, 12),
+            Point::new(110, 12),
+        ];
+        const LEVEL: [Level; 1] = [Level::new(&GOALS, Point::new(60, 40), None)];
+
+        // THEN it is rejected by a configuration with the default goal capacity
+        assert_eq!(
+            GameConfig::builder()
+                .with_levels(&LEVEL)
+                .build()
+                .unwrap_err(),
+            ConfigError::TooManyGoals { level: 0 }
+        );
+
+        // THEN it is accepted by a configuration with a larger goal capacity
+        let config = GameConfigBuilder::<8, { max_events(8) }>::new()
+            .with_levels(&LEVEL)
+            .build()
+            .unwrap();
+        assert_eq!(config.levels()[0].goals().len(), 6);
+
+        // THEN the default levels are rejected by a configuration with a smaller capacity
+        assert_eq!(
+            GameConfigBuilder::<2>::new().build().unwrap_err(),
+            ConfigError::TooManyGoals { level: 0 }
+        );
+    }
+
+    #[test]
+    fn config_validation_test() {
+        const GOALS: [Point; 1] = [Point::new(0, 0)];
+        const OUTSIDE: [Level; 2] = [
+            Level::new(&[Point::new(10, 12)], Point::new(88, 20), None),
+            Level::new(&GOALS, Point::new(88, 20), None),
+        ];
+        const BALL_OUTSIDE: [Level; 1] =
+            [Level::new(&[Point::new(10, 12)], Point::new(120, 20), None)];
+        const NO_GOALS: [Level; 1] = [Level::new(&[], Point::new(88, 20), None)];
+        const TOO_MANY_GOALS: [Level; 1] = [Level::new(
+            &[Point::new(10, 12); 5],
+            Point::new(88, 20),
+            None,
+        )];
+
+        const OBSTACLE: Obstacle = Obstacle::new(Point::new(30, 30), Size::new(3, 3));
+        const TOO_MANY_OBSTACLES: [Level; 1] =
+            [Level::new(&[Point::new(10, 12)], Point::new(88, 20), None)
+                .with_obstacles(&[OBSTACLE; 5])];
+        const HAZARD: Hazard = Hazard::new(Point::new(30, 30), 8, HazardEffect::Reset);
+        const ON_BALL: [Level; 1] = [Level::new(&[Point::new(84, 24)], Point::new(88, 20), None)];
+        const ON_HAZARD: [Level; 1] = [Level::new(
+            &[Point::new(10, 12), Point::new(34, 34)],
+            Point::new(88, 20),
+            None,
+        )
+        .with_hazards(&[HAZARD])];
+        const ON_OBSTACLE: [Level; 1] =
+            [Level::new(&[Point::new(28, 28)], Point::new(88, 20), None)
+                .with_obstacles(&[OBSTACLE])];
+        const ON_GOAL: [Level; 1] = [Level::new(
+            &[Point::new(10, 12), Point::new(14, 16)],
+            Point::new(88, 20),
+            None,
+        )];
+        const SQUEEZED: [Level; 1] = [Level::new(&[Point::new(60, 50)], Point::new(55, 30), None)];
+        const TOO_MANY_HAZARDS: [Level; 1] =
+            [Level::new(&[Point::new(10, 12)], Point::new(88, 20), None)
+                .with_hazards(&[HAZARD; 5])];
+
+        let builder = GameConfig::builder();
+        assert_eq!(
+            builder
+                .with_levels(&TOO_MANY_OBSTACLES)
+                .build()
+                .unwrap_err(),
+            ConfigError::TooManyObstacles { level: 0 }
+        );
+        assert_eq!(
+            builder.with_levels(&TOO_MANY_HAZARDS).build().unwrap_err(),
+            ConfigError::TooManyHazards { level: 0 }
+        );
+        assert_eq!(
+            builder.with_levels(&OUTSIDE).build().unwrap_err(),
+            ConfigError::GoalOutOfBounds { level: 1, goal: 0 }
+        );
+        assert_eq!(
+            builder.with_levels(&BALL_OUTSIDE).build().unwrap_err(),
+            ConfigError::BallOutOfBounds { level: 0 }
+        );
+        for (levels, goal) in [
+            (&ON_BALL, 0),
+            (&ON_HAZARD, 1),
+            (&ON_OBSTACLE, 0),
+            (&ON_GOAL, 1),
+        ] {
+            assert_eq!(
+                builder.with_levels(levels).build().unwrap_err(),
+                ConfigError::GoalOverlaps { level: 0, goal }
+            );
+        }
+        // a level that only overlaps once it is squeezed onto a short display
+        let short = Layout::new(Size::new(128, 32));
+        assert!(builder.with_levels(&SQUEEZED).build().is_ok());
+        assert_eq!(
+            builder
+                .with_levels(&SQUEEZED)
+                .with_layout(short)
+                .build()
+                .unwrap_err(),
+            ConfigError::GoalOverlaps { level: 0, goal: 0 }
+        );
+        assert_eq!(
+            builder.with_levels(&NO_GOALS).build().unwrap_err(),
+            ConfigError::NoGoals { level: 0 }
+        );
+        assert_eq!(
+            builder.with_levels(&TOO_MANY_GOALS).build().unwrap_err(),
+            ConfigError::TooManyGoals { level: 0 }
+        );
+        assert_eq!(
+            builder.with_levels(&[]).build().unwrap_err(),
+            ConfigError::NoLevels
+        );
+        assert_eq!(
+            builder.with_ball_size(60).build().unwrap_err(),
+            ConfigError::BallTooLarge
+        );
+        assert_eq!(
+            builder.with_goal_size(60).build().unwrap_err(),
+            ConfigError::GoalTooLarge
+        );
+        assert_eq!(
+            builder.with_physics_step_ms(0).build().unwrap_err(),
+            ConfigError::ZeroPhysicsStep
+        );
+        assert_eq!(
+            builder.with_ball_acceleration(-0.1).build().unwrap_err(),
+            ConfigError::InvalidAcceleration
+        );
+        assert_eq!(
+            builder
+                .with_ball_acceleration(f32::NAN)
+                .build()
+                .unwrap_err(),
+            ConfigError::InvalidAcceleration
+        );
+        assert_eq!(
+            builder.with_ball_friction(1.5).build().unwrap_err(),
+            ConfigError::InvalidFriction
+        );
+        assert_eq!(
+            builder.with_wall_restitution(-0.1).build().unwrap_err(),
+            ConfigError::InvalidRestitution
+        );
+        assert_eq!(
+            builder.with_ball_max_speed(0.0).build().unwrap_err(),
+            ConfigError::InvalidMaxSpeed
+        );
+        assert_eq!(
+            builder.with_ball_stop_speed(-0.01).build().unwrap_err(),
+            ConfigError::InvalidStopSpeed
+        );
+        assert_eq!(
+            builder
+                .with_ball_stop_speed(f32::INFINITY)
+                .build()
+                .unwrap_err(),
+            ConfigError::InvalidStopSpeed
+        );
+        assert!(builder
+            .with_ball_acceleration(0.0)
+            .with_ball_stop_speed(0.0)
+            .build()
+            .is_ok());
+        assert_eq!(
+            builder.with_angle_threshold(-0.1).build().unwrap_err(),
+            ConfigError::InvalidInputShaping
+        );
+        assert_eq!(
+            builder
+                .with_input_shaping(InputShaping::new(0.1, 0.8, ResponseCurve::Exponential(0.0)))
+                .build()
+                .unwrap_err(),
+            ConfigError::InvalidInputShaping
+        );
+        assert_eq!(
+            builder
+                .with_screen_times(INTRO_MS, NEXT_LEVEL_MS, OVER_PAGE_MS)
+                .build()
+                .unwrap_err(),
+            ConfigError::OverPageTooLong
+        );
+        assert!(builder
+            .with_screen_times(INTRO_MS, NEXT_LEVEL_MS, OVER_PAGE_MS)
+            .with_over_page_ms(OVER_PAGE_MS / 2)
+            .build()
+            .is_ok());
+        assert_eq!(
+            builder
+                .with_calibration_times(CALIBRATE_SETTLE_MS, CALIBRATE_SETTLE_MS)
+                .build()
+                .unwrap_err(),
+            ConfigError::CalibrationTooShort
+        );
+        assert_eq!(
+            builder
+                .with_calibration_gesture(SKIP_ANGLE, CALIBRATE_HOLD_MS)
+                .build()
+                .unwrap_err(),
+            ConfigError::CalibrateAngleTooLarge
+        );
+        assert_eq!(
+            builder
+                .with_skip(f32::NAN, SKIP_MIN_MS)
+                .build()
+                .unwrap_err(),
+            ConfigError::CalibrateAngleTooLarge
+        );
+    }
+}
@@ -0,0 +1,350 @@
+//!
+//! This file fuses the readings of the accelerometer and the gyroscope of an mpu sensor into
+//! its tilt. The angles of the accelerometer are right on average, but noisy and thrown off
+//! whenever the sensor is shaken, while the rates of the gyroscope are smooth but drift when
+//! they are integrated. The complementary filter follows the gyroscope over short times and
+//! the accelerometer over long times. The Mahony filter tracks the orientation of the sensor
+//! as a quaternion, corrected towards the accelerometer by a PI controller, which also
+//! cancels the bias of the gyroscope. The roll is taken about the x axis after the pitch
+//! about the y axis, which matches the accelerometer angles of the MPU6050 driver when the
+//! sensor is tilted along one axis, so either filter can stand in for them.
+//!
+
+use crate::smallball::Tilt;
+use libm::{asinf, atan2f, sqrtf};
+
+/// A reading of an mpu sensor, in the axes of the sensor.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ImuSample {
+    /// the acceleration along the x, y and z axes, in any unit, only its direction is used
+    pub acc: [f32; 3],
+    /// the rate of rotation about the x, y and z axes in radians per second
+    pub gyro: [f32; 3],
+}
+
+impl ImuSample {
+    /// Return a new reading.
+    /// # Arguments
+    /// * `acc` - the acceleration along the x, y and z axes
+    /// * `gyro` - the rate of rotation about the x, y and z axes in radians per second
+    pub fn new(acc: [f32; 3], gyro: [f32; 3]) -> Self {
+        ImuSample { acc, gyro }
+    }
+
+    /// Return the tilt given by the accelerometer alone, the direction of gravity, or None
+    /// if the sensor is in free fall and there is no direction.
+    pub fn acc_tilt(&self) -> Option<Tilt> {
+        let [x, y, z] = self.acc;
+        if x == 0.0 && y == 0.0 && z == 0.0 {
+            return None;
+        }
+        let roll = atan2f(y, z);
+        let pitch = atan2f(-x, sqrtf(y * y + z * z));
+        Some(Tilt::new(pitch, roll))
+    }
+}
+
+/// A filter that estimates the tilt of an mpu sensor from its readings over time.
+pub trait TiltFilter {
+    /// Add a reading to the filter and return the estimated tilt.
+    /// # Arguments
+    /// * `sample` - the reading of the sensor
+    /// * `elapsed_ms` - the time in milliseconds since the previous reading
+    fn update(&mut self, sample: &ImuSample, elapsed_ms: u32) -> Tilt;
+}
+
+/// A complementary filter, which integrates the rates of the gyroscope and pulls the result
+/// towards the angles of the accelerometer with the given time constant.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ComplementaryFilter {
+    /// the time constant in milliseconds of the pull towards the accelerometer
+    time_constant_ms: u32,
+    /// the estimated tilt, None until the first reading
+    tilt: Option<Tilt>,
+}
+
+impl ComplementaryFilter {
+    /// Return a new filter, which starts at the tilt of the accelerometer.
+    /// # Arguments
+    /// * `time_constant_ms` - the time constant in milliseconds, longer trusts the gyroscope
+    ///   more, zero only uses the accelerometer
+    pub fn new(time_constant_ms: u32) -> Self {
+        ComplementaryFilter {
+            time_constant_ms,
+            tilt: None,
+        }
+    }
+}
+
+impl TiltFilter for ComplementaryFilter {
+    fn update(&mut self, sample: &ImuSample, elapsed_ms: u32) -> Tilt {
+        let acc = sample.acc_tilt();
+        let tilt = match (self.tilt, acc) {
+            (None, acc) => acc.unwrap_or_default(),
+            (Some(tilt), acc) => {
+                let dt = elapsed_ms as f32 / 1000.0;
+                let gyro = Tilt::new(
+                    tilt.pitch + sample.gyro[1] * dt,
+                    tilt.roll + sample.gyro[0] * dt,
+                );
+                match acc {
+                    Some(acc) => {
+                        let tau = self.time_constant_ms as f32;
+                        let alpha = if tau > 0.0 {
+                            tau / (tau + elapsed_ms as f32)
+                        } else {
+                            0.0
+                        };
+                        Tilt::new(
+                            alpha * gyro.pitch + (1.0 - alpha) * acc.pitch,
+                            alpha * gyro.roll + (1.0 - alpha) * acc.roll,
+                        )
+                    }
+                    None => gyro,
+                }
+            }
+        };
+        self.tilt = Some(tilt);
+        tilt
+    }
+}
+
+/// A Mahony filter, which integrates the rates of the gyroscope into the orientation of the
+/// sensor and corrects them with the error between the gravity the orientation predicts and
+/// the gravity the accelerometer measures.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MahonyFilter {
+    /// the proportional gain of the correction
+    kp: f32,
+    /// the integral gain of the correction
+    ki: f32,
+    /// the orientation of the sensor as a unit quaternion (w, x, y, z), None until the
+    /// first reading
+    q: Option<[f32; 4]>,
+    /// the integral of the error, the estimated bias of the gyroscope
+    integral: [f32; 3],
+}
+
+impl MahonyFilter {
+    /// Return a new filter, which starts at the tilt of the accelerometer.
+    /// # Arguments
+    /// * `kp` - the proportional gain, higher trusts the accelerometer more
+    /// * `ki` - the integral gain, zero does not correct the bias of the gyroscope
+    pub fn new(kp: f32, ki: f32) -> Self {
+        MahonyFilter {
+            kp,
+            ki,
+            q: None,
+            integral: [0.0; 3],
+        }
+    }
+
+    /// Return the quaternion of the given tilt without heading.
+    /// # Arguments
+    /// * `tilt` - the tilt
+    fn from_tilt(tilt: Tilt) -> [f32; 4] {
+        let (sr, cr) = (libm::sinf(tilt.roll / 2.0), libm::cosf(tilt.roll / 2.0));
+        let (sp, cp) = (libm::sinf(tilt.pitch / 2.0), libm::cosf(tilt.pitch / 2.0));
+        [cr * cp, sr * cp, cr * sp, -sr * sp]
+    }
+
+    /// Return the tilt of the given quaternion.
+    /// # Arguments
+    /// * `q` - the quaternion
+    fn to_tilt(q: [f32; 4]) -> Tilt {
+        let [w, x, y, z] = q;
+        let roll = atan2f(2.0 * (w * x + y * z), 1.0 - 2.0 * (x * x + y * y));
+        let pitch = asinf((2.0 * (w * y - z * x)).clamp(-1.0, 1.0));
+        Tilt::new(pitch, roll)
+    }
+}
+
+impl TiltFilter for MahonyFilter {
+    fn update(&mut self, sample: &ImuSample, elapsed_ms: u32) -> Tilt {
+        let Some([mut w, mut x, mut y, mut z]) = self.q else {
+            let q = Self::from_tilt(sample.acc_tilt().unwrap_or_default());
+            self.q = Some(q);
+            return Self::to_tilt(q);
+        };
+        let dt = elapsed_ms as f32 / 1000.0;
+        let [mut gx, mut gy, mut gz] = sample.gyro;
+
+        // correct the rates with the error between the measured and the predicted gravity,
+        // unless the sensor is in free fall
+        let [ax, ay, az] = sample.acc;
+        let norm = sqrtf(ax * ax + ay * ay + az * az);
+        if norm > 0.0 {
+            let (ax, ay, az) = (ax / norm, ay / norm, az / norm);
+            let vx = 2.0 * (x * z - w * y);
+            let vy = 2.0 * (w * x + y * z);
+            let vz = w * w - x * x - y * y + z * z;
+            let error = [ay * vz - az * vy, az * vx - ax * vz, ax * vy - ay * vx];
+            for (integral, error) in self.integral.iter_mut().zip(error) {
+                *integral += self.ki * error * dt;
+            }
+            gx += self.kp * error[0] + self.integral[0];
+            gy += self.kp * error[1] + self.integral[1];
+            gz += self.kp * error[2] + self.integral[2];
+        }
+
+        // integrate the rate of change of the quaternion and normalise it again
+        let (hx, hy, hz) = (gx * dt / 2.0, gy * dt / 2.0, gz * dt / 2.0);
+        (w, x, y, z) = (
+            w - x * hx - y * hy - z * hz,
+            x + w * hx + y * hz - z * hy,
+            y + w * hy - x * hz + z * hx,
+            z + w * hz + x * hy - y * hx,
+        );
+        let norm = sqrtf(w * w + x * x + y * y + z * z);
+        let q = [w / norm, x / norm, y / norm, z / norm];
+        self.q = Some(q);
+        Self::to_tilt(q)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ComplementaryFilter, ImuSample, MahonyFilter, TiltFilter};
+    use crate::{
+        config::{FUSION_TIME_CONSTANT_MS, MAHONY_KI, MAHONY_KP},
+        rng::Rng,
+        smallball::Tilt,
+    };
+    use libm::{cosf, sinf};
+
+    // the time in milliseconds between the readings of the traces
+    const STEP_MS: u32 = 10;
+
+    /// Return the reading of a sensor at rest at the given tilt and turning at the given rates
+    /// of pitch and roll, with the given acceleration on top of gravity.
+    /// # Arguments
+    /// * `tilt` - the tilt of the sensor
+    /// * `rates` - the rates of change of the pitch and the roll in radians per second
+    /// * `shake` - the acceleration of the sensor along its x, y and z axes
+    fn sample(tilt: Tilt, rates: Tilt, shake: [f32; 3]) -> ImuSample {
+        let (pitch, roll) = (tilt.pitch, tilt.roll);
+        let acc = [
+            -sinf(pitch) + shake[0],
+            sinf(roll) * cosf(pitch) + shake[1],
+            cosf(roll) * cosf(pitch) + shake[2],
+        ];
+        let gyro = [
+            rates.roll,
+            rates.pitch * cosf(roll),
+            -rates.pitch * sinf(roll),
+        ];
+        ImuSample::new(acc, gyro)
+    }
+
+    /// Return the largest difference between the pitch or roll of two tilts.
+    fn error(tilt: Tilt, expected: Tilt) -> f32 {
+        (tilt.pitch - expected.pitch)
+            .abs()
+            .max((tilt.roll - expected.roll).abs())
+    }
+
+    #[test]
+    fn acc_tilt_test() {
+        let tilt = Tilt::new(0.3, -0.5);
+        let acc = sample(tilt, Tilt::default(), [0.0; 3]).acc_tilt().unwrap();
+        assert!(error(acc, tilt) < 1e-5);
+        assert_eq!(ImuSample::default().acc_tilt(), None);
+    }
+
+    #[test]
+    fn filters_follow_turning_sensor_test() {
+        // GIVEN a sensor that turns steadily from level in pitch and then in roll
+        let rate = 0.5;
+        let mut filters = (
+            ComplementaryFilter::new(FUSION_TIME_CONSTANT_MS),
+            MahonyFilter::new(MAHONY_KP, MAHONY_KI),
+        );
+
+        // WHEN the readings are filtered
+        // THEN both filters track the tilt of the sensor closely
+        for step in 0..200 {
+            let t = (step * STEP_MS) as f32 / 1000.0;
+            let (tilt, rates) = if t < 1.0 {
+                (Tilt::new(rate * t, 0.0), Tilt::new(rate, 0.0))
+            } else {
+                (Tilt::new(rate, rate * (t - 1.0)), Tilt::new(0.0, rate))
+            };
+            let sample = sample(tilt, rates, [0.0; 3]);
+            assert!(error(filters.0.update(&sample, STEP_MS), tilt) < 0.01);
+            assert!(error(filters.1.update(&sample, STEP_MS), tilt) < 0.01);
+        }
+    }
+
+    #[test]
+    fn filters_smooth_noise_and_shaking_test() {
+        // GIVEN a sensor at rest whose accelerometer is noisy and which is shaken for a while
+        let tilt = Tilt::new(0.2, -0.1);
+        let mut rng = Rng::new(42);
+        let mut filters = (
+            ComplementaryFilter::new(FUSION_TIME_CONSTANT_MS),
+            MahonyFilter::new(MAHONY_KP, MAHONY_KI),
+        );
+        let mut worst = (0.0f32, 0.0f32, 0.0f32);
+
+        // WHEN the readings are filtered
+        for step in 0..300 {
+            let mut noise = || rng.range(-100, 100) as f32 / 1000.0;
+            let mut shake = [noise(), noise(), noise()];
+            if (100..110).contains(&step) {
+                shake[0] += 0.5;
+            }
+            let sample = sample(tilt, Tilt::default(), shake);
+            let acc = sample.acc_tilt().unwrap();
+            let complementary = filters.0.update(&sample, STEP_MS);
+            let mahony = filters.1.update(&sample, STEP_MS);
+            if step >= 50 {
+                worst.0 = worst.0.max(error(acc, tilt));
+                worst.1 = worst.1.max(error(complementary, tilt));
+                worst.2 = worst.2.max(error(mahony, tilt));
+            }
+        }
+
+        // THEN the filtered tilt stays much closer to the tilt than the accelerometer
+        assert!(worst.0 > 0.5);
+        assert!(worst.1 < 0.15);
+        assert!(worst.2 < 0.1);
+    }
+
+    #[test]
+    fn mahony_cancels_gyro_bias_test() {
+        // GIVEN a level sensor at rest whose gyroscope has a bias
+        let mut filter = MahonyFilter::new(MAHONY_KP, MAHONY_KI);
+        let mut reading = sample(Tilt::default(), Tilt::default(), [0.0; 3]);
+        reading.gyro = [0.05, -0.03, 0.0];
+
+        // WHEN the readings are filtered for a while
+        let mut tilt = Tilt::default();
+        for _ in 0..6000 {
+            tilt = filter.update(&reading, STEP_MS);
+        }
+
+        // THEN the bias is learnt and the tilt settles back at level
+        assert!(error(tilt, Tilt::default()) < 0.005);
+        assert!((filter.integral[0] + 0.05).abs() < 0.005);
+        assert!((filter.integral[1] - 0.03).abs() < 0.005);
+    }
+
+    #[test]
+    fn free_fall_test() {
+        // GIVEN filters that start in free fall, without gravity to go by
+        let mut complementary = ComplementaryFilter::new(0);
+        let mut mahony = MahonyFilter::new(MAHONY_KP, 0.0);
+        let falling = ImuSample::new([0.0; 3], [1.0, 0.0, 0.0]);
+
+        // THEN the filters start level and only follow the gyroscope
+        assert_eq!(complementary.update(&falling, STEP_MS), Tilt::default());
+        assert_eq!(mahony.update(&falling, STEP_MS), Tilt::default());
+        assert!((complementary.update(&falling, 100).roll - 0.1).abs() < 1e-5);
+        assert!((mahony.update(&falling, 100).roll - 0.1).abs() < 1e-3);
+
+        // THEN a filter with a time constant of zero only uses the accelerometer
+        let tilt = Tilt::new(0.4, 0.2);
+        let reading = sample(tilt, Tilt::default(), [0.0; 3]);
+        assert!(error(complementary.update(&reading, STEP_MS), tilt) < 1e-5);
+    }
+}
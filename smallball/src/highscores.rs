@@ -0,0 +1,399 @@
+//!
+//! This file defines the high score table of the SmallBall game, a ranked table of the ten
+//! best scores of single player games, each with the initials of the player who achieved
+//! it. The initials are entered with the tilt of the mpu, like on an arcade machine: rolling
+//! the sensor cycles the letter that is chosen and pitching it confirms the letter.
+//!
+
+use crate::{
+    config::{HIGH_SCORES, INITIALS},
+    smallball::Tilt,
+};
+use heapless::Vec;
+
+/// The initials of a player, made of the letters A to Z.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Initials([u8; INITIALS]);
+
+impl Initials {
+    /// Return the given initials, or None if they are not INITIALS letters from A to Z.
+    /// # Arguments
+    /// * `letters` - the letters of the initials
+    pub fn new(letters: [u8; INITIALS]) -> Option<Self> {
+        if letters.iter().all(u8::is_ascii_uppercase) {
+            Some(Initials(letters))
+        } else {
+            None
+        }
+    }
+
+    /// Return the letters of the initials.
+    pub fn letters(&self) -> [u8; INITIALS] {
+        self.0
+    }
+
+    /// Return the initials as text.
+    pub fn as_str(&self) -> &str {
+        // the letters are checked to be ASCII when the initials are made
+        core::str::from_utf8(&self.0).unwrap()
+    }
+}
+
+impl Default for Initials {
+    fn default() -> Self {
+        Initials([b'A'; INITIALS])
+    }
+}
+
+/// A score in the high score table.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct HighScore {
+    /// the initials of the player who achieved the score
+    initials: Initials,
+    /// the score, the time it took to complete the game
+    score: i32,
+}
+
+impl HighScore {
+    /// Return the initials of the player who achieved the score.
+    pub fn initials(&self) -> Initials {
+        self.initials
+    }
+
+    /// Return the score.
+    pub fn score(&self) -> i32 {
+        self.score
+    }
+}
+
+/// The best scores of the game, ranked from the lowest score, which is the best.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct HighScoreTable {
+    /// the scores in the table, from best to worst
+    entries: Vec<HighScore, HIGH_SCORES>,
+}
+
+impl HighScoreTable {
+    /// Return an empty table.
+    pub fn new() -> Self {
+        HighScoreTable {
+            entries: Vec::new(),
+        }
+    }
+
+    /// Return the scores in the table, from best to worst.
+    pub fn entries(&self) -> &[HighScore] {
+        &self.entries
+    }
+
+    /// Return the rank, starting at zero for the best score, the given score would have in
+    /// the table, or None if it is not good enough for the table. A score that ties with a
+    /// score in the table ranks below it.
+    /// # Arguments
+    /// * `score` - the score of a completed game
+    pub fn rank(&self, score: i32) -> Option<usize> {
+        let rank = self
+            .entries
+            .iter()
+            .position(|entry| score < entry.score)
+            .unwrap_or(self.entries.len());
+        if rank < HIGH_SCORES {
+            Some(rank)
+        } else {
+            None
+        }
+    }
+
+    /// Add the given score to the table if it is good enough and return its rank. The worst
+    /// score drops out of a full table.
+    /// # Arguments
+    /// * `initials` - the initials of the player who achieved the score
+    /// * `score` - the score of a completed game
+    pub fn insert(&mut self, initials: Initials, score: i32) -> Option<usize> {
+        let rank = self.rank(score)?;
+        if self.entries.is_full() {
+            self.entries.pop();
+        }
+        // there is room for the score, the worst score has been dropped from a full table
+        self.entries.push(HighScore { initials, score }).unwrap();
+        self.entries[rank..].rotate_right(1);
+        Some(rank)
+    }
+}
+
+/// The initials a player is entering for a new high score.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InitialsEntry {
+    /// the letters chosen so far, the letters after the current one are still A
+    initials: Initials,
+    /// the index of the letter that is chosen, INITIALS once every letter is confirmed
+    position: usize,
+    /// whether the sensor has been level since the entry started, so that a tilt that is
+    /// still held from the game does not choose a letter
+    ready: bool,
+    /// the direction the letters are cycled in while the roll is held and the time in
+    /// milliseconds since the letter last changed
+    cycle: Option<(i8, u32)>,
+    /// whether the pitch that confirmed the last letter is still held
+    confirming: bool,
+}
+
+impl InitialsEntry {
+    /// Return a new entry that starts at the first letter.
+    pub fn new() -> Self {
+        InitialsEntry {
+            initials: Initials::default(),
+            position: 0,
+            ready: false,
+            cycle: None,
+            confirming: false,
+        }
+    }
+
+    /// Return an entry restored from a save, or None if the position is not valid. The
+    /// sensor has to be level again before the entry goes on.
+    /// # Arguments
+    /// * `initials` - the letters chosen so far
+    /// * `position` - the index of the letter that is chosen
+    pub(crate) fn restore(initials: Initials, position: usize) -> Option<Self> {
+        if position > INITIALS {
+            return None;
+        }
+        Some(InitialsEntry {
+            initials,
+            position,
+            ..InitialsEntry::new()
+        })
+    }
+
+    /// Return the letters chosen so far.
+    pub fn initials(&self) -> Initials {
+        self.initials
+    }
+
+    /// Return the index of the letter that is chosen, INITIALS once every letter is
+    /// confirmed.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Return true once every letter is confirmed.
+    pub fn is_done(&self) -> bool {
+        self.position == INITIALS
+    }
+
+    /// Update the entry based on the time that has passed since the last update and the
+    /// latest tilt of the mpu. Rolling further than the angle moves the letter on, to the
+    /// next letter for a roll to the right and to the previous one for a roll to the left,
+    /// and keeps moving it while the roll is held. Pitching further than the angle confirms
+    /// the letter, the pitch has to be released before the next letter can be confirmed.
+    /// # Arguments
+    /// * `elapsed_ms` - the time in milliseconds since the last update
+    /// * `tilt` - the tilt of the mpu of the player
+    /// * `angle` - the tilt angle that moves the letter on or confirms it
+    /// * `repeat_ms` - the time in milliseconds a roll is held before the letter moves on again
+    pub(crate) fn update(&mut self, elapsed_ms: u32, tilt: Tilt, angle: f32, repeat_ms: u32) {
+        let direction = if tilt.roll > angle {
+            1
+        } else if tilt.roll < -angle {
+            -1
+        } else {
+            0
+        };
+        let pitched = tilt.pitch.abs() > angle;
+        if !self.ready {
+            self.ready = direction == 0 && !pitched;
+            return;
+        }
+        if self.is_done() {
+            return;
+        }
+
+        if pitched {
+            if !self.confirming {
+                self.position += 1;
+            }
+            self.confirming = true;
+            self.cycle = None;
+            return;
+        }
+        self.confirming = false;
+
+        self.cycle = match (direction, self.cycle) {
+            (0, _) => None,
+            (direction, Some((held, held_ms))) if held == direction => {
+                let held_ms = held_ms + elapsed_ms;
+                if held_ms >= repeat_ms {
+                    self.step(direction);
+                    Some((direction, held_ms - repeat_ms))
+                } else {
+                    Some((direction, held_ms))
+                }
+            }
+            (direction, _) => {
+                self.step(direction);
+                Some((direction, 0))
+            }
+        };
+    }
+
+    /// Move the letter that is chosen on by one, wrapping around from Z to A.
+    /// # Arguments
+    /// * `direction` - 1 to move to the next letter, -1 to move to the previous one
+    fn step(&mut self, direction: i8) {
+        let letter = &mut self.initials.0[self.position];
+        let index = (*letter - b'A') as i8 + direction;
+        *letter = b'A' + index.rem_euclid(26) as u8;
+    }
+}
+
+impl Default for InitialsEntry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HighScoreTable, Initials, InitialsEntry};
+    use crate::{
+        config::{HIGH_SCORES, INITIALS},
+        smallball::Tilt,
+    };
+
+    // the angle and the repeat time used to enter initials in the tests
+    const ANGLE: f32 = 0.3;
+    const REPEAT_MS: u32 = 100;
+
+    #[test]
+    fn initials_test() {
+        let initials = Initials::new(*b"ABC").unwrap();
+        assert_eq!(initials.as_str(), "ABC");
+        assert_eq!(initials.letters(), *b"ABC");
+        assert_eq!(Initials::default().as_str(), "AAA");
+        assert_eq!(Initials::new(*b"AbC"), None);
+        assert_eq!(Initials::new(*b"A C"), None);
+    }
+
+    #[test]
+    fn high_score_table_test() {
+        // GIVEN an empty table
+        let mut table = HighScoreTable::new();
+        assert!(table.entries().is_empty());
+        let initials = |text: &[u8; INITIALS]| Initials::new(*text).unwrap();
+
+        // WHEN scores are inserted
+        // THEN they are ranked from the lowest score
+        assert_eq!(table.insert(initials(b"BBB"), 2000), Some(0));
+        assert_eq!(table.insert(initials(b"AAA"), 1000), Some(0));
+        assert_eq!(table.insert(initials(b"CCC"), 3000), Some(2));
+
+        // THEN a tie ranks below the score already in the table
+        assert_eq!(table.insert(initials(b"DDD"), 2000), Some(2));
+        let scores: std::vec::Vec<([u8; INITIALS], i32)> = table
+            .entries()
+            .iter()
+            .map(|entry| (entry.initials().letters(), entry.score()))
+            .collect();
+        assert_eq!(
+            scores,
+            [
+                (*b"AAA", 1000),
+                (*b"BBB", 2000),
+                (*b"DDD", 2000),
+                (*b"CCC", 3000)
+            ]
+        );
+    }
+
+    #[test]
+    fn full_high_score_table_test() {
+        // GIVEN a full table
+        let mut table = HighScoreTable::new();
+        for score in 1..=HIGH_SCORES as i32 {
+            table.insert(Initials::default(), score * 100);
+        }
+        assert_eq!(table.entries().len(), HIGH_SCORES);
+
+        // WHEN a score worse than every score in the table is inserted
+        // THEN it is not added
+        let worst = HIGH_SCORES as i32 * 100;
+        assert_eq!(table.rank(worst), None);
+        assert_eq!(table.insert(Initials::default(), worst), None);
+
+        // WHEN a better score is inserted
+        // THEN the worst score drops out
+        assert_eq!(table.insert(Initials::default(), 150), Some(1));
+        assert_eq!(table.entries().len(), HIGH_SCORES);
+        assert_eq!(table.entries()[1].score(), 150);
+        assert_eq!(table.entries()[HIGH_SCORES - 1].score(), worst - 100);
+    }
+
+    #[test]
+    fn roll_cycles_letters_test() {
+        // GIVEN an entry with the sensor level
+        let mut entry = InitialsEntry::new();
+        entry.update(10, Tilt::new(0.0, 0.0), ANGLE, REPEAT_MS);
+
+        // WHEN the sensor is rolled to the left
+        entry.update(10, Tilt::new(0.0, -(ANGLE + 0.1)), ANGLE, REPEAT_MS);
+
+        // THEN the letter wraps around to Z
+        assert_eq!(entry.initials().as_str(), "ZAA");
+
+        // WHEN the roll is held
+        entry.update(
+            REPEAT_MS - 1,
+            Tilt::new(0.0, -(ANGLE + 0.1)),
+            ANGLE,
+            REPEAT_MS,
+        );
+
+        // THEN the letter only moves on once the repeat time has passed
+        assert_eq!(entry.initials().as_str(), "ZAA");
+        entry.update(1, Tilt::new(0.0, -(ANGLE + 0.1)), ANGLE, REPEAT_MS);
+        assert_eq!(entry.initials().as_str(), "YAA");
+
+        // WHEN the sensor is rolled to the right, released and rolled again
+        entry.update(10, Tilt::new(0.0, ANGLE + 0.1), ANGLE, REPEAT_MS);
+        entry.update(10, Tilt::new(0.0, 0.0), ANGLE, REPEAT_MS);
+        entry.update(10, Tilt::new(0.0, ANGLE + 0.1), ANGLE, REPEAT_MS);
+
+        // THEN the letter moves on right away every time
+        assert_eq!(entry.initials().as_str(), "AAA");
+        assert_eq!(entry.position(), 0);
+    }
+
+    #[test]
+    fn pitch_confirms_letters_test() {
+        // GIVEN an entry that starts while the sensor is still tilted
+        let mut entry = InitialsEntry::default();
+        entry.update(10, Tilt::new(ANGLE + 0.1, ANGLE + 0.1), ANGLE, REPEAT_MS);
+
+        // THEN the tilt is ignored until the sensor is level
+        assert_eq!(entry.initials().as_str(), "AAA");
+        assert_eq!(entry.position(), 0);
+        entry.update(10, Tilt::new(0.0, 0.0), ANGLE, REPEAT_MS);
+
+        // WHEN a letter is chosen and the sensor is pitched and held
+        entry.update(10, Tilt::new(0.0, ANGLE + 0.1), ANGLE, REPEAT_MS);
+        entry.update(10, Tilt::new(ANGLE + 0.1, 0.0), ANGLE, REPEAT_MS);
+        entry.update(10, Tilt::new(ANGLE + 0.1, 0.0), ANGLE, REPEAT_MS);
+
+        // THEN only the first letter is confirmed
+        assert_eq!(entry.position(), 1);
+        assert!(!entry.is_done());
+
+        // WHEN the other letters are chosen and confirmed
+        entry.update(10, Tilt::new(0.0, 0.0), ANGLE, REPEAT_MS);
+        entry.update(10, Tilt::new(-(ANGLE + 0.1), 0.0), ANGLE, REPEAT_MS);
+        entry.update(10, Tilt::new(0.0, -(ANGLE + 0.1)), ANGLE, REPEAT_MS);
+        entry.update(10, Tilt::new(ANGLE + 0.1, 0.0), ANGLE, REPEAT_MS);
+
+        // THEN the entry is done and ignores the tilt
+        assert!(entry.is_done());
+        entry.update(10, Tilt::new(0.0, ANGLE + 0.1), ANGLE, REPEAT_MS);
+        assert_eq!(entry.initials().as_str(), "BAZ");
+        assert_eq!(entry.position(), INITIALS);
+    }
+}
@@ -0,0 +1,296 @@
+//!
+//! This file defines the control input of the SmallBall game and the InputSource trait of
+//! the controllers that yield it, so that the game does not depend on how it is controlled.
+//! A controller is read once per update, such as the MPU6050 of the RP2040 board (see
+//! imu.rs of the firmware), the ScriptedInput used to test the game on the host or the
+//! RecordedInput that plays back a log of the inputs of an earlier game. A CalibratedInput
+//! takes the offset measured by a calibration off the tilt of any controller.
+//!
+
+use crate::smallball::Tilt;
+
+/// The button that starts the game and skips the screens between games, like a strong tilt.
+pub const BUTTON_START: u8 = 1 << 0;
+
+/// The size in bytes of a control input in a log: the pitch, the roll, the buttons and
+/// whether the input is valid.
+pub const INPUT_RECORD_SIZE: usize = 4 + 4 + 1 + 1;
+
+/// The control input of a player at one update of the game.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ControlInput {
+    /// the tilt of the controller
+    tilt: Tilt,
+    /// the buttons that are pressed, one bit per button
+    buttons: u8,
+    /// whether the controller could be read, the other fields are meaningless otherwise
+    valid: bool,
+}
+
+impl ControlInput {
+    /// Return a new valid control input.
+    /// # Arguments
+    /// * `tilt` - the tilt of the controller
+    /// * `buttons` - the buttons that are pressed, such as BUTTON_START
+    pub fn new(tilt: Tilt, buttons: u8) -> Self {
+        ControlInput {
+            tilt,
+            buttons,
+            valid: true,
+        }
+    }
+
+    /// Return the input of a controller that could not be read. The ball of its player is
+    /// not accelerated and no button is pressed.
+    pub fn invalid() -> Self {
+        ControlInput::default()
+    }
+
+    /// Return the tilt of the controller, level if the input is invalid.
+    pub fn tilt(&self) -> Tilt {
+        self.tilt
+    }
+
+    /// Return the buttons that are pressed, one bit per button.
+    pub fn buttons(&self) -> u8 {
+        self.buttons
+    }
+
+    /// Return whether the given button is pressed.
+    /// # Arguments
+    /// * `button` - the button, such as BUTTON_START
+    pub fn is_pressed(&self, button: u8) -> bool {
+        self.buttons & button != 0
+    }
+
+    /// Return whether the controller could be read.
+    pub fn is_valid(&self) -> bool {
+        self.valid
+    }
+
+    /// Return the input as a record of a log, in little endian byte order.
+    pub fn to_bytes(&self) -> [u8; INPUT_RECORD_SIZE] {
+        let mut bytes = [0; INPUT_RECORD_SIZE];
+        bytes[..4].copy_from_slice(&self.tilt.pitch.to_le_bytes());
+        bytes[4..8].copy_from_slice(&self.tilt.roll.to_le_bytes());
+        bytes[8] = self.buttons;
+        bytes[9] = self.valid as u8;
+        bytes
+    }
+
+    /// Return the input of the given record of a log, or None if it is not a record.
+    /// # Arguments
+    /// * `bytes` - the record
+    pub fn from_bytes(bytes: &[u8; INPUT_RECORD_SIZE]) -> Option<Self> {
+        let pitch = f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        let roll = f32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+        match bytes[9] {
+            0 => Some(ControlInput::invalid()),
+            1 => Some(ControlInput::new(Tilt::new(pitch, roll), bytes[8])),
+            _ => None,
+        }
+    }
+}
+
+/// A controller of the game, read once per update.
+pub trait InputSource {
+    /// Return the current control input of the controller.
+    fn read(&mut self) -> ControlInput;
+}
+
+/// A controller that yields the given inputs one after another, and invalid inputs once
+/// they have all been read.
+pub struct ScriptedInput<'a> {
+    /// the inputs to yield
+    inputs: &'a [ControlInput],
+    /// the index of the next input to yield
+    index: usize,
+}
+
+impl<'a> ScriptedInput<'a> {
+    /// Return a controller that yields the given inputs.
+    /// # Arguments
+    /// * `inputs` - the inputs to yield in order
+    pub fn new(inputs: &'a [ControlInput]) -> Self {
+        ScriptedInput { inputs, index: 0 }
+    }
+}
+
+impl InputSource for ScriptedInput<'_> {
+    fn read(&mut self) -> ControlInput {
+        let input = self.inputs.get(self.index).copied().unwrap_or_default();
+        self.index += 1;
+        input
+    }
+}
+
+/// A controller that plays back a log of control inputs, made of the records that
+/// ControlInput::to_bytes returns. A record that cannot be decoded and the end of the log
+/// yield invalid inputs.
+pub struct RecordedInput<'a> {
+    /// the log
+    log: &'a [u8],
+    /// the offset in bytes of the next record
+    offset: usize,
+}
+
+impl<'a> RecordedInput<'a> {
+    /// Return a controller that plays back the given log.
+    /// # Arguments
+    /// * `log` - the records of the inputs in order
+    pub fn new(log: &'a [u8]) -> Self {
+        RecordedInput { log, offset: 0 }
+    }
+}
+
+impl InputSource for RecordedInput<'_> {
+    fn read(&mut self) -> ControlInput {
+        let record = self
+            .log
+            .get(self.offset..self.offset + INPUT_RECORD_SIZE)
+            .and_then(|record| record.try_into().ok())
+            .and_then(ControlInput::from_bytes);
+        self.offset += INPUT_RECORD_SIZE;
+        record.unwrap_or_default()
+    }
+}
+
+/// A controller whose tilt is corrected by the offset measured by a calibration (see
+/// Mode::Calibrate), so that the neutral pose of the controller is level.
+pub struct CalibratedInput<S> {
+    /// the controller
+    source: S,
+    /// the tilt of the controller in its neutral pose
+    offset: Tilt,
+}
+
+impl<S> CalibratedInput<S> {
+    /// Return the given controller corrected by the given offset.
+    /// # Arguments
+    /// * `source` - the controller
+    /// * `offset` - the tilt of the controller in its neutral pose
+    pub fn new(source: S, offset: Tilt) -> Self {
+        CalibratedInput { source, offset }
+    }
+
+    /// Return the tilt of the controller in its neutral pose.
+    pub fn offset(&self) -> Tilt {
+        self.offset
+    }
+
+    /// Change the tilt of the controller in its neutral pose, level while it is calibrated.
+    /// # Arguments
+    /// * `offset` - the tilt of the controller in its neutral pose
+    pub fn set_offset(&mut self, offset: Tilt) {
+        self.offset = offset;
+    }
+}
+
+impl<S: InputSource> InputSource for CalibratedInput<S> {
+    fn read(&mut self) -> ControlInput {
+        let input = self.source.read();
+        if !input.is_valid() {
+            return input;
+        }
+        let tilt = input.tilt();
+        ControlInput::new(
+            Tilt::new(tilt.pitch - self.offset.pitch, tilt.roll - self.offset.roll),
+            input.buttons(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        CalibratedInput, ControlInput, InputSource, RecordedInput, ScriptedInput, BUTTON_START,
+        INPUT_RECORD_SIZE,
+    };
+    use crate::smallball::Tilt;
+
+    #[test]
+    fn control_input_test() {
+        let input = ControlInput::new(Tilt::new(0.5, -0.25), BUTTON_START);
+        assert_eq!(input.tilt(), Tilt::new(0.5, -0.25));
+        assert_eq!(input.buttons(), BUTTON_START);
+        assert!(input.is_pressed(BUTTON_START));
+        assert!(!input.is_pressed(1 << 1));
+        assert!(input.is_valid());
+
+        // an invalid input is level without buttons
+        let invalid = ControlInput::invalid();
+        assert_eq!(invalid.tilt(), Tilt::default());
+        assert!(!invalid.is_pressed(BUTTON_START));
+        assert!(!invalid.is_valid());
+
+        // inputs survive the trip through a record, a broken record is not an input
+        assert_eq!(ControlInput::from_bytes(&input.to_bytes()), Some(input));
+        assert_eq!(ControlInput::from_bytes(&invalid.to_bytes()), Some(invalid));
+        let mut bytes = input.to_bytes();
+        bytes[9] = 2;
+        assert_eq!(ControlInput::from_bytes(&bytes), None);
+    }
+
+    #[test]
+    fn scripted_input_test() {
+        // GIVEN a script of two inputs
+        let inputs = [
+            ControlInput::new(Tilt::new(0.1, 0.2), 0),
+            ControlInput::new(Tilt::new(0.3, 0.4), BUTTON_START),
+        ];
+        let mut source = ScriptedInput::new(&inputs);
+
+        // THEN the inputs are read in order and then the input is invalid
+        assert_eq!(source.read(), inputs[0]);
+        assert_eq!(source.read(), inputs[1]);
+        assert_eq!(source.read(), ControlInput::invalid());
+        assert_eq!(source.read(), ControlInput::invalid());
+    }
+
+    #[test]
+    fn recorded_input_test() {
+        // GIVEN a log of three inputs, the second of which is broken, cut short at the end
+        let inputs = [
+            ControlInput::new(Tilt::new(0.1, 0.2), 0),
+            ControlInput::new(Tilt::new(0.3, 0.4), 0),
+            ControlInput::new(Tilt::new(-0.5, 0.6), BUTTON_START),
+        ];
+        let mut log = [0; 3 * INPUT_RECORD_SIZE];
+        for (record, input) in log.chunks_mut(INPUT_RECORD_SIZE).zip(inputs.iter()) {
+            record.copy_from_slice(&input.to_bytes());
+        }
+        log[2 * INPUT_RECORD_SIZE - 1] = 0xff;
+        let mut source = RecordedInput::new(&log[..log.len() - 1]);
+
+        // THEN the log is played back with invalid inputs for the broken and missing records
+        assert_eq!(source.read(), inputs[0]);
+        assert_eq!(source.read(), ControlInput::invalid());
+        assert_eq!(source.read(), ControlInput::invalid());
+        assert_eq!(
+            RecordedInput::new(&log[2 * INPUT_RECORD_SIZE..]).read(),
+            inputs[2]
+        );
+    }
+
+    #[test]
+    fn calibrated_input_test() {
+        // GIVEN a controller with an offset, which cannot be read the second time
+        let inputs = [
+            ControlInput::new(Tilt::new(0.15, -0.05), BUTTON_START),
+            ControlInput::invalid(),
+        ];
+        let mut source = CalibratedInput::new(ScriptedInput::new(&inputs), Tilt::new(0.1, 0.05));
+        assert_eq!(source.offset(), Tilt::new(0.1, 0.05));
+
+        // THEN the offset is taken off the tilt of valid inputs only
+        let input = source.read();
+        assert!((input.tilt().pitch - 0.05).abs() < 1e-6);
+        assert!((input.tilt().roll + 0.1).abs() < 1e-6);
+        assert!(input.is_pressed(BUTTON_START));
+        assert_eq!(source.read(), ControlInput::invalid());
+
+        // THEN a controller without an offset is not corrected
+        source.set_offset(Tilt::default());
+        assert_eq!(source.offset(), Tilt::default());
+    }
+}
@@ -0,0 +1,139 @@
+//!
+//! This file defines the layout of the SmallBall game on a display. The screens of the game
+//! are designed for a display of the reference screen size, the layout places them on a
+//! display of any size by keeping the header at the top and scaling everything else.
+//!
+
+use crate::{
+    config::{HEADER_HEIGHT, REFERENCE_SCREEN_SIZE},
+    math::scale,
+};
+use embedded_graphics::{
+    prelude::{Point, Size},
+    primitives::Rectangle,
+};
+
+/// The layout of the game on a display of a given size.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Layout {
+    /// the size of the display in pixels
+    size: Size,
+}
+
+impl Layout {
+    /// Return the layout of the game on a display of the given size.
+    /// # Arguments
+    /// * `size` - the size of the display in pixels
+    pub const fn new(size: Size) -> Self {
+        Layout { size }
+    }
+
+    /// Return the size of the display.
+    pub fn size(&self) -> Size {
+        self.size
+    }
+
+    /// Return the rectangle that outlines the entire screen.
+    pub fn full_screen_outline(&self) -> Rectangle {
+        Rectangle::new(
+            Point::zero(),
+            Size::new(self.size.width - 1, self.size.height - 1),
+        )
+    }
+
+    /// Return the rectangle that outlines the game space during game play, below the header
+    /// that shows the score.
+    pub fn screen_outline(&self) -> Rectangle {
+        Rectangle::new(
+            Point::new(0, HEADER_HEIGHT as i32 - 1),
+            Size::new(self.size.width - 1, self.size.height - HEADER_HEIGHT + 1),
+        )
+    }
+
+    /// Return the area of the screen the ball moves in, inside the screen outline.
+    pub fn play_area(&self) -> Rectangle {
+        Rectangle::new(
+            Point::new(0, HEADER_HEIGHT as i32),
+            Size::new(self.size.width - 2, self.size.height - HEADER_HEIGHT),
+        )
+    }
+
+    /// Return the location on this display of the given location on a display of the
+    /// reference screen size, such as the location of a text.
+    /// # Arguments
+    /// * `location` - the location on a display of the reference screen size
+    pub fn location(&self, location: Point) -> Point {
+        Point::new(
+            scale(
+                location.x,
+                0,
+                REFERENCE_SCREEN_SIZE.width,
+                0,
+                self.size.width,
+            ),
+            scale(
+                location.y,
+                0,
+                REFERENCE_SCREEN_SIZE.height,
+                0,
+                self.size.height,
+            ),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Layout;
+    use crate::config::{
+        BALL_SIZE, FULL_SCREEN_OUTLINE_SIZE, FULL_SCREEN_OUTLINE_TOP_LET, GAME_OVER_SCORE_LOCATION,
+        REFERENCE_SCREEN_SIZE, SCREEN_OUTLINE_SIZE, SCREEN_OUTLINE_TOP_LET, X_MAX, X_MIN, Y_MAX,
+        Y_MIN,
+    };
+    use embedded_graphics::prelude::{Point, Size};
+
+    #[test]
+    fn reference_layout_test() {
+        // GIVEN the layout of a display of the reference screen size
+        let layout = Layout::new(REFERENCE_SCREEN_SIZE);
+
+        // THEN the layout matches the constants
+        assert_eq!(layout.size(), Size::new(128, 64));
+        assert_eq!(
+            layout.full_screen_outline().top_left,
+            FULL_SCREEN_OUTLINE_TOP_LET
+        );
+        assert_eq!(layout.full_screen_outline().size, FULL_SCREEN_OUTLINE_SIZE);
+        assert_eq!(layout.screen_outline().top_left, SCREEN_OUTLINE_TOP_LET);
+        assert_eq!(layout.screen_outline().size, SCREEN_OUTLINE_SIZE);
+        assert_eq!(layout.play_area().top_left, Point::new(X_MIN, Y_MIN));
+        assert_eq!(
+            layout.location(GAME_OVER_SCORE_LOCATION),
+            GAME_OVER_SCORE_LOCATION
+        );
+        let bottom_right = layout.play_area().bottom_right().unwrap();
+        let ball = BALL_SIZE as i32 - 1;
+        assert_eq!(bottom_right, Point::new(X_MAX + ball, Y_MAX + ball));
+    }
+
+    #[test]
+    fn scaled_layout_test() {
+        // GIVEN the layout of a short display
+        let layout = Layout::new(Size::new(128, 32));
+
+        // THEN the header keeps its height and the game space shrinks
+        assert_eq!(layout.screen_outline().top_left, SCREEN_OUTLINE_TOP_LET);
+        assert_eq!(layout.screen_outline().size, Size::new(127, 23));
+        assert_eq!(layout.play_area().size, Size::new(126, 22));
+        assert_eq!(layout.location(Point::new(2, 40)), Point::new(2, 20));
+
+        // GIVEN the layout of a square display
+        let layout = Layout::new(Size::new(128, 128));
+
+        // THEN the header keeps its height and the game space grows
+        assert_eq!(layout.full_screen_outline().size, Size::new(127, 127));
+        assert_eq!(layout.screen_outline().size, Size::new(127, 119));
+        assert_eq!(layout.play_area().size, Size::new(126, 118));
+        assert_eq!(layout.location(Point::new(2, 40)), Point::new(2, 80));
+    }
+}
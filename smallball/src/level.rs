@@ -0,0 +1,158 @@
+//!
+//! This file defines the levels of the SmallBall game. Each level is a layout of goals
+//! for the ball to visit, of obstacles that block the ball and of holes the ball can fall
+//! into. A game is won by visiting all goals of every level in turn.
+//!
+
+use crate::smallball::{Hazard, Obstacle};
+use embedded_graphics::prelude::Point;
+
+/// The definition of a level of the game.
+#[derive(Debug)]
+pub struct Level {
+    /// the initial location of each goal
+    goals: &'static [Point],
+    /// the initial location of the ball
+    ball: Point,
+    /// the obstacles that block the ball
+    obstacles: &'static [Obstacle],
+    /// the holes the ball can fall into
+    hazards: &'static [Hazard],
+    /// the time in milliseconds a good player needs to complete the level, if the level
+    /// has one
+    par: Option<i32>,
+}
+
+impl Level {
+    /// Return a new level definition without obstacles and holes.
+    /// # Arguments
+    /// * `goals` - the initial location of each goal
+    /// * `ball` - the initial location of the ball
+    /// * `par` - the time in milliseconds a good player needs to complete the level, if any
+    pub const fn new(goals: &'static [Point], ball: Point, par: Option<i32>) -> Self {
+        Level {
+            goals,
+            ball,
+            obstacles: &[],
+            hazards: &[],
+            par,
+        }
+    }
+
+    /// Return this level definition with the given obstacles.
+    /// # Arguments
+    /// * `obstacles` - the obstacles that block the ball
+    pub const fn with_obstacles(self, obstacles: &'static [Obstacle]) -> Self {
+        Level { obstacles, ..self }
+    }
+
+    /// Return this level definition with the given holes.
+    /// # Arguments
+    /// * `hazards` - the holes the ball can fall into
+    pub const fn with_hazards(self, hazards: &'static [Hazard]) -> Self {
+        Level { hazards, ..self }
+    }
+
+    /// Return the initial location of each goal.
+    pub fn goals(&self) -> &'static [Point] {
+        self.goals
+    }
+
+    /// Return the initial location of the ball.
+    pub fn ball(&self) -> Point {
+        self.ball
+    }
+
+    /// Return the obstacles that block the ball.
+    pub fn obstacles(&self) -> &'static [Obstacle] {
+        self.obstacles
+    }
+
+    /// Return the holes the ball can fall into.
+    pub fn hazards(&self) -> &'static [Hazard] {
+        self.hazards
+    }
+
+    /// Return the par time for the level in milliseconds, if the level has one.
+    pub fn par(&self) -> Option<i32> {
+        self.par
+    }
+}
+
+/// Return the largest number of goals of any of the given levels. The function is const so
+/// that levels defined as constants can be checked at compile time.
+/// # Arguments
+/// * `levels` - the levels to check
+pub const fn max_goals(levels: &[Level]) -> usize {
+    let mut max = 0;
+    let mut index = 0;
+    while index < levels.len() {
+        if levels[index].goals.len() > max {
+            max = levels[index].goals.len();
+        }
+        index += 1;
+    }
+    max
+}
+
+/// Return the number of goals of all the given levels together. The function is const so
+/// that levels defined as constants can be checked at compile time.
+/// # Arguments
+/// * `levels` - the levels to count the goals of
+pub const fn total_goals(levels: &[Level]) -> usize {
+    let mut total = 0;
+    let mut index = 0;
+    while index < levels.len() {
+        total += levels[index].goals.len();
+        index += 1;
+    }
+    total
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{max_goals, total_goals, Level};
+    use crate::smallball::{Hazard, HazardEffect, Obstacle};
+    use embedded_graphics::prelude::{Point, Size};
+
+    #[test]
+    fn level_test() {
+        const GOALS: [Point; 2] = [Point::new(1, 2), Point::new(3, 4)];
+        let level = Level::new(&GOALS, Point::new(5, 6), None);
+        assert_eq!(level.goals(), &GOALS);
+        assert_eq!(level.ball(), Point::new(5, 6));
+        assert_eq!(level.par(), None);
+        assert!(level.obstacles().is_empty());
+        assert!(level.hazards().is_empty());
+
+        const OBSTACLES: [Obstacle; 1] = [Obstacle::new(Point::new(7, 8), Size::new(9, 10))];
+        let level = Level::new(&[], Point::new(5, 6), Some(100)).with_obstacles(&OBSTACLES);
+        assert_eq!(level.par(), Some(100));
+        assert_eq!(level.ball(), Point::new(5, 6));
+        assert_eq!(level.obstacles()[0].location(), Point::new(7, 8));
+        assert_eq!(level.obstacles()[0].size(), Size::new(9, 10));
+        assert!(level.hazards().is_empty());
+
+        const HAZARDS: [Hazard; 1] = [Hazard::new(Point::new(1, 1), 4, HazardEffect::Reset)];
+        let level = Level::new(&[], Point::new(5, 6), None).with_hazards(&HAZARDS);
+        assert_eq!(level.hazards()[0].location(), Point::new(1, 1));
+        assert_eq!(level.hazards()[0].size(), 4);
+        assert_eq!(level.hazards()[0].effect(), HazardEffect::Reset);
+        assert!(level.obstacles().is_empty());
+    }
+
+    #[test]
+    fn max_goals_test() {
+        const GOALS: [Point; 2] = [Point::new(1, 2), Point::new(3, 4)];
+        const LEVELS: [Level; 3] = [
+            Level::new(&GOALS, Point::new(5, 6), None),
+            Level::new(&GOALS, Point::new(5, 6), None),
+            Level::new(&[], Point::new(5, 6), None),
+        ];
+        assert_eq!(max_goals(&LEVELS), 2);
+        assert_eq!(max_goals(&LEVELS[2..]), 0);
+        assert_eq!(max_goals(&[]), 0);
+        assert_eq!(total_goals(&LEVELS), 4);
+        assert_eq!(total_goals(&[]), 0);
+    }
+}
@@ -3,5 +3,17 @@
 #![cfg_attr(not(test), no_std)]
 
 pub mod config;
+pub mod fusion;
+pub mod highscores;
+pub mod input;
+pub mod layout;
+pub mod level;
 mod math;
+pub mod persistence;
+pub mod render;
+mod rng;
+pub mod save;
+pub mod shaping;
 pub mod smallball;
+pub mod stats;
+pub mod time;
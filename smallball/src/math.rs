@@ -2,7 +2,75 @@
 //! Custom math functions needed by the game.
 //!
 
-use embedded_graphics::prelude::Point;
+use core::ops::{Add, Sub};
+use embedded_graphics::prelude::{Point, Size};
+
+/// A two dimensional vector with sub-pixel precision, used for the position and
+/// velocity of entities that move around the screen.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Vector {
+    /// the horizontal component
+    pub x: f32,
+    /// the vertical component
+    pub y: f32,
+}
+
+impl Vector {
+    /// Return a new vector.
+    /// # Arguments
+    /// * `x` - the horizontal component
+    /// * `y` - the vertical component
+    pub fn new(x: f32, y: f32) -> Self {
+        Vector { x, y }
+    }
+
+    /// Return the pixel nearest to this vector.
+    pub fn to_point(self) -> Point {
+        Point::new(round(self.x), round(self.y))
+    }
+}
+
+impl From<Point> for Vector {
+    fn from(point: Point) -> Self {
+        Vector::new(point.x as f32, point.y as f32)
+    }
+}
+
+impl Add for Vector {
+    type Output = Vector;
+
+    fn add(self, other: Vector) -> Vector {
+        Vector::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl Sub for Vector {
+    type Output = Vector;
+
+    fn sub(self, other: Vector) -> Vector {
+        Vector::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+/// An axis of the screen.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Axis {
+    /// The horizontal axis
+    X,
+    /// The vertical axis
+    Y,
+}
+
+/// Return the integer nearest to the given value, rounding half way cases away from zero.
+/// # Arguments
+/// * `x` - the value to round
+pub fn round(x: f32) -> i32 {
+    if x < 0.0 {
+        (x - 0.5) as i32
+    } else {
+        (x + 0.5) as i32
+    }
+}
 
 /// Return true if the given square defined by point `top_left1` and size `size1`
 /// intersects the given square defined by point `top_left2` and size `size2`.
@@ -12,21 +80,345 @@ use embedded_graphics::prelude::Point;
 /// * `top_left2` - the top left point of the second square
 /// * `size2` - the size of the second square
 pub fn intersects(top_left1: Point, size1: u32, top_left2: Point, size2: u32) -> bool {
-    let size1 = size1 as i32;
-    let size2 = size2 as i32;
+    intersects_rectangle(
+        top_left1,
+        Size::new_equal(size1),
+        top_left2,
+        Size::new_equal(size2),
+    )
+}
+
+/// Return true if the given rectangle defined by point `top_left1` and size `size1`
+/// intersects the given rectangle defined by point `top_left2` and size `size2`.
+/// # Arguments
+/// * `top_left1` - the top left point of the first rectangle
+/// * `size1` - the size of the first rectangle
+/// * `top_left2` - the top left point of the second rectangle
+/// * `size2` - the size of the second rectangle
+pub fn intersects_rectangle(top_left1: Point, size1: Size, top_left2: Point, size2: Size) -> bool {
     intersects1d(
         top_left1.x,
-        top_left1.x + size1,
+        top_left1.x + size1.width as i32,
         top_left2.x,
-        top_left2.x + size2,
+        top_left2.x + size2.width as i32,
     ) && intersects1d(
         top_left1.y,
-        top_left1.y + size1,
+        top_left1.y + size1.height as i32,
         top_left2.y,
-        top_left2.y + size2,
+        top_left2.y + size2.height as i32,
     )
 }
 
+/// Return true if the given rectangle defined by point `top_left` and size `size` lies
+/// entirely inside the given outer rectangle defined by point `outer_top_left` and size
+/// `outer_size`. A rectangle that shares the edges of the outer rectangle is inside.
+/// # Arguments
+/// * `outer_top_left` - the top left point of the outer rectangle
+/// * `outer_size` - the size of the outer rectangle
+/// * `top_left` - the top left point of the rectangle
+/// * `size` - the size of the rectangle
+pub fn contains_rectangle(
+    outer_top_left: Point,
+    outer_size: Size,
+    top_left: Point,
+    size: Size,
+) -> bool {
+    top_left.x >= outer_top_left.x
+        && top_left.y >= outer_top_left.y
+        && top_left.x + size.width as i32 <= outer_top_left.x + outer_size.width as i32
+        && top_left.y + size.height as i32 <= outer_top_left.y + outer_size.height as i32
+}
+
+/// Return true if the given circle defined by point `top_left` of its bounding square and
+/// diameter `size` intersects the given rectangle defined by point `rect_top_left` and size
+/// `rect_size`. Shapes that touch on their boundary intersect.
+/// # Arguments
+/// * `top_left` - the top left point of the bounding square of the circle
+/// * `size` - the diameter of the circle
+/// * `rect_top_left` - the top left point of the rectangle
+/// * `rect_size` - the size of the rectangle
+pub fn circle_intersects_rectangle(
+    top_left: Point,
+    size: u32,
+    rect_top_left: Point,
+    rect_size: Size,
+) -> bool {
+    let centre = doubled_centre(top_left, size);
+    // the point of the rectangle nearest to the centre of the circle
+    let nearest = Point::new(
+        centre.x.clamp(
+            2 * rect_top_left.x,
+            2 * (rect_top_left.x + rect_size.width as i32),
+        ),
+        centre.y.clamp(
+            2 * rect_top_left.y,
+            2 * (rect_top_left.y + rect_size.height as i32),
+        ),
+    );
+    within(centre - nearest, size)
+}
+
+/// Return true if the given circle defined by point `top_left1` of its bounding square and
+/// diameter `size1` intersects the given circle defined by point `top_left2` and diameter
+/// `size2`. Circles that touch on their boundary intersect, a circle of zero diameter is a
+/// point.
+/// # Arguments
+/// * `top_left1` - the top left point of the bounding square of the first circle
+/// * `size1` - the diameter of the first circle
+/// * `top_left2` - the top left point of the bounding square of the second circle
+/// * `size2` - the diameter of the second circle
+pub fn circles_intersect(top_left1: Point, size1: u32, top_left2: Point, size2: u32) -> bool {
+    within(
+        doubled_centre(top_left1, size1) - doubled_centre(top_left2, size2),
+        size1 + size2,
+    )
+}
+
+/// Return the shortest translation that moves the given square defined by point `top_left`
+/// and size `size` out of the given rectangle defined by point `rect_top_left` and size
+/// `rect_size`, or None if they do not overlap. The translation is either horizontal or
+/// vertical so that a square moving into the side of a rectangle keeps sliding along it.
+/// Squares and rectangles that only touch do not overlap.
+/// # Arguments
+/// * `top_left` - the top left point of the square
+/// * `size` - the size of the square
+/// * `rect_top_left` - the top left point of the rectangle
+/// * `rect_size` - the size of the rectangle
+pub fn separation(
+    top_left: Vector,
+    size: u32,
+    rect_top_left: Point,
+    rect_size: Size,
+) -> Option<Vector> {
+    let size = size as f32;
+    // the translation needed to move the square out of each side of the rectangle
+    let left = rect_top_left.x as f32 - (top_left.x + size);
+    let right = (rect_top_left.x + rect_size.width as i32) as f32 - top_left.x;
+    let up = rect_top_left.y as f32 - (top_left.y + size);
+    let down = (rect_top_left.y + rect_size.height as i32) as f32 - top_left.y;
+
+    if left >= 0.0 || right <= 0.0 || up >= 0.0 || down <= 0.0 {
+        return None;
+    }
+
+    let x = if -left < right { left } else { right };
+    let y = if -up < down { up } else { down };
+    if x.abs() <= y.abs() {
+        Some(Vector::new(x, 0.0))
+    } else {
+        Some(Vector::new(0.0, y))
+    }
+}
+
+/// Return the fraction of the move of the given square from `from` to `to` at which it
+/// first overlaps the given rectangle defined by point `rect_top_left` and size `rect_size`,
+/// together with the axis along which it runs into the rectangle, or None if the square
+/// does not run into the rectangle during the move. The whole move is checked, so a fast
+/// square cannot pass through a thin rectangle. A square that already overlaps the
+/// rectangle at the start of the move does not run into it, and squares and rectangles
+/// that only touch do not overlap.
+/// # Arguments
+/// * `from` - the top left point of the square at the start of the move
+/// * `to` - the top left point of the square at the end of the move
+/// * `size` - the size of the square
+/// * `rect_top_left` - the top left point of the rectangle
+/// * `rect_size` - the size of the rectangle
+pub fn sweep(
+    from: Vector,
+    to: Vector,
+    size: u32,
+    rect_top_left: Point,
+    rect_size: Size,
+) -> Option<(f32, Axis)> {
+    let size = size as f32;
+    let (x_entry, x_exit) = sweep1d(
+        from.x,
+        to.x - from.x,
+        size,
+        rect_top_left.x as f32,
+        rect_size.width as f32,
+    );
+    let (y_entry, y_exit) = sweep1d(
+        from.y,
+        to.y - from.y,
+        size,
+        rect_top_left.y as f32,
+        rect_size.height as f32,
+    );
+    let (entry, axis) = if x_entry >= y_entry {
+        (x_entry, Axis::X)
+    } else {
+        (y_entry, Axis::Y)
+    };
+    if (0.0..1.0).contains(&entry) && entry < x_exit.min(y_exit) {
+        Some((entry, axis))
+    } else {
+        None
+    }
+}
+
+/// Return true if the given circle defined by point `from` of its bounding square and
+/// diameter `size` intersects the given rectangle defined by point `rect_top_left` and size
+/// `rect_size` anywhere on its move from `from` to `to`. The whole move is checked, so a fast
+/// circle cannot pass over a small rectangle. Shapes that touch on their boundary intersect.
+/// # Arguments
+/// * `from` - the top left point of the bounding square of the circle at the start of the move
+/// * `to` - the top left point of the bounding square of the circle at the end of the move
+/// * `size` - the diameter of the circle
+/// * `rect_top_left` - the top left point of the rectangle
+/// * `rect_size` - the size of the rectangle
+pub fn swept_circle_intersects_rectangle(
+    from: Point,
+    to: Point,
+    size: u32,
+    rect_top_left: Point,
+    rect_size: Size,
+) -> bool {
+    if circle_intersects_rectangle(to, size, rect_top_left, rect_size) {
+        return true;
+    }
+    // the path of the centre of the circle touches the rectangle grown by the radius of the
+    // circle, which has round corners, in doubled coordinates
+    let (start, end) = (doubled_centre(from, size), doubled_centre(to, size));
+    let radius = size as i32;
+    let min = rect_top_left * 2;
+    let max = (rect_top_left + rect_size) * 2;
+    let corners = [min, Point::new(max.x, min.y), max, Point::new(min.x, max.y)];
+    segment_intersects_box(
+        start,
+        end,
+        min - Point::new(radius, 0),
+        max + Point::new(radius, 0),
+    ) || segment_intersects_box(
+        start,
+        end,
+        min - Point::new(0, radius),
+        max + Point::new(0, radius),
+    ) || corners
+        .iter()
+        .any(|corner| segment_within(start, end, *corner, size))
+}
+
+/// Return the given value moved from one interval to another, keeping its relative position
+/// in the interval. The result is rounded down.
+/// # Arguments
+/// * `value` - the value to scale
+/// * `from_min` - the minimum value of the interval the value is in
+/// * `from_span` - the length of the interval the value is in
+/// * `to_min` - the minimum value of the interval the value is moved to
+/// * `to_span` - the length of the interval the value is moved to
+pub fn scale(value: i32, from_min: i32, from_span: u32, to_min: i32, to_span: u32) -> i32 {
+    if from_span == 0 {
+        return to_min;
+    }
+    to_min + (value - from_min) * to_span as i32 / from_span as i32
+}
+
+/// Return the centre of the given circle in doubled coordinates, so that the centre of a
+/// circle of odd diameter is a whole number and the circle tests need no float unit.
+/// # Arguments
+/// * `top_left` - the top left point of the bounding square of the circle
+/// * `size` - the diameter of the circle
+fn doubled_centre(top_left: Point, size: u32) -> Point {
+    Point::new(2 * top_left.x + size as i32, 2 * top_left.y + size as i32)
+}
+
+/// Return true if the given offset in doubled coordinates is no longer than the given
+/// distance in pixels, which is the same length in doubled coordinates as the sum of radii.
+/// # Arguments
+/// * `offset` - the offset in doubled coordinates
+/// * `distance` - the distance in pixels
+fn within(offset: Point, distance: u32) -> bool {
+    offset.x * offset.x + offset.y * offset.y <= (distance * distance) as i32
+}
+
+/// Return the fractions of a move along one axis at which the given moving interval starts
+/// and stops overlapping the given fixed interval. The fractions are infinite if the
+/// interval does not move.
+/// # Arguments
+/// * `min` - the minimum value of the moving interval at the start of the move
+/// * `delta` - the distance the moving interval moves
+/// * `size` - the length of the moving interval
+/// * `fixed_min` - the minimum value of the fixed interval
+/// * `fixed_size` - the length of the fixed interval
+fn sweep1d(min: f32, delta: f32, size: f32, fixed_min: f32, fixed_size: f32) -> (f32, f32) {
+    // the distances to move for the intervals to start and to stop overlapping
+    let (start, stop) = if delta < 0.0 {
+        (fixed_min + fixed_size - min, fixed_min - (min + size))
+    } else {
+        (fixed_min - (min + size), fixed_min + fixed_size - min)
+    };
+    if delta != 0.0 {
+        (start / delta, stop / delta)
+    } else if start < 0.0 && stop > 0.0 {
+        (f32::NEG_INFINITY, f32::INFINITY)
+    } else {
+        (f32::INFINITY, f32::NEG_INFINITY)
+    }
+}
+
+/// Return true if the given line segment from `start` to `end` intersects the given box
+/// from point `min` to point `max`. Segments that touch the box intersect.
+/// # Arguments
+/// * `start` - the start of the segment
+/// * `end` - the end of the segment
+/// * `min` - the top left corner of the box
+/// * `max` - the bottom right corner of the box
+fn segment_intersects_box(start: Point, end: Point, min: Point, max: Point) -> bool {
+    if start.x.max(end.x) < min.x
+        || start.x.min(end.x) > max.x
+        || start.y.max(end.y) < min.y
+        || start.y.min(end.y) > max.y
+    {
+        return false;
+    }
+    // the segment misses the box if every corner of the box is on the same side of it
+    let direction = end - start;
+    let sides = [min, Point::new(max.x, min.y), max, Point::new(min.x, max.y)]
+        .map(|corner| cross(direction, corner - start).signum());
+    !(sides.iter().all(|side| *side > 0) || sides.iter().all(|side| *side < 0))
+}
+
+/// Return true if the given point is no further than the given distance from the given line
+/// segment from `start` to `end`.
+/// # Arguments
+/// * `start` - the start of the segment
+/// * `end` - the end of the segment
+/// * `point` - the point to check
+/// * `distance` - the distance
+fn segment_within(start: Point, end: Point, point: Point, distance: u32) -> bool {
+    let direction = end - start;
+    let offset = point - start;
+    let along = dot(offset, direction);
+    let length = dot(direction, direction);
+    let distance = distance as i64 * distance as i64;
+    if along <= 0 {
+        dot(offset, offset) <= distance
+    } else if along >= length {
+        dot(point - end, point - end) <= distance
+    } else {
+        // the squared distance to the nearest point of the segment, times the squared length
+        dot(offset, offset) * length - along * along <= distance * length
+    }
+}
+
+/// Return the dot product of the given vectors.
+/// # Arguments
+/// * `a` - the first vector
+/// * `b` - the second vector
+fn dot(a: Point, b: Point) -> i64 {
+    a.x as i64 * b.x as i64 + a.y as i64 * b.y as i64
+}
+
+/// Return the cross product of the given vectors, positive if `b` is clockwise of `a` on the
+/// screen.
+/// # Arguments
+/// * `a` - the first vector
+/// * `b` - the second vector
+fn cross(a: Point, b: Point) -> i64 {
+    a.x as i64 * b.y as i64 - a.y as i64 * b.x as i64
+}
+
 /// Return true if the given two interval intersect.
 /// # Arguments
 /// * `min1` - interval 1 min value
@@ -51,9 +443,13 @@ fn contains1d(x: i32, min: i32, max: i32) -> bool {
 
 #[cfg(test)]
 mod tests {
-    use super::intersects;
+    use super::{
+        circle_intersects_rectangle, circles_intersect, contains_rectangle, intersects,
+        intersects_rectangle, round, scale, separation, sweep, swept_circle_intersects_rectangle,
+        Axis, Vector,
+    };
     use crate::math::intersects1d;
-    use embedded_graphics::prelude::Point;
+    use embedded_graphics::prelude::{Point, Size};
 
     #[test]
     fn shapes_on_top_of_each_other_intersects_test() {
@@ -140,4 +536,335 @@ mod tests {
         assert!(!intersects1d(10, 20, 30, 40));
         assert!(intersects1d(10, 20, -10, 40));
     }
+
+    #[test]
+    fn round_test() {
+        assert_eq!(round(0.0), 0);
+        assert_eq!(round(0.49), 0);
+        assert_eq!(round(0.5), 1);
+        assert_eq!(round(1.2), 1);
+        assert_eq!(round(-0.49), 0);
+        assert_eq!(round(-0.5), -1);
+        assert_eq!(round(-1.7), -2);
+    }
+
+    #[test]
+    fn vector_test() {
+        let vector = Vector::from(Point::new(3, -4)) + Vector::new(0.4, -0.6);
+        assert_eq!(vector, Vector::new(3.4, -4.6));
+        assert_eq!(vector.to_point(), Point::new(3, -5));
+        assert_eq!(vector - Vector::new(0.4, 0.4), Vector::new(3.0, -5.0));
+    }
+
+    #[test]
+    fn rectangles_intersects_test() {
+        let wide = Size::new(20, 2);
+        let tall = Size::new(2, 20);
+        assert!(intersects_rectangle(
+            Point::new(0, 10),
+            wide,
+            Point::new(10, 0),
+            tall
+        ));
+        assert!(intersects_rectangle(
+            Point::new(0, 20),
+            wide,
+            Point::new(10, 0),
+            tall
+        ));
+        assert!(!intersects_rectangle(
+            Point::new(0, 21),
+            wide,
+            Point::new(10, 0),
+            tall
+        ));
+        assert!(!intersects_rectangle(
+            Point::new(13, 10),
+            wide,
+            Point::new(10, 0),
+            tall
+        ));
+    }
+
+    #[test]
+    fn separation_test() {
+        let rect = Point::new(10, 10);
+        let rect_size = Size::new(10, 4);
+
+        // apart and touching
+        assert_eq!(separation(Vector::new(0.0, 0.0), 8, rect, rect_size), None);
+        assert_eq!(separation(Vector::new(2.0, 10.0), 8, rect, rect_size), None);
+        assert_eq!(
+            separation(Vector::new(20.0, 10.0), 8, rect, rect_size),
+            None
+        );
+        assert_eq!(separation(Vector::new(12.0, 2.0), 8, rect, rect_size), None);
+        assert_eq!(
+            separation(Vector::new(12.0, 14.0), 8, rect, rect_size),
+            None
+        );
+
+        // overlapping each side
+        assert_eq!(
+            separation(Vector::new(2.5, 9.0), 8, rect, rect_size),
+            Some(Vector::new(-0.5, 0.0))
+        );
+        assert_eq!(
+            separation(Vector::new(19.0, 9.0), 8, rect, rect_size),
+            Some(Vector::new(1.0, 0.0))
+        );
+        assert_eq!(
+            separation(Vector::new(12.0, 3.0), 8, rect, rect_size),
+            Some(Vector::new(0.0, -1.0))
+        );
+        assert_eq!(
+            separation(Vector::new(12.0, 13.5), 8, rect, rect_size),
+            Some(Vector::new(0.0, 0.5))
+        );
+
+        // overlapping a corner, the shallower overlap wins
+        assert_eq!(
+            separation(Vector::new(3.0, 5.0), 8, rect, rect_size),
+            Some(Vector::new(-1.0, 0.0))
+        );
+        assert_eq!(
+            separation(Vector::new(4.0, 3.0), 8, rect, rect_size),
+            Some(Vector::new(0.0, -1.0))
+        );
+    }
+
+    #[test]
+    fn contains_rectangle_test() {
+        let outer = Point::new(10, 20);
+        let outer_size = Size::new(30, 20);
+        let size = Size::new_equal(8);
+        // inside and sharing the edges of the outer rectangle
+        assert!(contains_rectangle(
+            outer,
+            outer_size,
+            Point::new(15, 25),
+            size
+        ));
+        assert!(contains_rectangle(outer, outer_size, outer, size));
+        assert!(contains_rectangle(
+            outer,
+            outer_size,
+            Point::new(32, 32),
+            size
+        ));
+        assert!(contains_rectangle(outer, outer_size, outer, outer_size));
+        // sticking out of each side of the outer rectangle
+        assert!(!contains_rectangle(
+            outer,
+            outer_size,
+            Point::new(9, 25),
+            size
+        ));
+        assert!(!contains_rectangle(
+            outer,
+            outer_size,
+            Point::new(33, 25),
+            size
+        ));
+        assert!(!contains_rectangle(
+            outer,
+            outer_size,
+            Point::new(15, 19),
+            size
+        ));
+        assert!(!contains_rectangle(
+            outer,
+            outer_size,
+            Point::new(15, 33),
+            size
+        ));
+        // larger than the outer rectangle
+        assert!(!contains_rectangle(
+            outer,
+            outer_size,
+            outer,
+            Size::new(31, 20)
+        ));
+    }
+
+    #[test]
+    fn scale_test() {
+        // the same interval keeps the value
+        assert_eq!(scale(37, 10, 54, 10, 54), 37);
+        // the ends of the interval map to the ends of the other interval
+        assert_eq!(scale(10, 10, 54, 10, 22), 10);
+        assert_eq!(scale(64, 10, 54, 10, 22), 32);
+        assert_eq!(scale(64, 10, 54, 0, 108), 108);
+        // values in between keep their relative position, rounded down
+        assert_eq!(scale(37, 10, 54, 10, 22), 21);
+        assert_eq!(scale(37, 10, 54, 20, 108), 74);
+        // an empty interval maps every value to its minimum
+        assert_eq!(scale(37, 10, 0, 5, 22), 5);
+    }
+
+    #[test]
+    fn circle_intersects_rectangle_test() {
+        let rect = Point::new(20, 20);
+        let rect_size = Size::new(10, 10);
+        let circle = |x, y| circle_intersects_rectangle(Point::new(x, y), 10, rect, rect_size);
+
+        // on top of each other and a circle around the rectangle
+        assert!(circle(20, 20));
+        assert!(circle_intersects_rectangle(
+            Point::new(0, 0),
+            100,
+            rect,
+            rect_size
+        ));
+
+        // touching and just apart on each side
+        assert!(circle(10, 22));
+        assert!(!circle(9, 22));
+        assert!(circle(30, 22));
+        assert!(!circle(31, 22));
+        assert!(circle(22, 10));
+        assert!(!circle(22, 9));
+        assert!(circle(22, 30));
+        assert!(!circle(22, 31));
+
+        // touching and just apart at a corner, where the bounding squares overlap
+        assert!(circle(12, 11));
+        assert!(!circle(12, 10));
+        assert!(!circle(11, 11));
+        assert!(!circle(29, 29));
+        assert!(intersects(Point::new(29, 29), 10, rect, 10));
+
+        // a circle of odd diameter
+        assert!(circle_intersects_rectangle(
+            Point::new(15, 22),
+            5,
+            rect,
+            rect_size
+        ));
+        assert!(!circle_intersects_rectangle(
+            Point::new(14, 22),
+            5,
+            rect,
+            rect_size
+        ));
+
+        // far from each other
+        assert!(!circle(100, 100));
+    }
+
+    #[test]
+    fn circles_intersect_test() {
+        let top_left = Point::new(0, 0);
+
+        // on top of each other
+        assert!(circles_intersect(top_left, 8, top_left, 8));
+
+        // touching and just apart side by side
+        assert!(circles_intersect(top_left, 8, Point::new(8, 0), 8));
+        assert!(!circles_intersect(top_left, 8, Point::new(9, 0), 8));
+        assert!(circles_intersect(top_left, 8, Point::new(8, 2), 4));
+        assert!(!circles_intersect(top_left, 8, Point::new(9, 2), 4));
+
+        // diagonally, where the bounding squares overlap
+        assert!(circles_intersect(top_left, 8, Point::new(5, 5), 8));
+        assert!(!circles_intersect(top_left, 8, Point::new(6, 6), 8));
+
+        // a point inside, on the edge of and outside of a circle
+        assert!(circles_intersect(top_left, 8, Point::new(4, 4), 0));
+        assert!(circles_intersect(top_left, 8, Point::new(8, 4), 0));
+        assert!(!circles_intersect(top_left, 8, Point::new(9, 4), 0));
+        assert!(!circles_intersect(top_left, 8, Point::new(7, 7), 0));
+
+        // far from each other
+        assert!(!circles_intersect(top_left, 8, Point::new(100, 100), 8));
+    }
+
+    #[test]
+    fn sweep_test() {
+        let rect = Point::new(10, 10);
+        let rect_size = Size::new(3, 20);
+        let sweep = |from, to| sweep(from, to, 8, rect, rect_size);
+
+        // moving into each side of the rectangle
+        assert_eq!(
+            sweep(Vector::new(0.0, 12.0), Vector::new(4.0, 12.0)),
+            Some((0.5, Axis::X))
+        );
+        assert_eq!(
+            sweep(Vector::new(15.0, 12.0), Vector::new(11.0, 12.0)),
+            Some((0.5, Axis::X))
+        );
+        assert_eq!(
+            sweep(Vector::new(8.0, 0.0), Vector::new(8.0, 4.0)),
+            Some((0.5, Axis::Y))
+        );
+        assert_eq!(
+            sweep(Vector::new(8.0, 32.0), Vector::new(8.0, 28.0)),
+            Some((0.5, Axis::Y))
+        );
+
+        // passing through the thin rectangle in a single move
+        assert_eq!(
+            sweep(Vector::new(0.0, 12.0), Vector::new(20.0, 12.0)),
+            Some((0.1, Axis::X))
+        );
+
+        // into a corner, along the axis that is entered last
+        assert_eq!(
+            sweep(Vector::new(0.0, 0.0), Vector::new(4.0, 8.0)),
+            Some((0.5, Axis::X))
+        );
+        assert_eq!(
+            sweep(Vector::new(4.0, 0.0), Vector::new(8.0, 4.0)),
+            Some((0.5, Axis::Y))
+        );
+
+        // stopping short, touching, sliding along and missing the rectangle
+        assert_eq!(sweep(Vector::new(0.0, 12.0), Vector::new(1.0, 12.0)), None);
+        assert_eq!(sweep(Vector::new(0.0, 12.0), Vector::new(2.0, 12.0)), None);
+        assert_eq!(sweep(Vector::new(2.0, 0.0), Vector::new(2.0, 30.0)), None);
+        assert_eq!(sweep(Vector::new(0.0, 0.0), Vector::new(30.0, 2.0)), None);
+        assert_eq!(sweep(Vector::new(0.0, 0.0), Vector::new(0.0, 0.0)), None);
+
+        // moving away or starting inside
+        assert_eq!(sweep(Vector::new(2.0, 12.0), Vector::new(0.0, 12.0)), None);
+        assert_eq!(sweep(Vector::new(8.0, 12.0), Vector::new(9.0, 12.0)), None);
+    }
+
+    #[test]
+    fn swept_circle_intersects_rectangle_test() {
+        let rect = Point::new(20, 20);
+        let rect_size = Size::new(2, 2);
+        let swept = |from: (i32, i32), to: (i32, i32)| {
+            swept_circle_intersects_rectangle(
+                Point::new(from.0, from.1),
+                Point::new(to.0, to.1),
+                4,
+                rect,
+                rect_size,
+            )
+        };
+
+        // at rest, the same as the circle test
+        assert!(swept((17, 19), (17, 19)));
+        assert!(!swept((15, 19), (15, 19)));
+
+        // ending on the rectangle
+        assert!(swept((0, 19), (17, 19)));
+
+        // passing over the rectangle in a single move
+        assert!(swept((0, 19), (40, 19)));
+        assert!(swept((19, 0), (19, 40)));
+        assert!(swept((0, 0), (40, 40)));
+
+        // passing a corner of the rectangle, touching and just apart
+        assert!(swept((0, 9), (32, 33)));
+        assert!(!swept((0, 10), (32, 34)));
+        assert!(swept((0, 16), (40, 16)));
+        assert!(!swept((0, 15), (40, 15)));
+
+        // passing by or moving away from the rectangle
+        assert!(!swept((0, 30), (40, 30)));
+        assert!(!swept((15, 19), (0, 19)));
+    }
 }
@@ -0,0 +1,474 @@
+//!
+//! This file keeps the low score of the SmallBall game across power cycles. The scores are
+//! stored in a log of CRC-checked records that is only ever appended to, spread over a few
+//! sectors of flash memory so that each sector is erased as rarely as possible. A record
+//! that is cut short by a power loss fails its CRC and is skipped, so the best score stored
+//! before it is kept. The offsets measured by the latest calibration of the mpu sensors are
+//! kept the same way in a sector of their own. Both work on any Storage, such as the flash
+//! of the RP2040 or the RamStorage used to test them on the host.
+//!
+
+use crate::{config::MAX_PLAYERS, save::crc32, smallball::Tilt};
+
+// the size of a score record: the sequence number, the score and the CRC of both
+const RECORD_SIZE: usize = 4 + 4 + 4;
+
+// the size of a calibration record: the pitch and roll offsets of each player and the CRC
+// of them
+const CALIBRATION_SIZE: usize = MAX_PLAYERS * (4 + 4) + 4;
+
+// the value of every byte of erased storage
+const ERASED: u8 = 0xff;
+
+/// Memory that behaves like NOR flash: it is erased a sector at a time, which sets every
+/// byte to 0xFF, and writing can only clear bits.
+pub trait Storage {
+    /// Return the size in bytes of a sector, the smallest part of the storage that can be
+    /// erased.
+    fn sector_size(&self) -> usize;
+
+    /// Return the number of sectors of the storage.
+    fn sectors(&self) -> usize;
+
+    /// Read bytes from the storage.
+    /// # Arguments
+    /// * `offset` - the offset in bytes of the first byte to read
+    /// * `bytes` - the buffer the bytes are read into
+    fn read(&mut self, offset: usize, bytes: &mut [u8]);
+
+    /// Write bytes to erased storage.
+    /// # Arguments
+    /// * `offset` - the offset in bytes of the first byte to write
+    /// * `bytes` - the bytes to write
+    fn write(&mut self, offset: usize, bytes: &[u8]);
+
+    /// Erase a sector of the storage.
+    /// # Arguments
+    /// * `sector` - the index of the sector to erase
+    fn erase(&mut self, sector: usize);
+}
+
+/// Storage in RAM that behaves like flash memory, to test code that uses Storage on the host.
+pub struct RamStorage<const SECTOR_SIZE: usize, const SECTORS: usize> {
+    /// the contents of each sector
+    bytes: [[u8; SECTOR_SIZE]; SECTORS],
+    /// the number of times each sector has been erased
+    erases: [u32; SECTORS],
+}
+
+impl<const SECTOR_SIZE: usize, const SECTORS: usize> RamStorage<SECTOR_SIZE, SECTORS> {
+    /// Return new storage that is erased.
+    pub fn new() -> Self {
+        RamStorage {
+            bytes: [[ERASED; SECTOR_SIZE]; SECTORS],
+            erases: [0; SECTORS],
+        }
+    }
+
+    /// Return the number of times the given sector has been erased.
+    /// # Arguments
+    /// * `sector` - the index of the sector
+    pub fn erases(&self, sector: usize) -> u32 {
+        self.erases[sector]
+    }
+}
+
+impl<const SECTOR_SIZE: usize, const SECTORS: usize> Default for RamStorage<SECTOR_SIZE, SECTORS> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const SECTOR_SIZE: usize, const SECTORS: usize> Storage for RamStorage<SECTOR_SIZE, SECTORS> {
+    fn sector_size(&self) -> usize {
+        SECTOR_SIZE
+    }
+
+    fn sectors(&self) -> usize {
+        SECTORS
+    }
+
+    fn read(&mut self, offset: usize, bytes: &mut [u8]) {
+        for (index, byte) in bytes.iter_mut().enumerate() {
+            let offset = offset + index;
+            *byte = self.bytes[offset / SECTOR_SIZE][offset % SECTOR_SIZE];
+        }
+    }
+
+    fn write(&mut self, offset: usize, bytes: &[u8]) {
+        for (index, byte) in bytes.iter().enumerate() {
+            let offset = offset + index;
+            self.bytes[offset / SECTOR_SIZE][offset % SECTOR_SIZE] &= byte;
+        }
+    }
+
+    fn erase(&mut self, sector: usize) {
+        self.bytes[sector] = [ERASED; SECTOR_SIZE];
+        self.erases[sector] += 1;
+    }
+}
+
+/// The log of the best scores of the game, kept in a Storage. Each record holds a score that
+/// beats every score before it. The records fill one sector after another and when the last
+/// sector is full the log wraps around to the first one, erasing the oldest records.
+pub struct ScoreLog<S: Storage> {
+    /// the storage the log is kept in
+    storage: S,
+    /// the best score in the log, if the log has any
+    best: Option<i32>,
+    /// the sequence number of the next record
+    sequence: u32,
+    /// the index of the sector the next record is appended to
+    sector: usize,
+    /// the index of the slot in the sector the next record is appended to
+    slot: usize,
+}
+
+impl<S: Storage> ScoreLog<S> {
+    /// Return the log kept in the given storage, after finding the best score and the end
+    /// of the log. Storage that holds no records is taken as an empty log.
+    /// # Arguments
+    /// * `storage` - the storage the log is kept in
+    pub fn new(mut storage: S) -> Self {
+        let slots = storage.sector_size() / RECORD_SIZE;
+        let mut best: Option<i32> = None;
+        let mut latest: Option<(u32, usize)> = None;
+        for sector in 0..storage.sectors() {
+            for slot in 0..slots {
+                let mut bytes = [0; RECORD_SIZE];
+                storage.read(
+                    sector * storage.sector_size() + slot * RECORD_SIZE,
+                    &mut bytes,
+                );
+                if let Some((sequence, score)) = Self::decode(&bytes) {
+                    best = Some(best.map_or(score, |best| best.min(score)));
+                    if latest.is_none_or(|(latest, _)| sequence > latest) {
+                        latest = Some((sequence, sector));
+                    }
+                }
+            }
+        }
+
+        // append after the last slot that is not erased in the sector of the latest record,
+        // or start at the first sector, which is erased first, if there are no records
+        let (sequence, sector, slot) = match latest {
+            Some((sequence, sector)) => {
+                let mut slot = slots;
+                while slot > 0 && Self::is_erased(&mut storage, sector, slot - 1) {
+                    slot -= 1;
+                }
+                (sequence.wrapping_add(1), sector, slot)
+            }
+            None => (0, storage.sectors() - 1, slots),
+        };
+        ScoreLog {
+            storage,
+            best,
+            sequence,
+            sector,
+            slot,
+        }
+    }
+
+    /// Return the best score in the log, if the log has any.
+    pub fn best(&self) -> Option<i32> {
+        self.best
+    }
+
+    /// Append the given score to the log if it beats the best score in the log and return
+    /// whether it was appended.
+    /// # Arguments
+    /// * `score` - the score of a completed game, lower is better
+    pub fn record(&mut self, score: i32) -> bool {
+        if self.best.is_some_and(|best| score >= best) {
+            return false;
+        }
+
+        // move on to the next sector when this one is full, erasing its old records
+        if self.slot == self.storage.sector_size() / RECORD_SIZE {
+            self.sector = (self.sector + 1) % self.storage.sectors();
+            self.slot = 0;
+            self.storage.erase(self.sector);
+        }
+
+        let mut bytes = [0; RECORD_SIZE];
+        bytes[..4].copy_from_slice(&self.sequence.to_le_bytes());
+        bytes[4..8].copy_from_slice(&score.to_le_bytes());
+        let crc = crc32(&bytes[..8]);
+        bytes[8..].copy_from_slice(&crc.to_le_bytes());
+        let offset = self.sector * self.storage.sector_size() + self.slot * RECORD_SIZE;
+        self.storage.write(offset, &bytes);
+
+        self.best = Some(score);
+        self.sequence = self.sequence.wrapping_add(1);
+        self.slot += 1;
+        true
+    }
+
+    /// Return the sequence number and the score of the given record, or None if it is
+    /// erased or fails its CRC.
+    /// # Arguments
+    /// * `bytes` - the record
+    fn decode(bytes: &[u8; RECORD_SIZE]) -> Option<(u32, i32)> {
+        let crc = u32::from_le_bytes([bytes[8], bytes[9], bytes[10], bytes[11]]);
+        if crc != crc32(&bytes[..8]) {
+            return None;
+        }
+        let sequence = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        let score = i32::from_le_bytes([bytes[4], bytes[5], bytes[6], bytes[7]]);
+        Some((sequence, score))
+    }
+
+    /// Return true if the given slot of the storage is erased.
+    /// # Arguments
+    /// * `storage` - the storage the log is kept in
+    /// * `sector` - the index of the sector of the slot
+    /// * `slot` - the index of the slot in the sector
+    fn is_erased(storage: &mut S, sector: usize, slot: usize) -> bool {
+        let mut bytes = [0; RECORD_SIZE];
+        storage.read(
+            sector * storage.sector_size() + slot * RECORD_SIZE,
+            &mut bytes,
+        );
+        bytes.iter().all(|byte| *byte == ERASED)
+    }
+}
+
+/// The offsets measured by the latest calibration of the mpu sensors, kept in the first
+/// sector of a Storage. Each calibration is appended to the sector, and the sector is erased
+/// when it is full, so a power loss while it is erased loses the offsets.
+pub struct CalibrationStore<S: Storage> {
+    /// the storage the offsets are kept in
+    storage: S,
+    /// the offsets of each player, if the storage holds any
+    offsets: Option<[Tilt; MAX_PLAYERS]>,
+    /// the index of the slot the next record is written to
+    slot: usize,
+}
+
+impl<S: Storage> CalibrationStore<S> {
+    /// Return the offsets kept in the given storage, after finding the latest record.
+    /// Storage that holds no records holds no offsets.
+    /// # Arguments
+    /// * `storage` - the storage the offsets are kept in
+    pub fn new(mut storage: S) -> Self {
+        let slots = storage.sector_size() / CALIBRATION_SIZE;
+        let mut offsets = None;
+        let mut slot = 0;
+        for index in 0..slots {
+            let mut bytes = [0; CALIBRATION_SIZE];
+            storage.read(index * CALIBRATION_SIZE, &mut bytes);
+            if bytes.iter().all(|byte| *byte == ERASED) {
+                continue;
+            }
+            // records that fail their CRC take up their slot but are skipped
+            slot = index + 1;
+            if let Some(record) = Self::decode(&bytes) {
+                offsets = Some(record);
+            }
+        }
+        CalibrationStore {
+            storage,
+            offsets,
+            slot,
+        }
+    }
+
+    /// Return the offsets of each player, if the storage holds any.
+    pub fn offsets(&self) -> Option<[Tilt; MAX_PLAYERS]> {
+        self.offsets
+    }
+
+    /// Keep the given offsets in the storage, in place of the offsets kept before.
+    /// # Arguments
+    /// * `offsets` - the offsets of each player
+    pub fn save(&mut self, offsets: [Tilt; MAX_PLAYERS]) {
+        if self.slot == self.storage.sector_size() / CALIBRATION_SIZE {
+            self.storage.erase(0);
+            self.slot = 0;
+        }
+
+        let mut bytes = [0; CALIBRATION_SIZE];
+        for (player, offset) in offsets.iter().enumerate() {
+            bytes[player * 8..player * 8 + 4].copy_from_slice(&offset.pitch.to_le_bytes());
+            bytes[player * 8 + 4..player * 8 + 8].copy_from_slice(&offset.roll.to_le_bytes());
+        }
+        let crc = crc32(&bytes[..CALIBRATION_SIZE - 4]);
+        bytes[CALIBRATION_SIZE - 4..].copy_from_slice(&crc.to_le_bytes());
+        self.storage.write(self.slot * CALIBRATION_SIZE, &bytes);
+
+        self.offsets = Some(offsets);
+        self.slot += 1;
+    }
+
+    /// Return the offsets of the given record, or None if it fails its CRC.
+    /// # Arguments
+    /// * `bytes` - the record
+    fn decode(bytes: &[u8; CALIBRATION_SIZE]) -> Option<[Tilt; MAX_PLAYERS]> {
+        let value = |index: usize| {
+            f32::from_le_bytes([
+                bytes[index],
+                bytes[index + 1],
+                bytes[index + 2],
+                bytes[index + 3],
+            ])
+        };
+        let end = CALIBRATION_SIZE - 4;
+        let crc = u32::from_le_bytes([bytes[end], bytes[end + 1], bytes[end + 2], bytes[end + 3]]);
+        if crc != crc32(&bytes[..end]) {
+            return None;
+        }
+        let mut offsets = [Tilt::default(); MAX_PLAYERS];
+        for (player, offset) in offsets.iter_mut().enumerate() {
+            *offset = Tilt::new(value(player * 8), value(player * 8 + 4));
+        }
+        Some(offsets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CalibrationStore, RamStorage, ScoreLog, Storage, CALIBRATION_SIZE, RECORD_SIZE};
+    use crate::smallball::Tilt;
+
+    // storage with room for four records in each of two sectors
+    type TestStorage = RamStorage<{ 4 * RECORD_SIZE }, 2>;
+
+    #[test]
+    fn ram_storage_test() {
+        let mut storage = RamStorage::<8, 2>::new();
+        assert_eq!((storage.sector_size(), storage.sectors()), (8, 2));
+
+        // writing only clears bits, like flash
+        storage.write(6, &[0x0f, 0xf0, 0x33]);
+        storage.write(7, &[0x3c]);
+        let mut bytes = [0; 4];
+        storage.read(5, &mut bytes);
+        assert_eq!(bytes, [0xff, 0x0f, 0x30, 0x33]);
+
+        // erasing a sector sets its bytes again and is counted
+        storage.erase(1);
+        storage.read(5, &mut bytes);
+        assert_eq!(bytes, [0xff, 0x0f, 0x30, 0xff]);
+        assert_eq!((storage.erases(0), storage.erases(1)), (0, 1));
+
+        // new storage is erased
+        let mut storage = RamStorage::<8, 2>::default();
+        storage.read(5, &mut bytes);
+        assert_eq!(bytes, [0xff; 4]);
+    }
+
+    #[test]
+    fn record_test() {
+        // GIVEN an empty log
+        let mut log = ScoreLog::new(TestStorage::new());
+        assert_eq!(log.best(), None);
+
+        // WHEN scores are recorded
+        // THEN only scores that beat the best score are appended
+        assert!(log.record(5000));
+        assert!(!log.record(6000));
+        assert!(!log.record(5000));
+        assert!(log.record(4000));
+        assert_eq!(log.best(), Some(4000));
+
+        // THEN the best score is found again after a power cycle
+        let mut log = ScoreLog::new(log.storage);
+        assert_eq!(log.best(), Some(4000));
+        assert!(!log.record(4500));
+        assert!(log.record(3000));
+        let log = ScoreLog::new(log.storage);
+        assert_eq!(log.best(), Some(3000));
+        assert_eq!((log.sector, log.slot), (0, 3));
+    }
+
+    #[test]
+    fn wear_leveling_test() {
+        // GIVEN a log that is reopened after every record
+        let mut storage = TestStorage::new();
+
+        // WHEN many records are appended
+        for score in (1..=20).rev() {
+            let mut log = ScoreLog::new(storage);
+            assert!(log.record(score * 100));
+            storage = log.storage;
+        }
+
+        // THEN the log wraps around the sectors and erases them in turn
+        let log = ScoreLog::new(storage);
+        assert_eq!(log.best(), Some(100));
+        assert_eq!(log.storage.erases(0), 3);
+        assert_eq!(log.storage.erases(1), 2);
+        assert_eq!((log.sector, log.slot), (0, 4));
+    }
+
+    #[test]
+    fn broken_record_test() {
+        // GIVEN a log whose latest record was cut short by a power loss
+        let mut log = ScoreLog::new(TestStorage::new());
+        assert!(log.record(5000));
+        assert!(log.record(4000));
+        log.storage.write(RECORD_SIZE + 5, &[0]);
+
+        // WHEN the log is opened
+        let mut log = ScoreLog::new(log.storage);
+
+        // THEN the best score before the broken record is kept
+        assert_eq!(log.best(), Some(5000));
+
+        // THEN the next record is appended after the broken record
+        assert!(log.record(4500));
+        assert_eq!((log.sector, log.slot), (0, 3));
+        let log = ScoreLog::new(log.storage);
+        assert_eq!(log.best(), Some(4500));
+    }
+
+    #[test]
+    fn foreign_data_test() {
+        // GIVEN storage that holds data that is not a log
+        let mut storage = TestStorage::new();
+        storage.write(0, &[0x12; 2 * 4 * RECORD_SIZE]);
+
+        // WHEN the log is opened
+        let mut log = ScoreLog::new(storage);
+
+        // THEN the log is empty and the first sector is erased for the first record
+        assert_eq!(log.best(), None);
+        assert!(log.record(5000));
+        assert_eq!(log.storage.erases(0), 1);
+        assert_eq!(ScoreLog::new(log.storage).best(), Some(5000));
+    }
+
+    #[test]
+    fn calibration_store_test() {
+        // GIVEN empty storage with room for three calibrations
+        let store = CalibrationStore::new(RamStorage::<{ 3 * CALIBRATION_SIZE }, 1>::new());
+        assert_eq!(store.offsets(), None);
+
+        // WHEN offsets are saved, more often than there is room for
+        // THEN the latest offsets are found again after a power cycle
+        let mut storage = store.storage;
+        for calibration in 1..=4 {
+            let offsets = [
+                Tilt::new(0.01 * calibration as f32, -0.02),
+                Tilt::new(0.0, 0.5),
+            ];
+            let mut store = CalibrationStore::new(storage);
+            store.save(offsets);
+            assert_eq!(store.offsets(), Some(offsets));
+            let store = CalibrationStore::new(store.storage);
+            assert_eq!(store.offsets(), Some(offsets));
+            storage = store.storage;
+        }
+        assert_eq!(storage.erases(0), 1);
+
+        // WHEN the next record is cut short by a power loss
+        storage.write(CALIBRATION_SIZE + 2, &[0]);
+
+        // THEN the offsets saved before it are found, and the next offsets go after it
+        let mut store = CalibrationStore::new(storage);
+        assert_eq!(store.offsets().unwrap()[0], Tilt::new(0.04, -0.02));
+        store.save([Tilt::default(); 2]);
+        assert_eq!(store.slot, 3);
+    }
+}
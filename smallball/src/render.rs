@@ -0,0 +1,920 @@
+//!
+//! This file draws the SmallBall game on any display with binary colors, such as the SSD1306
+//! OLED display of the RP2040 board or the MockDisplay of embedded-graphics on the host. Each
+//! mode of the game has its own screen, laid out on the display (see layout.rs) in the text
+//! and shape styles of a RenderStyle. The Renderer also animates the ball falling into a
+//! hole, which the game state does not keep track of.
+//!
+
+use crate::{
+    config::{
+        CALIBRATE_BAR_LOCATION, CALIBRATE_BAR_SIZE, CALIBRATE_LOCATION, CALIBRATE_OFFSET_LOCATIONS,
+        CALIBRATE_TEXT, ENTER_INITIALS_LOCATION, ENTER_INITIALS_TEXT, FALL_ANIMATION_MS, GAME_NAME,
+        GAME_NAME_LOCATION, GAME_OVER_LOCATION, GAME_OVER_LOW_SCORE_LOCATION,
+        GAME_OVER_PLAYER_LOCATIONS, GAME_OVER_SCORE_LOCATION, GAME_OVER_TEXT, GOALS_TEXT,
+        GOAL_NUMBER_OFFSET, HIGH_SCORES_LOCATION, HIGH_SCORES_TEXT, HIGH_SCORE_LOCATIONS,
+        INITIALS_CURSOR_OFFSET, INITIALS_CURSOR_SIZE, INITIALS_LOCATION, INITIALS_SPACING,
+        LEVEL_LOCATION, LEVEL_TEXT, LOW_SCORE_TEXT, PAR_LOCATION, PAR_TEXT, PLAYER_TEXT,
+        SCORE_LOCATION, SCORE_TEXT, SPLASH_SCREEN_SHAPE_LOCATIONS, SPLASH_SCREEN_SHAPE_SIZE,
+        SPLITS_LOCATION, SPLITS_TEXT, SPLIT_LOCATIONS, SPLIT_TEXT,
+    },
+    layout::Layout,
+    smallball::{GameEvent, Mode, State},
+    time::{Seconds, Time},
+};
+use core::fmt::{Arguments, Write};
+use embedded_graphics::{
+    mono_font::{
+        ascii::{FONT_4X6, FONT_6X10},
+        MonoTextStyle, MonoTextStyleBuilder,
+    },
+    pixelcolor::BinaryColor,
+    prelude::{DrawTarget, Point, Primitive, Size},
+    primitives::{Circle, PrimitiveStyle, PrimitiveStyleBuilder, Rectangle},
+    text::{Baseline, Text},
+    Drawable,
+};
+use heapless::String;
+
+/// The styles the screens of the game are drawn in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RenderStyle<'a> {
+    /// the style of text, such as the titles and the score
+    text: MonoTextStyle<'a, BinaryColor>,
+    /// the style of small text, such as the goal numbers and the high scores
+    small_text: MonoTextStyle<'a, BinaryColor>,
+    /// the style of outlined shapes, such as the screen outline and the goals
+    outline: PrimitiveStyle<BinaryColor>,
+    /// the style of filled shapes, such as the obstacles and the holes
+    fill: PrimitiveStyle<BinaryColor>,
+}
+
+impl Default for RenderStyle<'_> {
+    fn default() -> Self {
+        RenderStyle {
+            text: MonoTextStyleBuilder::new()
+                .font(&FONT_6X10)
+                .text_color(BinaryColor::On)
+                .build(),
+            small_text: MonoTextStyleBuilder::new()
+                .font(&FONT_4X6)
+                .text_color(BinaryColor::On)
+                .build(),
+            outline: PrimitiveStyleBuilder::new()
+                .stroke_width(1)
+                .stroke_color(BinaryColor::On)
+                .build(),
+            fill: PrimitiveStyleBuilder::new()
+                .fill_color(BinaryColor::On)
+                .build(),
+        }
+    }
+}
+
+impl<'a> RenderStyle<'a> {
+    /// Return this style with the given text style.
+    /// # Arguments
+    /// * `text` - the style of text, such as the titles and the score
+    pub fn with_text(mut self, text: MonoTextStyle<'a, BinaryColor>) -> Self {
+        self.text = text;
+        self
+    }
+
+    /// Return this style with the given small text style.
+    /// # Arguments
+    /// * `small_text` - the style of small text, such as the goal numbers and the high scores
+    pub fn with_small_text(mut self, small_text: MonoTextStyle<'a, BinaryColor>) -> Self {
+        self.small_text = small_text;
+        self
+    }
+
+    /// Return this style with the given style of outlined shapes.
+    /// # Arguments
+    /// * `outline` - the style of outlined shapes, such as the screen outline and the goals
+    pub fn with_outline(mut self, outline: PrimitiveStyle<BinaryColor>) -> Self {
+        self.outline = outline;
+        self
+    }
+
+    /// Return this style with the given style of filled shapes.
+    /// # Arguments
+    /// * `fill` - the style of filled shapes, such as the obstacles and the holes
+    pub fn with_fill(mut self, fill: PrimitiveStyle<BinaryColor>) -> Self {
+        self.fill = fill;
+        self
+    }
+}
+
+/// Draw the screen of the current mode of the game on the given display, in the default
+/// style and without animations. The display is not cleared first.
+/// # Arguments
+/// * `state` - the state of the game
+/// * `target` - the display, the screen is laid out on its whole area
+pub fn render<D, const N: usize, const E: usize>(
+    state: &State<N, E>,
+    target: &mut D,
+) -> Result<(), D::Error>
+where
+    D: DrawTarget<Color = BinaryColor>,
+{
+    Renderer::default().render(state, target)
+}
+
+/// The renderer of the screens of the game and of their animations.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Renderer<'a> {
+    /// the styles the screens are drawn in
+    style: RenderStyle<'a>,
+    /// the centre of the hole a ball fell into and the remaining time in milliseconds of the
+    /// animation of the ball falling into it
+    fall: Option<(Point, u32)>,
+}
+
+impl<'a> Renderer<'a> {
+    /// Return a renderer that draws in the given styles.
+    /// # Arguments
+    /// * `style` - the styles the screens are drawn in
+    pub fn new(style: RenderStyle<'a>) -> Self {
+        Renderer { style, fall: None }
+    }
+
+    /// Advance the animations by the given time and start the animations of the events of
+    /// the last update of the game.
+    /// # Arguments
+    /// * `state` - the state of the game, just updated
+    /// * `elapsed_ms` - the time in milliseconds the game was updated by
+    pub fn update<const N: usize, const E: usize>(&mut self, state: &State<N, E>, elapsed_ms: u32) {
+        self.fall = self
+            .fall
+            .filter(|(_, remaining_ms)| *remaining_ms > elapsed_ms)
+            .map(|(centre, remaining_ms)| (centre, remaining_ms - elapsed_ms));
+        for event in state.events() {
+            if let GameEvent::FellIntoHole { hazard, .. } = event {
+                let centre = Circle::new(hazard.location(), hazard.size()).center();
+                self.fall = Some((centre, FALL_ANIMATION_MS));
+            }
+        }
+    }
+
+    /// Draw the screen of the current mode of the game on the given display. The display is
+    /// not cleared first.
+    /// # Arguments
+    /// * `state` - the state of the game
+    /// * `target` - the display, the screen is laid out on its whole area
+    pub fn render<D, const N: usize, const E: usize>(
+        &self,
+        state: &State<N, E>,
+        target: &mut D,
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        let layout = Layout::new(target.bounding_box().size);
+        match state.mode() {
+            Mode::Intro => self.render_intro(&layout, target),
+            Mode::Play => self.render_play(state, target),
+            Mode::NextLevel => self.render_next_level(state, &layout, target),
+            Mode::Over
+                if state.players().len() == 1
+                    && state.mode_ms() >= state.config().over_page_ms() =>
+            {
+                self.render_splits(state, &layout, target)
+            }
+            Mode::Over => self.render_over(state, &layout, target),
+            Mode::Calibrate => self.render_calibrate(state, &layout, target),
+            Mode::EnterInitials => self.render_initials(state, &layout, target),
+            Mode::HighScores => self.render_high_scores(state, &layout, target),
+        }
+    }
+
+    /// Draw the intro screen, the name of the game over a few shapes.
+    fn render_intro<D>(&self, layout: &Layout, target: &mut D) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        self.render_title(GAME_NAME, GAME_NAME_LOCATION, layout, target)?;
+
+        // draw a square, a circle and a square
+        let [first, second, third] = SPLASH_SCREEN_SHAPE_LOCATIONS;
+        Rectangle::new(layout.location(first), SPLASH_SCREEN_SHAPE_SIZE)
+            .into_styled(self.style.outline)
+            .draw(target)?;
+        Circle::new(layout.location(second), SPLASH_SCREEN_SHAPE_SIZE.width)
+            .into_styled(self.style.outline)
+            .draw(target)?;
+        Rectangle::new(layout.location(third), SPLASH_SCREEN_SHAPE_SIZE)
+            .into_styled(self.style.outline)
+            .draw(target)
+    }
+
+    /// Draw the game play screen, the level with the balls of the players under the score.
+    fn render_play<D, const N: usize, const E: usize>(
+        &self,
+        state: &State<N, E>,
+        target: &mut D,
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        // draw the screen outline
+        Rectangle::new(state.screen_outline_top_left(), state.screen_outline_size())
+            .into_styled(self.style.outline)
+            .draw(target)?;
+
+        // draw the obstacles
+        for obstacle in state.obstacles() {
+            Rectangle::new(obstacle.location(), obstacle.size())
+                .into_styled(self.style.fill)
+                .draw(target)?;
+        }
+
+        // draw the holes
+        for hazard in state.hazards() {
+            Circle::new(hazard.location(), hazard.size())
+                .into_styled(self.style.fill)
+                .draw(target)?;
+        }
+
+        // draw the goals that are alive, when the goals have to be visited in order only the
+        // target goal is outlined and the others show their number
+        let target_goal = state.target_goal().map(|goal| goal.number());
+        for goal in state.goals_alive() {
+            if target_goal.is_none_or(|number| number == goal.number()) {
+                Rectangle::new(goal.location(), Size::new_equal(goal.size()))
+                    .into_styled(self.style.outline)
+                    .draw(target)?;
+            } else {
+                let number_text = format::<4>("", format_args!("{}", goal.number()));
+                self.render_text(
+                    &number_text,
+                    goal.location() + GOAL_NUMBER_OFFSET,
+                    self.style.small_text,
+                    target,
+                )?;
+            }
+        }
+
+        // draw the ball of each player, the ball of the second player is filled
+        for (index, player) in state.players().iter().enumerate() {
+            let ball_style = if index == 0 {
+                self.style.outline
+            } else {
+                self.style.fill
+            };
+            Circle::new(player.ball().location(), player.ball().size())
+                .into_styled(ball_style)
+                .draw(target)?;
+        }
+
+        // draw the ball falling into a hole as a shrinking circle
+        if let Some((centre, remaining_ms)) = self.fall {
+            let size = state.ball().size() * remaining_ms / FALL_ANIMATION_MS;
+            Circle::with_center(centre, size)
+                .into_styled(self.style.outline)
+                .draw(target)?;
+        }
+
+        // draw the score
+        let score_text = format::<20>(SCORE_TEXT, format_args!("{}", Time(state.score())));
+        self.render_text(&score_text, SCORE_LOCATION, self.style.text, target)
+    }
+
+    /// Draw the next level screen, the number of the level and its par score if it has one.
+    fn render_next_level<D, const N: usize, const E: usize>(
+        &self,
+        state: &State<N, E>,
+        layout: &Layout,
+        target: &mut D,
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        // draw the level number, counting levels from one
+        let level_text = format::<20>(LEVEL_TEXT, format_args!("{}", state.level() + 1));
+        self.render_title(&level_text, LEVEL_LOCATION, layout, target)?;
+
+        if let Some(par) = state.current_level().par() {
+            let par_text = format::<20>(PAR_TEXT, format_args!("{}", Time(par)));
+            self.render_text(
+                &par_text,
+                layout.location(PAR_LOCATION),
+                self.style.text,
+                target,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Draw the first page of the game over screen, the score and the low score of a single
+    /// player game or the goals and the time of the last goal of each player of a race.
+    fn render_over<D, const N: usize, const E: usize>(
+        &self,
+        state: &State<N, E>,
+        layout: &Layout,
+        target: &mut D,
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        self.render_title(GAME_OVER_TEXT, GAME_OVER_LOCATION, layout, target)?;
+
+        if state.players().len() == 1 {
+            let score_text = format::<20>(SCORE_TEXT, format_args!("{}", Time(state.score())));
+            self.render_text(
+                &score_text,
+                layout.location(GAME_OVER_SCORE_LOCATION),
+                self.style.text,
+                target,
+            )?;
+            let low_score_text =
+                format::<20>(LOW_SCORE_TEXT, format_args!("{}", Time(state.low_score())));
+            self.render_text(
+                &low_score_text,
+                layout.location(GAME_OVER_LOW_SCORE_LOCATION),
+                self.style.text,
+                target,
+            )?;
+        } else {
+            for (index, player) in state.players().iter().enumerate() {
+                let player_text = format::<24>(
+                    PLAYER_TEXT,
+                    format_args!(
+                        "{}: {}{}{}",
+                        index + 1,
+                        player.goals(),
+                        GOALS_TEXT,
+                        Time(player.time())
+                    ),
+                );
+                self.render_text(
+                    &player_text,
+                    layout.location(GAME_OVER_PLAYER_LOCATIONS[index]),
+                    self.style.text,
+                    target,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Draw the second page of the game over screen of a single player game, the split of
+    /// each goal compared with the best run if there is one.
+    fn render_splits<D, const N: usize, const E: usize>(
+        &self,
+        state: &State<N, E>,
+        layout: &Layout,
+        target: &mut D,
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        self.render_title(SPLITS_TEXT, SPLITS_LOCATION, layout, target)?;
+
+        let run = state.run();
+        for (index, split) in run.splits().iter().enumerate() {
+            let split_text = match run.delta(index) {
+                Some(delta) => format::<16>(
+                    SPLIT_TEXT,
+                    format_args!("{} {:+}", index + 1, Seconds(delta)),
+                ),
+                None => format::<16>(
+                    SPLIT_TEXT,
+                    format_args!("{} {}", index + 1, Seconds(*split)),
+                ),
+            };
+            self.render_text(
+                &split_text,
+                layout.location(SPLIT_LOCATIONS[index]),
+                self.style.small_text,
+                target,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Draw the calibration screen, a progress bar and the offsets measured so far.
+    fn render_calibrate<D, const N: usize, const E: usize>(
+        &self,
+        state: &State<N, E>,
+        layout: &Layout,
+        target: &mut D,
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        self.render_title(CALIBRATE_TEXT, CALIBRATE_LOCATION, layout, target)?;
+
+        // draw the progress bar, filled up to the time spent calibrating
+        let bar_location = layout.location(CALIBRATE_BAR_LOCATION);
+        Rectangle::new(bar_location, CALIBRATE_BAR_SIZE)
+            .into_styled(self.style.outline)
+            .draw(target)?;
+        let calibrate_ms = state.config().calibrate_ms().max(1);
+        let progress = state.mode_ms().min(calibrate_ms);
+        let filled = CALIBRATE_BAR_SIZE.width * progress / calibrate_ms;
+        Rectangle::new(bar_location, Size::new(filled, CALIBRATE_BAR_SIZE.height))
+            .into_styled(self.style.fill)
+            .draw(target)?;
+
+        // draw the offsets measured so far for each player
+        let offsets = state.offsets();
+        for (index, offset) in offsets.iter().enumerate().take(state.players().len()) {
+            let offset_text = format::<24>(
+                PLAYER_TEXT,
+                format_args!("{}: {:+.2} {:+.2}", index + 1, offset.pitch, offset.roll),
+            );
+            self.render_text(
+                &offset_text,
+                layout.location(CALIBRATE_OFFSET_LOCATIONS[index]),
+                self.style.small_text,
+                target,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Draw the screen of the initials entry, each letter of the initials and a line under
+    /// the letter that is chosen.
+    fn render_initials<D, const N: usize, const E: usize>(
+        &self,
+        state: &State<N, E>,
+        layout: &Layout,
+        target: &mut D,
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        self.render_title(ENTER_INITIALS_TEXT, ENTER_INITIALS_LOCATION, layout, target)?;
+
+        let initials = state.initials();
+        let location = layout.location(INITIALS_LOCATION);
+        for (index, letter) in initials.initials().as_str().char_indices() {
+            let letter_location = location + Point::new(index as i32 * INITIALS_SPACING, 0);
+            let letter_text = format::<4>("", format_args!("{}", letter));
+            self.render_text(&letter_text, letter_location, self.style.text, target)?;
+            if index == initials.position() {
+                Rectangle::new(
+                    letter_location + INITIALS_CURSOR_OFFSET,
+                    INITIALS_CURSOR_SIZE,
+                )
+                .into_styled(self.style.fill)
+                .draw(target)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Draw the high score table, the rank, the initials and the score of each high score.
+    fn render_high_scores<D, const N: usize, const E: usize>(
+        &self,
+        state: &State<N, E>,
+        layout: &Layout,
+        target: &mut D,
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        self.render_title(HIGH_SCORES_TEXT, HIGH_SCORES_LOCATION, layout, target)?;
+
+        for (index, entry) in state.high_scores().entries().iter().enumerate() {
+            let entry_text = format::<20>(
+                "",
+                format_args!(
+                    "{:>2} {} {}",
+                    index + 1,
+                    entry.initials().as_str(),
+                    Time(entry.score())
+                ),
+            );
+            self.render_text(
+                &entry_text,
+                layout.location(HIGH_SCORE_LOCATIONS[index]),
+                self.style.small_text,
+                target,
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Draw the outline of the whole screen and the given title, as the screens between the
+    /// games start.
+    /// # Arguments
+    /// * `title` - the title of the screen
+    /// * `location` - the location of the title on a display of the reference screen size
+    /// * `layout` - the layout of the game on the display
+    /// * `target` - the display
+    fn render_title<D>(
+        &self,
+        title: &str,
+        location: Point,
+        layout: &Layout,
+        target: &mut D,
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        layout
+            .full_screen_outline()
+            .into_styled(self.style.outline)
+            .draw(target)?;
+        self.render_text(title, layout.location(location), self.style.text, target)
+    }
+
+    /// Draw the given text with its top left corner at the given location.
+    /// # Arguments
+    /// * `text` - the text
+    /// * `location` - the location of the top left corner of the text on the display
+    /// * `style` - the style of the text
+    /// * `target` - the display
+    fn render_text<D>(
+        &self,
+        text: &str,
+        location: Point,
+        style: MonoTextStyle<'a, BinaryColor>,
+        target: &mut D,
+    ) -> Result<(), D::Error>
+    where
+        D: DrawTarget<Color = BinaryColor>,
+    {
+        Text::with_baseline(text, location, style, Baseline::Top).draw(target)?;
+        Ok(())
+    }
+}
+
+/// Return the given label followed by the given formatted text, cut short if it does not fit.
+/// # Arguments
+/// * `label` - the label, such as SCORE_TEXT
+/// * `args` - the formatted text
+fn format<const L: usize>(label: &str, args: Arguments) -> String<L> {
+    let mut text = String::new();
+    text.push_str(label).ok();
+    text.write_fmt(args).ok();
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{render, RenderStyle, Renderer};
+    use crate::{
+        config::{
+            GameConfig, CALIBRATE_BAR_LOCATION, CALIBRATE_MS, ENTER_INITIALS_LOCATION,
+            FALL_ANIMATION_MS, GAME_NAME_LOCATION, GAME_OVER_LOCATION,
+            GAME_OVER_LOW_SCORE_LOCATION, GOAL_NUMBER_OFFSET, HIGH_SCORES_LOCATION,
+            HIGH_SCORE_LOCATIONS, INITIALS_CURSOR_OFFSET, INITIALS_LOCATION, INITIALS_MS, INTRO_MS,
+            LEVEL_LOCATION, NEXT_LEVEL_MS, OVER_MS, OVER_PAGE_MS, PHYSICS_STEP_MS, SCORE_LOCATION,
+            SPLASH_SCREEN_SHAPE_LOCATIONS, SPLITS_LOCATION, SPLIT_LOCATIONS,
+        },
+        layout::Layout,
+        level::Level,
+        smallball::{GameEvent, GameVariant, Hazard, HazardEffect, Mode, State},
+    };
+    use embedded_graphics::{
+        mock_display::MockDisplay,
+        mono_font::{ascii::FONT_6X10, MonoTextStyle},
+        pixelcolor::BinaryColor,
+        prelude::{Point, PointsIter, Size},
+        primitives::{Circle, PrimitiveStyle, Rectangle},
+    };
+
+    /// The layout of the game on a mock display.
+    const LAYOUT: Layout = Layout::new(Size::new_equal(64));
+
+    /// Two levels whose goal is right next to where the ball starts, so that each is over
+    /// as soon as the ball is rolled into the goal.
+    const GOALS: [Point; 1] = [Point::new(80, 30)];
+    const QUICK_LEVEL: Level = Level::new(&GOALS, Point::new(60, 30), Some(2000));
+    const QUICK_LEVELS: [Level; 2] = [QUICK_LEVEL; 2];
+
+    /// A level of two goals, visited in sequence.
+    const SEQUENCE_GOALS: [Point; 2] = [Point::new(20, 20), Point::new(100, 40)];
+    const SEQUENCE_LEVEL: [Level; 1] = [Level::new(&SEQUENCE_GOALS, Point::new(60, 30), None)];
+
+    /// A level whose ball starts in a hole.
+    const HAZARDS: [Hazard; 1] = [Hazard::new(Point::new(10, 20), 20, HazardEffect::Reset)];
+    const HOLE_LEVEL: [Level; 1] =
+        [Level::new(&[Point::new(100, 40)], Point::new(16, 26), None).with_hazards(&HAZARDS)];
+
+    /// A level whose ball starts in a hole next to the goal, followed by a level without holes.
+    const EDGE_GOALS: [Point; 1] = [Point::new(31, 30)];
+    const EDGE_HAZARDS: [Hazard; 1] = [Hazard::new(Point::new(18, 28), 12, HazardEffect::Reset)];
+    const EDGE_LEVELS: [Level; 2] = [
+        Level::new(&EDGE_GOALS, Point::new(20, 30), None).with_hazards(&EDGE_HAZARDS),
+        Level::new(&[Point::new(100, 40)], Point::new(20, 30), None),
+    ];
+
+    /// Return a new game laid out on a mock display, playing the given levels.
+    fn game(levels: &'static [Level]) -> State {
+        let config = GameConfig::builder()
+            .with_layout(LAYOUT)
+            .with_levels(levels)
+            .build()
+            .unwrap();
+        State::new_with_config(config)
+    }
+
+    /// Return the screen of the game drawn by the given renderer on a mock display, which
+    /// like the display of the board ignores what is drawn outside of it.
+    fn draw(renderer: &Renderer, state: &State) -> MockDisplay<BinaryColor> {
+        let mut display = MockDisplay::new();
+        display.set_allow_overdraw(true);
+        display.set_allow_out_of_bounds_drawing(true);
+        renderer.render(state, &mut display).unwrap();
+        display
+    }
+
+    /// Roll the balls right, into the goal next to them, until the level is over.
+    fn roll_to_goal(state: &mut State) {
+        while *state.mode() == Mode::Play {
+            state.update(PHYSICS_STEP_MS, &0.0, &0.5);
+        }
+    }
+
+    /// Return whether any pixel of the given area is on.
+    fn drawn(display: &MockDisplay<BinaryColor>, top_left: Point, size: Size) -> bool {
+        Rectangle::new(top_left, size)
+            .points()
+            .any(|point| display.get_pixel(point) == Some(BinaryColor::On))
+    }
+
+    /// Return whether the first letter of a title is drawn at the given location on a
+    /// display of the reference screen size.
+    fn title_drawn(display: &MockDisplay<BinaryColor>, location: Point) -> bool {
+        drawn(display, LAYOUT.location(location), Size::new(6, 10))
+    }
+
+    #[test]
+    fn intro_render_test() {
+        // GIVEN a game in intro mode
+        let state = game(&QUICK_LEVELS);
+        let display = draw(&Renderer::default(), &state);
+
+        // THEN the outline of the screen, the name of the game and the shapes are drawn
+        assert_eq!(display.get_pixel(Point::zero()), Some(BinaryColor::On));
+        assert_eq!(display.get_pixel(Point::new(62, 62)), Some(BinaryColor::On));
+        assert!(title_drawn(&display, GAME_NAME_LOCATION));
+        let square = LAYOUT.location(SPLASH_SCREEN_SHAPE_LOCATIONS[0]);
+        assert_eq!(display.get_pixel(square), Some(BinaryColor::On));
+        assert_eq!(display.get_pixel(square + Point::new(2, 2)), None);
+
+        // THEN the screen drawn in the default style is the same
+        let mut other = MockDisplay::new();
+        other.set_allow_out_of_bounds_drawing(true);
+        other.set_allow_overdraw(true);
+        render(&state, &mut other).unwrap();
+        assert_eq!(other, display);
+    }
+
+    #[test]
+    fn render_style_test() {
+        // GIVEN a style with filled outlines and dark text
+        let dark = MonoTextStyle::new(&FONT_6X10, BinaryColor::Off);
+        let style = RenderStyle::default()
+            .with_outline(PrimitiveStyle::with_fill(BinaryColor::On))
+            .with_text(dark)
+            .with_small_text(dark)
+            .with_fill(PrimitiveStyle::with_stroke(BinaryColor::On, 1));
+        let state = game(&QUICK_LEVELS);
+        let display = draw(&Renderer::new(style), &state);
+
+        // THEN the screen is filled and the title is cut out of it
+        assert_eq!(display.get_pixel(Point::new(30, 60)), Some(BinaryColor::On));
+        let title = Rectangle::new(LAYOUT.location(GAME_NAME_LOCATION), Size::new(6, 10));
+        assert!(title
+            .points()
+            .any(|point| display.get_pixel(point) == Some(BinaryColor::Off)));
+    }
+
+    #[test]
+    fn play_render_test() {
+        // GIVEN a game in play mode
+        let mut state = game(&HOLE_LEVEL);
+        state.update(INTRO_MS, &0.0, &0.0);
+        assert_eq!(*state.mode(), Mode::Play);
+        let display = draw(&Renderer::default(), &state);
+
+        // THEN the score, the hole, the goal and the ball are drawn in the outline
+        assert!(drawn(&display, SCORE_LOCATION, Size::new(6, 10)));
+        let outline = state.screen_outline_top_left();
+        assert_eq!(display.get_pixel(outline), Some(BinaryColor::On));
+        let hole = state.hazards()[0].location() + Point::new_equal(10);
+        assert_eq!(display.get_pixel(hole), Some(BinaryColor::On));
+        let goal = state.goals_alive()[0].location();
+        assert_eq!(display.get_pixel(goal), Some(BinaryColor::On));
+        let ball = state.ball();
+        assert!(drawn(
+            &display,
+            ball.location(),
+            Size::new_equal(ball.size())
+        ));
+
+        // GIVEN a game whose goals are visited in sequence
+        let mut state = game(&SEQUENCE_LEVEL).with_variant(GameVariant::Sequence);
+        state.update(INTRO_MS, &0.0, &0.0);
+        let display = draw(&Renderer::default(), &state);
+
+        // THEN the target goal is outlined and the other goal shows its number
+        let goals = state.goals_alive();
+        assert_eq!(
+            display.get_pixel(goals[0].location()),
+            Some(BinaryColor::On)
+        );
+        let number = goals[1].location() + GOAL_NUMBER_OFFSET;
+        assert_eq!(display.get_pixel(goals[1].location()), None);
+        assert!(drawn(&display, number, Size::new(4, 6)));
+    }
+
+    #[test]
+    fn fall_animation_test() {
+        // GIVEN a game whose ball falls into a hole as the level starts, with holes drawn
+        // as outlines so that the falling ball can be seen in them
+        let mut state = game(&HOLE_LEVEL);
+        let style =
+            RenderStyle::default().with_fill(PrimitiveStyle::with_stroke(BinaryColor::On, 1));
+        let mut renderer = Renderer::new(style);
+        state.update(INTRO_MS, &0.0, &0.0);
+        state.update(PHYSICS_STEP_MS, &0.0, &0.0);
+        assert!(matches!(
+            state.events(),
+            [GameEvent::FellIntoHole { hazard, .. }, ..] if *hazard == state.hazards()[0]
+        ));
+
+        // WHEN the renderer is updated with the game
+        let still = draw(&renderer, &state);
+        renderer.update(&state, PHYSICS_STEP_MS);
+
+        // THEN the ball falls into the centre of the hole
+        let hazard = state.hazards()[0];
+        let centre = Circle::new(hazard.location(), hazard.size()).center();
+        assert_eq!(renderer.fall, Some((centre, FALL_ANIMATION_MS)));
+        assert_ne!(draw(&renderer, &state), still);
+
+        // THEN the animation shrinks the ball and ends
+        state.update(PHYSICS_STEP_MS, &0.0, &0.0);
+        renderer.update(&state, FALL_ANIMATION_MS / 2);
+        assert_eq!(renderer.fall, Some((centre, FALL_ANIMATION_MS / 2)));
+        renderer.update(&state, FALL_ANIMATION_MS / 2);
+        assert_eq!(renderer.fall, None);
+        assert_eq!(draw(&renderer, &state), still);
+    }
+
+    #[test]
+    fn fall_at_end_of_level_test() {
+        // GIVEN a game whose ball falls into a hole in the step it reaches the last goal of a
+        // level that is followed by a level without holes
+        let config = GameConfig::builder()
+            .with_levels(&EDGE_LEVELS)
+            .with_ball_acceleration(5.0)
+            .with_ball_max_speed(5.0)
+            .build()
+            .unwrap();
+        let mut state = State::new_with_config(config);
+        let hazard = state.hazards()[0];
+        state.update(INTRO_MS, &0.0, &0.0);
+        state.update(PHYSICS_STEP_MS, &0.0, &1.0);
+        assert!(matches!(
+            state.events(),
+            [
+                GameEvent::GoalReached { .. },
+                GameEvent::FellIntoHole { .. },
+                GameEvent::ModeChanged {
+                    mode: Mode::NextLevel
+                }
+            ]
+        ));
+        assert!(state.hazards().is_empty());
+
+        // WHEN the renderer is updated with the game
+        let mut renderer = Renderer::default();
+        renderer.update(&state, PHYSICS_STEP_MS);
+
+        // THEN the ball falls into the centre of the hole of the level that is over
+        let centre = Circle::new(hazard.location(), hazard.size()).center();
+        assert_eq!(renderer.fall, Some((centre, FALL_ANIMATION_MS)));
+    }
+
+    #[test]
+    fn screens_between_games_test() {
+        let renderer = Renderer::default();
+
+        // GIVEN a game whose first level is over
+        let mut state = game(&QUICK_LEVELS);
+        state.update(INTRO_MS, &0.0, &0.0);
+        roll_to_goal(&mut state);
+        assert_eq!(*state.mode(), Mode::NextLevel);
+
+        // THEN the next level is shown
+        let display = draw(&renderer, &state);
+        assert!(title_drawn(&display, LEVEL_LOCATION));
+
+        // WHEN the last level is over
+        state.update(NEXT_LEVEL_MS, &0.0, &0.0);
+        roll_to_goal(&mut state);
+        assert_eq!(*state.mode(), Mode::Over);
+
+        // THEN the game over screen is shown with the low score and then the splits
+        let split = LAYOUT.location(SPLIT_LOCATIONS[0]);
+        let low_score = LAYOUT.location(GAME_OVER_LOW_SCORE_LOCATION);
+        let display = draw(&renderer, &state);
+        assert!(title_drawn(&display, GAME_OVER_LOCATION));
+        assert!(drawn(&display, low_score, Size::new(6, 10)));
+        assert!(!drawn(&display, split, Size::new(4, 6)));
+        state.update(OVER_PAGE_MS, &0.0, &0.0);
+        let display = draw(&renderer, &state);
+        assert!(title_drawn(&display, SPLITS_LOCATION));
+        assert!(drawn(&display, split, Size::new(4, 6)));
+        assert!(!drawn(&display, low_score, Size::new(6, 10)));
+
+        // WHEN the game over screen is over
+        state.update(OVER_MS - OVER_PAGE_MS, &0.0, &0.0);
+        assert_eq!(*state.mode(), Mode::EnterInitials);
+
+        // THEN the initials are entered with a cursor under the first letter
+        let display = draw(&renderer, &state);
+        assert!(title_drawn(&display, ENTER_INITIALS_LOCATION));
+        let cursor = LAYOUT.location(INITIALS_LOCATION) + INITIALS_CURSOR_OFFSET;
+        assert_eq!(display.get_pixel(cursor), Some(BinaryColor::On));
+
+        // THEN the high scores are shown when the initials are entered
+        state.update(INITIALS_MS, &0.0, &0.0);
+        assert_eq!(*state.mode(), Mode::HighScores);
+        let display = draw(&renderer, &state);
+        assert!(title_drawn(&display, HIGH_SCORES_LOCATION));
+        let entry = LAYOUT.location(HIGH_SCORE_LOCATIONS[0]);
+        assert!(drawn(&display, entry, Size::new(40, 6)));
+        assert!(!drawn(
+            &display,
+            LAYOUT.location(HIGH_SCORE_LOCATIONS[1]),
+            Size::new(40, 6)
+        ));
+    }
+
+    #[test]
+    fn race_over_render_test() {
+        // GIVEN a race that is over
+        let mut state = game(&QUICK_LEVELS[..1]).with_players(2);
+        state.update(INTRO_MS, &0.0, &0.0);
+        roll_to_goal(&mut state);
+        assert_eq!(*state.mode(), Mode::Over);
+
+        // THEN the game over screen of a race has no splits page
+        let display = draw(&Renderer::default(), &state);
+        assert!(title_drawn(&display, GAME_OVER_LOCATION));
+        state.update(OVER_PAGE_MS, &0.0, &0.0);
+        assert_eq!(draw(&Renderer::default(), &state), display);
+    }
+
+    #[test]
+    fn short_over_render_test() {
+        // GIVEN a game that is over with a game over screen shorter than the default page
+        let config = GameConfig::builder()
+            .with_layout(LAYOUT)
+            .with_levels(&QUICK_LEVELS[..1])
+            .with_screen_times(INTRO_MS, NEXT_LEVEL_MS, OVER_PAGE_MS / 2)
+            .with_over_page_ms(OVER_PAGE_MS / 4)
+            .build()
+            .unwrap();
+        let mut state = State::new_with_config(config);
+        state.update(INTRO_MS, &0.0, &0.0);
+        roll_to_goal(&mut state);
+        assert_eq!(*state.mode(), Mode::Over);
+
+        // THEN the splits are shown after the configured page time
+        let display = draw(&Renderer::default(), &state);
+        assert!(title_drawn(&display, GAME_OVER_LOCATION));
+        state.update(OVER_PAGE_MS / 4, &0.0, &0.0);
+        assert_eq!(*state.mode(), Mode::Over);
+        let display = draw(&Renderer::default(), &state);
+        assert!(title_drawn(&display, SPLITS_LOCATION));
+    }
+
+    #[test]
+    fn calibrate_render_test() {
+        // GIVEN a game half way through a calibration
+        let mut state = game(&QUICK_LEVELS);
+        state.calibrate();
+        state.update(CALIBRATE_MS / 2, &0.0, &0.0);
+        let display = draw(&Renderer::default(), &state);
+
+        // THEN the progress bar is half filled
+        let bar = LAYOUT.location(CALIBRATE_BAR_LOCATION);
+        assert_eq!(
+            display.get_pixel(bar + Point::new(25, 4)),
+            Some(BinaryColor::On)
+        );
+        assert_eq!(display.get_pixel(bar + Point::new(53, 4)), None);
+
+        // GIVEN a game half way through a shorter calibration
+        let config = GameConfig::builder()
+            .with_layout(LAYOUT)
+            .with_levels(&QUICK_LEVELS)
+            .with_calibration_times(1000, 200)
+            .build()
+            .unwrap();
+        let mut state = State::new_with_config(config);
+        state.calibrate();
+        state.update(500, &0.0, &0.0);
+
+        // THEN the progress bar is half filled too
+        assert_eq!(draw(&Renderer::default(), &state), display);
+    }
+}
@@ -0,0 +1,98 @@
+//!
+//! A small pseudo random number generator that does not need the standard library, used
+//! to generate goal layouts from a seed.
+//!
+
+/// A xorshift pseudo random number generator. The same seed always produces the same
+/// sequence of numbers.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rng {
+    /// the current state of the generator, never zero
+    state: u32,
+}
+
+impl Rng {
+    /// Return a new random number generator.
+    /// # Arguments
+    /// * `seed` - the seed of the sequence, a zero seed is replaced by a fixed non zero seed
+    ///   because the generator would only ever produce zeros
+    pub fn new(seed: u32) -> Self {
+        Rng {
+            state: if seed == 0 { 0x2545_f491 } else { seed },
+        }
+    }
+
+    /// Return the current state of the generator, a generator made with the state as its
+    /// seed continues the same sequence.
+    pub fn state(&self) -> u32 {
+        self.state
+    }
+
+    /// Return the next number of the sequence.
+    pub fn next_u32(&mut self) -> u32 {
+        let mut x = self.state;
+        x ^= x << 13;
+        x ^= x >> 17;
+        x ^= x << 5;
+        self.state = x;
+        x
+    }
+
+    /// Return the next number of the sequence in the given interval.
+    /// # Arguments
+    /// * `min` - interval minimum value
+    /// * `max` - interval maximum value, included in the interval
+    pub fn range(&mut self, min: i32, max: i32) -> i32 {
+        let span = (max - min) as u32 + 1;
+        min + (self.next_u32() % span) as i32
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Rng;
+
+    #[test]
+    fn same_seed_same_sequence_test() {
+        let mut rng1 = Rng::new(1234);
+        let mut rng2 = Rng::new(1234);
+        for _ in 0..100 {
+            assert_eq!(rng1.next_u32(), rng2.next_u32());
+        }
+    }
+
+    #[test]
+    fn different_seed_different_sequence_test() {
+        let mut rng1 = Rng::new(1234);
+        let mut rng2 = Rng::new(4321);
+        assert_ne!(rng1.next_u32(), rng2.next_u32());
+    }
+
+    #[test]
+    fn state_continues_sequence_test() {
+        let mut rng1 = Rng::new(1234);
+        rng1.next_u32();
+        let mut rng2 = Rng::new(rng1.state());
+        assert_eq!(rng1.next_u32(), rng2.next_u32());
+    }
+
+    #[test]
+    fn zero_seed_test() {
+        let mut rng = Rng::new(0);
+        assert_ne!(rng.next_u32(), 0);
+        assert_ne!(rng.next_u32(), 0);
+    }
+
+    #[test]
+    fn range_test() {
+        let mut rng = Rng::new(42);
+        let mut seen = [false; 5];
+        for _ in 0..1000 {
+            let x = rng.range(-2, 2);
+            assert!((-2..=2).contains(&x));
+            seen[(x + 2) as usize] = true;
+        }
+        assert!(seen.iter().all(|seen| *seen));
+        assert_eq!(rng.range(7, 7), 7);
+    }
+}
@@ -0,0 +1,342 @@
+//!
+//! This file defines the binary format a SmallBall game is saved in, so that a game can be
+//! snapshot mid-play and restored later, for example after a brown-out. A save starts with
+//! a header of the magic bytes, the version of the format and the length of the payload,
+//! and it ends with a CRC-32 of everything before it. All numbers are little endian. The
+//! format of a version never changes, a new version is added instead.
+//!
+
+use crate::config::{HIGH_SCORES, INITIALS, MAX_SPLITS};
+
+// the magic bytes every save starts with
+const MAGIC: [u8; 2] = *b"SB";
+
+// the version of the save format that is written
+pub const SAVE_VERSION: u8 = 3;
+
+// the size of the header: the magic bytes, the version and the length of the payload
+const HEADER_SIZE: usize = MAGIC.len() + 1 + 2;
+
+// the size of the CRC at the end of a save
+const CRC_SIZE: usize = 4;
+
+// the size of the part of the payload of version 1 that does not depend on the number of
+// players and goals
+const STATE_SIZE: usize = 1 + 4 + 1 + 1 + 4 + 4 + 4 + 1 + 4 + 1 + 1;
+
+// the size of each player in the payload of version 1
+const PLAYER_SIZE: usize = 4 + 1 + 4 + 1 + 4 * 4;
+
+// the size of each goal in the payload of version 1
+const GOAL_SIZE: usize = 2 + 2 + 1 + 1;
+
+// the size of the part of the payload that version 2 adds: the number of high scores, each
+// high score and the initials being entered
+const HIGH_SCORES_SIZE: usize = 1 + HIGH_SCORES * (INITIALS + 4) + INITIALS + 1;
+
+// the size of the time each goal was reached, which version 3 adds for each goal
+const GOAL_TIME_SIZE: usize = 4;
+
+// the size of the rest of the part of the payload that version 3 adds: the splits of the
+// run, of the best run it is compared with and of the run of the low score
+const SPLITS_SIZE: usize = 3 * (1 + MAX_SPLITS * 4);
+
+/// Return the largest size in bytes of a save of a game with the given goal capacity and
+/// number of players.
+/// # Arguments
+/// * `goals` - the goal capacity of the game
+/// * `players` - the number of players
+pub const fn save_size(goals: usize, players: usize) -> usize {
+    HEADER_SIZE
+        + STATE_SIZE
+        + players * PLAYER_SIZE
+        + goals * (GOAL_SIZE + GOAL_TIME_SIZE)
+        + HIGH_SCORES_SIZE
+        + SPLITS_SIZE
+        + CRC_SIZE
+}
+
+/// The reason a game cannot be saved or restored.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SaveError {
+    /// The buffer is too small for the save
+    BufferTooSmall,
+    /// The data does not start with the magic bytes of a save
+    NotASave,
+    /// The save was written in a version of the format that cannot be read
+    UnsupportedVersion { version: u8 },
+    /// The save is shorter than its header says
+    Truncated,
+    /// The CRC of the save does not match its contents
+    BadCrc,
+    /// The save does not describe a game that can be played with the configuration
+    InvalidData,
+}
+
+/// Writes the numbers of a save into a buffer.
+pub(crate) struct Writer<'a> {
+    /// the buffer the save is written into
+    buffer: &'a mut [u8],
+    /// the number of bytes written so far
+    position: usize,
+}
+
+impl<'a> Writer<'a> {
+    /// Return a writer that writes the payload of a save into the given buffer, after
+    /// room for the header.
+    /// # Arguments
+    /// * `buffer` - the buffer the save is written into
+    pub(crate) fn new(buffer: &'a mut [u8]) -> Self {
+        Writer {
+            buffer,
+            position: HEADER_SIZE,
+        }
+    }
+
+    /// Write the given bytes.
+    /// # Arguments
+    /// * `bytes` - the bytes to write
+    fn put(&mut self, bytes: &[u8]) -> Result<(), SaveError> {
+        let end = self.position + bytes.len();
+        self.buffer
+            .get_mut(self.position..end)
+            .ok_or(SaveError::BufferTooSmall)?
+            .copy_from_slice(bytes);
+        self.position = end;
+        Ok(())
+    }
+
+    /// Write a byte.
+    /// # Arguments
+    /// * `value` - the byte to write
+    pub(crate) fn put_u8(&mut self, value: u8) -> Result<(), SaveError> {
+        self.put(&[value])
+    }
+
+    /// Write a 16 bit signed number.
+    /// # Arguments
+    /// * `value` - the number to write
+    pub(crate) fn put_i16(&mut self, value: i16) -> Result<(), SaveError> {
+        self.put(&value.to_le_bytes())
+    }
+
+    /// Write a 32 bit unsigned number.
+    /// # Arguments
+    /// * `value` - the number to write
+    pub(crate) fn put_u32(&mut self, value: u32) -> Result<(), SaveError> {
+        self.put(&value.to_le_bytes())
+    }
+
+    /// Write a 32 bit signed number.
+    /// # Arguments
+    /// * `value` - the number to write
+    pub(crate) fn put_i32(&mut self, value: i32) -> Result<(), SaveError> {
+        self.put(&value.to_le_bytes())
+    }
+
+    /// Write a 32 bit float.
+    /// # Arguments
+    /// * `value` - the number to write
+    pub(crate) fn put_f32(&mut self, value: f32) -> Result<(), SaveError> {
+        self.put_u32(value.to_bits())
+    }
+
+    /// Write the header and the CRC around the payload written so far and return the size
+    /// of the save in bytes.
+    pub(crate) fn finish(mut self) -> Result<usize, SaveError> {
+        let length = self.position - HEADER_SIZE;
+        let length = u16::try_from(length).map_err(|_| SaveError::BufferTooSmall)?;
+        let end = self.position;
+        self.position = 0;
+        self.put(&MAGIC)?;
+        self.put_u8(SAVE_VERSION)?;
+        self.put(&length.to_le_bytes())?;
+        self.position = end;
+        let crc = crc32(&self.buffer[..end]);
+        self.put_u32(crc)?;
+        Ok(self.position)
+    }
+}
+
+/// Reads the numbers of a save from a buffer.
+pub(crate) struct Reader<'a> {
+    /// the payload of the save
+    payload: &'a [u8],
+    /// the number of bytes read so far
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    /// Return a reader of the payload of the given save and the version of the format it
+    /// was written in, after checking its header and its CRC.
+    /// # Arguments
+    /// * `bytes` - the save, which can be followed by other data
+    pub(crate) fn new(bytes: &'a [u8]) -> Result<(Self, u8), SaveError> {
+        if bytes.len() < HEADER_SIZE {
+            return Err(SaveError::Truncated);
+        }
+        if bytes[..MAGIC.len()] != MAGIC {
+            return Err(SaveError::NotASave);
+        }
+        let version = bytes[MAGIC.len()];
+        let length = u16::from_le_bytes([bytes[3], bytes[4]]) as usize;
+        let end = HEADER_SIZE + length;
+        if bytes.len() < end + CRC_SIZE {
+            return Err(SaveError::Truncated);
+        }
+        let crc = u32::from_le_bytes([bytes[end], bytes[end + 1], bytes[end + 2], bytes[end + 3]]);
+        if crc != crc32(&bytes[..end]) {
+            return Err(SaveError::BadCrc);
+        }
+        let reader = Reader {
+            payload: &bytes[HEADER_SIZE..end],
+            position: 0,
+        };
+        Ok((reader, version))
+    }
+
+    /// Read the next `COUNT` bytes.
+    fn take<const COUNT: usize>(&mut self) -> Result<[u8; COUNT], SaveError> {
+        let end = self.position + COUNT;
+        let bytes = self
+            .payload
+            .get(self.position..end)
+            .ok_or(SaveError::InvalidData)?;
+        self.position = end;
+        let mut array = [0; COUNT];
+        array.copy_from_slice(bytes);
+        Ok(array)
+    }
+
+    /// Read a byte.
+    pub(crate) fn u8(&mut self) -> Result<u8, SaveError> {
+        Ok(self.take::<1>()?[0])
+    }
+
+    /// Read a 16 bit signed number.
+    pub(crate) fn i16(&mut self) -> Result<i16, SaveError> {
+        Ok(i16::from_le_bytes(self.take()?))
+    }
+
+    /// Read a 32 bit unsigned number.
+    pub(crate) fn u32(&mut self) -> Result<u32, SaveError> {
+        Ok(u32::from_le_bytes(self.take()?))
+    }
+
+    /// Read a 32 bit signed number.
+    pub(crate) fn i32(&mut self) -> Result<i32, SaveError> {
+        Ok(i32::from_le_bytes(self.take()?))
+    }
+
+    /// Read a 32 bit float.
+    pub(crate) fn f32(&mut self) -> Result<f32, SaveError> {
+        Ok(f32::from_bits(self.u32()?))
+    }
+
+    /// Check that the whole payload has been read, a payload that is longer than its
+    /// version of the format says is not a save of that version.
+    pub(crate) fn finish(&self) -> Result<(), SaveError> {
+        if self.position == self.payload.len() {
+            Ok(())
+        } else {
+            Err(SaveError::InvalidData)
+        }
+    }
+}
+
+/// Return the CRC-32 of the given bytes, as used by zip and ethernet. The CRC is computed
+/// bit by bit rather than with a table to save flash.
+/// # Arguments
+/// * `bytes` - the bytes to check
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xffff_ffff;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xedb8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{crc32, save_size, Reader, SaveError, Writer, HEADER_SIZE, SAVE_VERSION};
+
+    #[test]
+    fn crc32_test() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_ne!(crc32(b"123456788"), 0xcbf4_3926);
+    }
+
+    #[test]
+    fn write_and_read_test() {
+        // GIVEN numbers written into a save
+        let mut buffer = [0; 32];
+        let mut writer = Writer::new(&mut buffer);
+        writer.put_u8(7).unwrap();
+        writer.put_i16(-300).unwrap();
+        writer.put_u32(70_000).unwrap();
+        writer.put_i32(-70_000).unwrap();
+        writer.put_f32(1.5).unwrap();
+        let size = writer.finish().unwrap();
+        assert_eq!(size, HEADER_SIZE + 15 + 4);
+        assert_eq!(&buffer[..3], &[b'S', b'B', SAVE_VERSION]);
+
+        // WHEN the save is read
+        let (mut reader, version) = Reader::new(&buffer[..size]).unwrap();
+
+        // THEN the numbers are read back
+        assert_eq!(version, SAVE_VERSION);
+        assert_eq!(reader.u8(), Ok(7));
+        assert_eq!(reader.i16(), Ok(-300));
+        assert_eq!(reader.u32(), Ok(70_000));
+        assert_eq!(reader.i32(), Ok(-70_000));
+        assert_eq!(reader.f32(), Ok(1.5));
+
+        // THEN the whole payload has been read and reading past it is an error
+        assert_eq!(reader.finish(), Ok(()));
+        assert_eq!(reader.u8(), Err(SaveError::InvalidData));
+
+        // THEN a payload that has not been read to its end is an error
+        let (mut reader, _) = Reader::new(&buffer[..size]).unwrap();
+        reader.u8().unwrap();
+        assert_eq!(reader.finish(), Err(SaveError::InvalidData));
+    }
+
+    #[test]
+    fn broken_save_test() {
+        let mut buffer = [0; 16];
+        let mut writer = Writer::new(&mut buffer);
+        writer.put_i32(42).unwrap();
+        let size = writer.finish().unwrap();
+
+        // a buffer too small for the save
+        let mut small = [0; 8];
+        let mut writer = Writer::new(&mut small);
+        assert_eq!(writer.put_i32(42), Err(SaveError::BufferTooSmall));
+
+        // a save that is cut short
+        assert_eq!(
+            Reader::new(&buffer[..size - 1]).err(),
+            Some(SaveError::Truncated)
+        );
+        assert_eq!(Reader::new(&buffer[..4]).err(), Some(SaveError::Truncated));
+
+        // a save with a flipped bit
+        let mut flipped = buffer;
+        flipped[HEADER_SIZE] ^= 1;
+        assert_eq!(Reader::new(&flipped[..size]).err(), Some(SaveError::BadCrc));
+
+        // data that is not a save
+        assert_eq!(Reader::new(&[0; 16]).err(), Some(SaveError::NotASave));
+    }
+
+    #[test]
+    fn save_size_test() {
+        assert_eq!(save_size(0, 0), HEADER_SIZE + 26 + 75 + 183 + 4);
+        assert_eq!(save_size(4, 2), save_size(0, 0) + 4 * 10 + 2 * 26);
+    }
+}
@@ -0,0 +1,351 @@
+//!
+//! This file defines the shaping of the tilt of a controller before it accelerates the ball.
+//! The tilt is ignored within a deadzone around level, the angle beyond the deadzone is
+//! passed through a response curve and the response saturates at a maximum angle, so that
+//! the game can be made forgiving for new players or fine grained for experienced ones.
+//! Presets of the shaping are given by InputPreset.
+//!
+
+use crate::config::ANGLE_THRESHOLD;
+use crate::smallball::Tilt;
+use core::f32::consts::PI;
+use libm::expm1f;
+
+/// How the angle of a tilt beyond the deadzone is turned into the response.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ResponseCurve {
+    /// the response grows as the angle
+    Linear,
+    /// the response grows as the square of the angle, gentle at small angles
+    Quadratic,
+    /// the response grows exponentially with the angle, the given steepness is positive and
+    /// the larger it is the gentler the response at small angles
+    Exponential(f32),
+}
+
+impl ResponseCurve {
+    /// Return the response to the given angle beyond the deadzone, from 0.0 at the deadzone
+    /// to the range at the maximum angle.
+    /// # Arguments
+    /// * `excess` - the angle beyond the deadzone, between 0.0 and the range
+    /// * `range` - the angle between the deadzone and the maximum angle
+    fn apply(&self, excess: f32, range: f32) -> f32 {
+        match *self {
+            ResponseCurve::Linear => excess,
+            ResponseCurve::Quadratic => excess * excess / range,
+            ResponseCurve::Exponential(steepness) => {
+                range * expm1f(steepness * excess / range) / expm1f(steepness)
+            }
+        }
+    }
+}
+
+/// The shaping of the tilt of a controller, applied to the pitch and the roll alike.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct InputShaping {
+    /// the angle in radians below which the tilt is ignored
+    deadzone: f32,
+    /// the angle in radians above which the response does not grow
+    max_angle: f32,
+    /// the response to the angle between the deadzone and the maximum angle
+    curve: ResponseCurve,
+    /// the factor the response is multiplied by
+    gain: f32,
+    /// whether a positive pitch accelerates the ball down rather than up
+    invert_pitch: bool,
+    /// whether a positive roll accelerates the ball left rather than right
+    invert_roll: bool,
+}
+
+impl InputShaping {
+    /// Return a new shaping without gain or inversion.
+    /// # Arguments
+    /// * `deadzone` - the angle in radians below which the tilt is ignored
+    /// * `max_angle` - the angle in radians above which the response does not grow
+    /// * `curve` - the response to the angle between the deadzone and the maximum angle
+    pub const fn new(deadzone: f32, max_angle: f32, curve: ResponseCurve) -> Self {
+        InputShaping {
+            deadzone,
+            max_angle,
+            curve,
+            gain: 1.0,
+            invert_pitch: false,
+            invert_roll: false,
+        }
+    }
+
+    /// Return this shaping with the given deadzone.
+    /// # Arguments
+    /// * `deadzone` - the angle in radians below which the tilt is ignored
+    pub const fn with_deadzone(mut self, deadzone: f32) -> Self {
+        self.deadzone = deadzone;
+        self
+    }
+
+    /// Return this shaping with the given gain.
+    /// # Arguments
+    /// * `gain` - the factor the response is multiplied by
+    pub const fn with_gain(mut self, gain: f32) -> Self {
+        self.gain = gain;
+        self
+    }
+
+    /// Return this shaping with the given inversion of the axes.
+    /// # Arguments
+    /// * `invert_pitch` - whether a positive pitch accelerates the ball down rather than up
+    /// * `invert_roll` - whether a positive roll accelerates the ball left rather than right
+    pub const fn with_inversion(mut self, invert_pitch: bool, invert_roll: bool) -> Self {
+        self.invert_pitch = invert_pitch;
+        self.invert_roll = invert_roll;
+        self
+    }
+
+    /// Return the angle in radians below which the tilt is ignored.
+    pub fn deadzone(&self) -> f32 {
+        self.deadzone
+    }
+
+    /// Return whether the shaping yields a finite response that grows with the angle: the
+    /// deadzone is not negative, the maximum angle is beyond it, the gain is positive and
+    /// an exponential curve is neither flat nor too steep to compute.
+    pub fn is_valid(&self) -> bool {
+        let curve = match self.curve {
+            ResponseCurve::Exponential(steepness) => {
+                steepness > 0.0 && expm1f(steepness).is_finite()
+            }
+            _ => true,
+        };
+        self.deadzone >= 0.0
+            && self.max_angle > self.deadzone
+            && self.max_angle.is_finite()
+            && self.gain > 0.0
+            && self.gain.is_finite()
+            && curve
+    }
+
+    /// Return the response to the tilt along one axis, 0.0 within the deadzone and of the
+    /// same sign as the angle otherwise. Inversion is not applied.
+    /// # Arguments
+    /// * `angle` - the tilt angle in radians along the axis
+    pub fn shape_axis(&self, angle: f32) -> f32 {
+        let magnitude = angle.abs();
+        // a reading that is not a number is ignored like a level one
+        if magnitude.is_nan() || magnitude <= self.deadzone {
+            return 0.0;
+        }
+        let range = self.max_angle - self.deadzone;
+        let excess = (magnitude - self.deadzone).min(range);
+        let response = self.gain * self.curve.apply(excess, range);
+        if angle < 0.0 {
+            -response
+        } else {
+            response
+        }
+    }
+
+    /// Return the response to the given tilt along both axes, inverted as configured.
+    /// # Arguments
+    /// * `tilt` - the tilt of the controller
+    pub fn shape(&self, tilt: Tilt) -> Tilt {
+        let pitch = self.shape_axis(tilt.pitch);
+        let roll = self.shape_axis(tilt.roll);
+        Tilt::new(
+            if self.invert_pitch { -pitch } else { pitch },
+            if self.invert_roll { -roll } else { roll },
+        )
+    }
+}
+
+/// The presets of the shaping of the tilt.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InputPreset {
+    /// the response of the original game, linear beyond the angle threshold
+    Classic,
+    /// a wide deadzone and a gentle response that saturates early, so that a shaky hand
+    /// does not move the ball and the ball does not run away
+    Casual,
+    /// a narrow deadzone and an exponential response, for fine control at small angles
+    Precise,
+    /// a narrow deadzone and a strong linear response that saturates early, so that a
+    /// small tilt moves the ball fast
+    Arcade,
+}
+
+impl InputPreset {
+    /// All the presets.
+    pub const ALL: [InputPreset; 4] = [
+        InputPreset::Classic,
+        InputPreset::Casual,
+        InputPreset::Precise,
+        InputPreset::Arcade,
+    ];
+
+    /// Return the shaping of the preset.
+    pub const fn shaping(self) -> InputShaping {
+        match self {
+            InputPreset::Classic => InputShaping::new(ANGLE_THRESHOLD, PI, ResponseCurve::Linear),
+            InputPreset::Casual => InputShaping::new(0.15, 0.7, ResponseCurve::Quadratic),
+            InputPreset::Precise => InputShaping::new(0.03, 1.0, ResponseCurve::Exponential(3.0)),
+            InputPreset::Arcade => {
+                InputShaping::new(0.05, 0.5, ResponseCurve::Linear).with_gain(2.5)
+            }
+        }
+    }
+
+    /// Return the name of the preset.
+    pub fn name(self) -> &'static str {
+        match self {
+            InputPreset::Classic => "Classic",
+            InputPreset::Casual => "Casual",
+            InputPreset::Precise => "Precise",
+            InputPreset::Arcade => "Arcade",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{InputPreset, InputShaping, ResponseCurve};
+    use crate::config::ANGLE_THRESHOLD;
+    use crate::smallball::Tilt;
+    use core::f32::consts::PI;
+
+    const CURVES: [ResponseCurve; 3] = [
+        ResponseCurve::Linear,
+        ResponseCurve::Quadratic,
+        ResponseCurve::Exponential(2.0),
+    ];
+
+    /// Return the presets and a shaping of every curve, the shapings the tests cover.
+    fn shapings() -> impl Iterator<Item = InputShaping> {
+        InputPreset::ALL
+            .iter()
+            .map(|preset| preset.shaping())
+            .chain(
+                CURVES
+                    .iter()
+                    .map(|curve| InputShaping::new(0.1, 0.8, *curve)),
+            )
+    }
+
+    /// Return angles from -3.2 to 3.2 radians in steps of 0.01 radians.
+    fn angles() -> impl Iterator<Item = f32> {
+        (-320..=320).map(|step| step as f32 * 0.01)
+    }
+
+    #[test]
+    fn presets_test() {
+        for preset in InputPreset::ALL {
+            assert!(preset.shaping().is_valid(), "{}", preset.name());
+        }
+        assert_eq!(InputPreset::Casual.name(), "Casual");
+
+        // THEN the classic preset is the original threshold step for any angle of a sensor
+        let classic = InputPreset::Classic.shaping();
+        assert_eq!(classic.deadzone(), ANGLE_THRESHOLD);
+        for angle in angles().filter(|angle| angle.abs() <= PI) {
+            let expected = if angle > ANGLE_THRESHOLD {
+                angle - ANGLE_THRESHOLD
+            } else if angle < -ANGLE_THRESHOLD {
+                angle + ANGLE_THRESHOLD
+            } else {
+                0.0
+            };
+            assert_eq!(classic.shape_axis(angle), expected, "{}", angle);
+        }
+    }
+
+    #[test]
+    fn monotonicity_test() {
+        for shaping in shapings() {
+            // THEN the response never falls as the angle grows
+            let mut last = f32::NEG_INFINITY;
+            for angle in angles() {
+                let response = shaping.shape_axis(angle);
+                assert!(response >= last, "{:?} at {}", shaping, angle);
+                last = response;
+            }
+        }
+    }
+
+    #[test]
+    fn symmetry_test() {
+        for shaping in shapings() {
+            // THEN tilting the other way gives the opposite response
+            for angle in angles() {
+                assert_eq!(
+                    shaping.shape_axis(-angle),
+                    -shaping.shape_axis(angle),
+                    "{:?} at {}",
+                    shaping,
+                    angle
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn deadzone_and_saturation_test() {
+        for curve in CURVES {
+            // GIVEN a shaping with a deadzone of 0.1 and a maximum angle of 0.8
+            let shaping = InputShaping::new(0.1, 0.8, curve).with_gain(2.0);
+
+            // THEN the tilt is ignored within the deadzone, a reading that is not a number too
+            assert_eq!(shaping.shape_axis(0.0), 0.0);
+            assert_eq!(shaping.shape_axis(0.1), 0.0);
+            assert_eq!(shaping.shape_axis(-0.1), 0.0);
+            assert_eq!(shaping.shape_axis(f32::NAN), 0.0);
+
+            // THEN the response starts at zero at the edge of the deadzone
+            assert!(shaping.shape_axis(0.1001) < 1e-3);
+
+            // THEN the response saturates at the maximum angle
+            let max = shaping.shape_axis(0.8);
+            assert!((max - 2.0 * 0.7).abs() < 1e-5, "{:?}", curve);
+            assert_eq!(shaping.shape_axis(1.5), max);
+            assert_eq!(shaping.shape_axis(f32::INFINITY), max);
+        }
+
+        // THEN the quadratic and exponential curves are gentler than the linear one
+        let linear = InputShaping::new(0.1, 0.8, ResponseCurve::Linear);
+        let quadratic = InputShaping::new(0.1, 0.8, ResponseCurve::Quadratic);
+        let exponential = InputShaping::new(0.1, 0.8, ResponseCurve::Exponential(2.0));
+        assert!(quadratic.shape_axis(0.3) < linear.shape_axis(0.3));
+        assert!(exponential.shape_axis(0.3) < linear.shape_axis(0.3));
+    }
+
+    #[test]
+    fn inversion_test() {
+        // GIVEN a shaping without a deadzone
+        let shaping = InputShaping::new(0.0, 1.0, ResponseCurve::Linear);
+        let tilt = Tilt::new(0.25, -0.5);
+
+        // THEN the axes are inverted as configured
+        assert_eq!(shaping.shape(tilt), tilt);
+        assert_eq!(
+            shaping.with_inversion(true, false).shape(tilt),
+            Tilt::new(-0.25, -0.5)
+        );
+        assert_eq!(
+            shaping.with_inversion(false, true).shape(tilt),
+            Tilt::new(0.25, 0.5)
+        );
+        assert_eq!(
+            shaping.with_deadzone(0.25).shape(tilt),
+            Tilt::new(0.0, -0.25)
+        );
+    }
+
+    #[test]
+    fn shaping_validation_test() {
+        let shaping = InputShaping::new(0.1, 0.8, ResponseCurve::Linear);
+        assert!(shaping.is_valid());
+        assert!(!shaping.with_deadzone(-0.1).is_valid());
+        assert!(!shaping.with_deadzone(0.8).is_valid());
+        assert!(!shaping.with_gain(0.0).is_valid());
+        assert!(!shaping.with_gain(f32::NAN).is_valid());
+        assert!(!InputShaping::new(0.1, f32::INFINITY, ResponseCurve::Linear).is_valid());
+        assert!(!InputShaping::new(0.1, 0.8, ResponseCurve::Exponential(0.0)).is_valid());
+        assert!(!InputShaping::new(0.1, 0.8, ResponseCurve::Exponential(100.0)).is_valid());
+    }
+}
@@ -1,75 +1,250 @@
 //!
 //! This file defines the SmallBall game. SmallBall is a game where you control a small ball
 //! on a small screen via an mpu sensor. The goal is to move the ball around the screen to
-//! visit all goals on the screen in the minimum amount of time. The game keeps track of the
-//! lowest score achieved.
+//! visit all goals on the screen in the minimum amount of time. The game is played over a
+//! sequence of levels, each with its own layout of goals, and the score is the total time in
+//! milliseconds over all levels. Obstacles in some levels block the ball on its way and holes
+//! either send the ball back to its start or cost points. The game keeps track of the lowest
+//! score achieved, of the time each goal was reached and of a table of the best scores, for
+//! which the player enters their initials, that the intro cycles through while the game
+//! waits to be started. The goals are either placed where the level defines them or at
+//! random locations generated from a seed. In the sequence variant of the game the goals
+//! have to be visited in order. Two players can race each other, each controlling their
+//! own ball with their own mpu sensor, and every goal is credited to the player whose ball
+//! visits it first. Every update raises the game events that happened during it, such as a
+//! goal reached or a mode change, so that the display, sound or LEDs can react to them. A
+//! game can be saved mid-play and restored later. The tilt of the mpu sensors in a neutral
+//! pose is measured by a calibration, which a moderate tilt held during the intro starts.
 //!
 
 use crate::{
     config::{
-        ANGLE_THRESHOLD, BALL_DELTA, BALL_LOCATION, BALL_SIZE, GOAL_LOCATIONS, GOAL_SIZE,
-        SCREEN_OUTLINE_SIZE, SCREEN_OUTLINE_TOP_LET, X_MAX, X_MIN, Y_MAX, Y_MIN,
+        GameConfig, GOAL_PLACEMENT_ATTEMPTS, HIGH_SCORES, INITIALS, MAX_EVENTS, MAX_GOALS,
+        MAX_HAZARDS, MAX_OBSTACLES, MAX_PLAYERS, MAX_SPLITS,
     },
-    math::intersects,
+    highscores::{HighScoreTable, Initials, InitialsEntry},
+    input::{ControlInput, BUTTON_START},
+    level::Level,
+    math::{
+        circles_intersect, round, separation, sweep, swept_circle_intersects_rectangle, Axis,
+        Vector,
+    },
+    rng::Rng,
+    save::{Reader, SaveError, Writer},
+    stats::RunStats,
 };
 use embedded_graphics::prelude::{Point, Size};
 use heapless::Vec;
 
 /// The mode the game is in.
-#[cfg_attr(test, derive(Debug, PartialEq))]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum Mode {
     /// Introduce the game with a splash screen
     Intro,
     /// Actively playing the game
     Play,
+    /// A level is complete, introduce the next level
+    NextLevel,
     /// The game is over, show the score and the low score
     Over,
+    /// The score is good enough for the high score table, the player enters their initials
+    EnterInitials,
+    /// Show the high score table, the intro and the high score table take turns until the
+    /// game is started
+    HighScores,
+    /// Measure the tilt of the mpu sensors held in a neutral pose, then go back to the intro
+    Calibrate,
+}
+
+/// The variant of the game that is played.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GameVariant {
+    /// The goals can be visited in any order
+    Free,
+    /// The goals have to be visited in order, only the next goal in the sequence can be
+    /// visited and the other goals are ignored by the ball
+    Sequence,
+}
+
+/// A wall that the ball can bounce off, named by the side of the ball it is on. The walls
+/// are the boundaries of the game space and the sides of the obstacles.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Wall {
+    /// A wall to the left of the ball, such as the left boundary of the game space
+    Left,
+    /// A wall to the right of the ball, such as the right boundary of the game space
+    Right,
+    /// A wall above the ball, such as the top boundary of the game space
+    Top,
+    /// A wall below the ball, such as the bottom boundary of the game space
+    Bottom,
+}
+
+/// Something that happened in the game during an update.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GameEvent {
+    /// The game switched to the given mode
+    ModeChanged { mode: Mode },
+    /// The ball of a player reached the goal with the given index and the goal is dead
+    GoalReached { index: usize, player: usize },
+    /// The ball of a player bounced off a wall, raised at most once per side of the ball
+    WallHit { player: usize, wall: Wall },
+    /// The ball of a player fell into the given hole, which is given whole because the next
+    /// level may have started by the end of the update
+    FellIntoHole { player: usize, hazard: Hazard },
+    /// The goals of the last level have been visited, the score is the score of the first
+    /// player and a new record is a new low score
+    GameOver { score: i32, new_record: bool },
+    /// The score of the game was added to the high score table with the given rank, starting
+    /// at zero for the best score
+    HighScoreEntered { rank: usize },
+    /// A calibration is over, the measured tilt of each player is given by State::offsets
+    Calibrated,
+}
+
+/// The tilt of an mpu sensor, the control input of a player.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Tilt {
+    /// the pitch reading from the mpu sensor
+    pub pitch: f32,
+    /// the roll reading from the mpu sensor
+    pub roll: f32,
+}
+
+impl Tilt {
+    /// Return a new tilt.
+    /// # Arguments
+    /// * `pitch` - the pitch reading from the mpu sensor
+    /// * `roll` - the roll reading from the mpu sensor
+    pub fn new(pitch: f32, roll: f32) -> Self {
+        Tilt { pitch, roll }
+    }
+
+    /// Return whether the sensor is tilted further than the given angle along either axis.
+    /// # Arguments
+    /// * `angle` - the angle to compare the tilt with
+    fn exceeds(&self, angle: f32) -> bool {
+        self.pitch.abs() > angle || self.roll.abs() > angle
+    }
 }
 
 /// The Ball is the entity that the user controls on the screen
 /// trying to visit goals as quickly as possible.
-#[cfg_attr(test, derive(Debug, PartialEq))]
+#[derive(Debug)]
+#[cfg_attr(test, derive(PartialEq))]
 pub struct Ball {
-    /// the current location of this ball
-    location: Point,
+    /// the current position of this ball with sub-pixel precision
+    position: Vector,
+    /// the current velocity of this ball in pixels per physics step
+    velocity: Vector,
+    /// the size of this ball
+    size: u32,
 }
 
 impl Ball {
-    /// Return a new ball.
+    /// Return a new ball at rest.
     /// # Arguments
     /// * `location` - the initial location of the ball
-    fn new(location: Point) -> Self {
-        Ball { location }
+    /// * `size` - the size of the ball
+    fn new(location: Point, size: u32) -> Self {
+        Ball {
+            position: Vector::from(location),
+            velocity: Vector::default(),
+            size,
+        }
     }
 
-    /// Return the current location of this ball.
+    /// Return the current location of this ball, rounded to the nearest pixel.
     pub fn location(&self) -> Point {
-        self.location
+        self.position.to_point()
     }
 
     /// Return the size of this ball.
     pub fn size(&self) -> u32 {
-        BALL_SIZE
+        self.size
+    }
+}
+
+/// A Player controls their own ball and keeps their own score.
+#[derive(Debug)]
+pub struct Player {
+    /// the current state of the ball of this player
+    ball: Ball,
+    /// the current score of this player, the total time in milliseconds over all levels
+    /// played so far plus any penalties
+    score: i32,
+    /// the number of goals this player has visited
+    goals: usize,
+    /// the score of this player when they visited their last goal
+    time: i32,
+    /// the index of the hole the ball is currently in, if any
+    hazard: Option<usize>,
+}
+
+impl Player {
+    /// Return a new player that has not visited any goals yet.
+    /// # Arguments
+    /// * `ball` - the initial state of the ball of the player
+    fn new(ball: Ball) -> Self {
+        Player {
+            ball,
+            score: 0,
+            goals: 0,
+            time: 0,
+            hazard: None,
+        }
+    }
+
+    /// Return the current state of the ball of this player.
+    pub fn ball(&self) -> &Ball {
+        &self.ball
+    }
+
+    /// Return the current score of this player.
+    pub fn score(&self) -> i32 {
+        self.score
+    }
+
+    /// Return the number of goals this player has visited.
+    pub fn goals(&self) -> usize {
+        self.goals
+    }
+
+    /// Return the score of this player when they visited their last goal.
+    pub fn time(&self) -> i32 {
+        self.time
     }
 }
 
 /// A goal is a box on the screen that the ball needs to visit.
 #[derive(Debug)]
+#[cfg_attr(test, derive(PartialEq))]
 pub struct Goal {
     /// The current location of the goal.
     location: Point,
+    /// The position of the goal in the sequence of goals of the level, starting at one.
+    number: usize,
     /// The goal is alive if it has yet to be visited by the ball.
     alive: bool,
+    /// The size of the goal.
+    size: u32,
+    /// The time in milliseconds into the game at which the goal was reached, if it has been.
+    time: Option<i32>,
 }
 
 impl Goal {
     /// Return a new goal.
     /// # Arguments
     /// * `location` - the initial location of the goal
-    fn new(location: Point) -> Self {
+    /// * `number` - the position of the goal in the sequence of goals, starting at one
+    /// * `size` - the size of the goal
+    fn new(location: Point, number: usize, size: u32) -> Self {
         Goal {
             location,
+            number,
             alive: true,
+            size,
+            time: None,
         }
     }
 
@@ -78,46 +253,292 @@ impl Goal {
         self.location
     }
 
+    /// Return the position of the goal in the sequence of goals of the level, starting
+    /// at one.
+    pub fn number(&self) -> usize {
+        self.number
+    }
+
     /// Return the size of the goal.
     pub fn size(&self) -> u32 {
-        GOAL_SIZE
+        self.size
+    }
+
+    /// Return the time in milliseconds into the game at which the goal was reached, if it
+    /// has been.
+    pub fn time(&self) -> Option<i32> {
+        self.time
     }
 }
 
-/// The SmallBall game state.
-pub struct State {
-    /// the current score
-    score: i32,
+/// An obstacle is a wall on the screen that blocks the ball.
+#[derive(Clone, Copy, Debug)]
+pub struct Obstacle {
+    /// The location of the top left corner of the obstacle.
+    location: Point,
+    /// The size of the obstacle.
+    size: Size,
+}
+
+impl Obstacle {
+    /// Return a new obstacle.
+    /// # Arguments
+    /// * `location` - the location of the top left corner of the obstacle
+    /// * `size` - the size of the obstacle
+    pub const fn new(location: Point, size: Size) -> Self {
+        Obstacle { location, size }
+    }
+
+    /// Return the location of the top left corner of the obstacle.
+    pub fn location(&self) -> Point {
+        self.location
+    }
+
+    /// Return the size of the obstacle.
+    pub fn size(&self) -> Size {
+        self.size
+    }
+}
+
+/// What happens when the ball falls into a hole.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HazardEffect {
+    /// The ball is sent back to its initial location in the level
+    Reset,
+    /// The given penalty is added to the score
+    Penalty(i32),
+}
+
+/// A hazard is a round hole on the screen that the ball should avoid. The ball falls into
+/// the hole when its centre is inside the hole.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Hazard {
+    /// The location of the top left corner of the bounding square of the hole.
+    location: Point,
+    /// The diameter of the hole.
+    size: u32,
+    /// What happens when the ball falls into the hole.
+    effect: HazardEffect,
+}
+
+impl Hazard {
+    /// Return a new hazard.
+    /// # Arguments
+    /// * `location` - the location of the top left corner of the bounding square of the hole
+    /// * `size` - the diameter of the hole
+    /// * `effect` - what happens when the ball falls into the hole
+    pub const fn new(location: Point, size: u32, effect: HazardEffect) -> Self {
+        Hazard {
+            location,
+            size,
+            effect,
+        }
+    }
+
+    /// Return the location of the top left corner of the bounding square of the hole.
+    pub fn location(&self) -> Point {
+        self.location
+    }
+
+    /// Return the diameter of the hole.
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    /// Return what happens when the ball falls into the hole.
+    pub fn effect(&self) -> HazardEffect {
+        self.effect
+    }
+}
+
+/// The SmallBall game state. The goal capacity `N` is the most goals a level of the game can
+/// have and the event capacity `E` is the most events an update can raise, the capacities of
+/// the configuration the game is played with.
+pub struct State<const N: usize = MAX_GOALS, const E: usize = MAX_EVENTS> {
+    /// the configuration the game is played with
+    config: GameConfig<N, E>,
+    /// the players of the game, the score of the game is the score of the first player
+    players: Vec<Player, MAX_PLAYERS>,
+    /// the score when the current level started
+    level_start_score: i32,
+    /// the index of the current level in the list of levels
+    level: usize,
     /// the lowest score achieved in a completed game
     low_score: i32,
-    /// the current state of the ball
-    ball: Ball,
+    /// the best scores of single player games
+    high_scores: HighScoreTable,
+    /// the initials entered for a new high score
+    initials: InitialsEntry,
+    /// the splits of the first player in the current or the last game
+    run: RunStats,
+    /// the splits of the run of the low score, empty if there was none or if the low score
+    /// was kept from an earlier game
+    best_splits: Vec<i32, MAX_SPLITS>,
+    /// the time in milliseconds the calibration gesture has been held during the intro
+    gesture_ms: u32,
+    /// the sum of the tilts of each player measured by the current or the last calibration
+    calibration_sums: [Tilt; MAX_PLAYERS],
+    /// the number of tilts of each player measured by the current or the last calibration
+    calibration_samples: [u32; MAX_PLAYERS],
     /// the current state of the goals
-    goals: Vec<Goal, 4>,
+    goals: Vec<Goal, N>,
+    /// the obstacles of the current level, scaled to the play area
+    obstacles: Vec<Obstacle, MAX_OBSTACLES>,
+    /// the holes of the current level, scaled to the play area
+    hazards: Vec<Hazard, MAX_HAZARDS>,
     /// the current game mode
     mode: Mode,
+    /// the time in milliseconds spent in the current game mode
+    mode_ms: u32,
+    /// the variant of the game that is played
+    variant: GameVariant,
+    /// the events raised during the last update
+    events: Vec<GameEvent, E>,
+    /// the random number generator used to place the goals, if the goals are placed
+    /// from a seed rather than where the level defines them
+    rng: Option<Rng>,
+    /// the time in milliseconds that has passed but is not yet simulated by the physics
+    physics_ms: u32,
 }
 
 impl State {
     /// Return a new game State with default initial state.
     pub fn new() -> Self {
+        State::new_with_config(GameConfig::default())
+    }
+
+    /// Return a new game State where the goals of every level are placed at random
+    /// locations generated from the given seed. The same seed always produces the same
+    /// sequence of goal layouts.
+    /// # Arguments
+    /// * `seed` - the seed for the random goal locations
+    pub fn new_with_seed(seed: u32) -> Self {
+        State::new().with_seed(seed)
+    }
+
+    /// Return the game restored from the given save, played with the default configuration.
+    /// # Arguments
+    /// * `bytes` - the save written by serialize_into
+    pub fn deserialize(bytes: &[u8]) -> Result<Self, SaveError> {
+        State::deserialize_with_config(GameConfig::default(), bytes)
+    }
+}
+
+impl<const N: usize, const E: usize> State<N, E> {
+    /// Return a new game State that is played with the given configuration, with room for
+    /// as many goals as the configuration.
+    /// # Arguments
+    /// * `config` - the configuration of the game, see GameConfigBuilder
+    pub fn new_with_config(config: GameConfig<N, E>) -> Self {
+        let level = &config.levels[0];
         State {
-            score: 0,
-            low_score: i32::max_value(),
-            ball: State::initial_ball(),
-            goals: State::initial_goals(),
+            players: Self::initial_players(1, level, &config),
+            level_start_score: 0,
+            level: 0,
+            low_score: i32::MAX,
+            high_scores: HighScoreTable::new(),
+            initials: InitialsEntry::new(),
+            run: RunStats::new(&[]),
+            best_splits: Vec::new(),
+            gesture_ms: 0,
+            calibration_sums: [Tilt::default(); MAX_PLAYERS],
+            calibration_samples: [0; MAX_PLAYERS],
+            goals: Self::initial_goals(level, &config, None),
+            obstacles: Self::initial_obstacles(level, &config),
+            hazards: Self::initial_hazards(level, &config),
+            config,
             mode: Mode::Intro,
+            mode_ms: 0,
+            variant: GameVariant::Free,
+            events: Vec::new(),
+            rng: None,
+            physics_ms: 0,
         }
     }
 
-    /// Return the current state of the ball.
+    /// Return this game State with the goals of every level placed at random locations
+    /// generated from the given seed.
+    /// # Arguments
+    /// * `seed` - the seed for the random goal locations
+    pub fn with_seed(mut self, seed: u32) -> Self {
+        let mut rng = Rng::new(seed);
+        self.goals = Self::initial_goals(self.current_level(), &self.config, Some(&mut rng));
+        self.rng = Some(rng);
+        self
+    }
+
+    /// Return this game State set up to play the given variant of the game.
+    /// # Arguments
+    /// * `variant` - the variant of the game to play
+    pub fn with_variant(mut self, variant: GameVariant) -> Self {
+        self.variant = variant;
+        self
+    }
+
+    /// Return this game State set up for the given number of players racing each other.
+    /// # Arguments
+    /// * `players` - the number of players, limited to between one and MAX_PLAYERS
+    pub fn with_players(mut self, players: usize) -> Self {
+        self.players = Self::initial_players(players, self.current_level(), &self.config);
+        self
+    }
+
+    /// Return this game State with the given low score, such as a low score kept from an
+    /// earlier game.
+    /// # Arguments
+    /// * `low_score` - the lowest score achieved in a completed game
+    pub fn with_low_score(mut self, low_score: i32) -> Self {
+        self.low_score = low_score;
+        self
+    }
+
+    /// Return this game State with the given high score table, such as a table kept from
+    /// earlier games.
+    /// # Arguments
+    /// * `high_scores` - the best scores of single player games
+    pub fn with_high_scores(mut self, high_scores: HighScoreTable) -> Self {
+        self.high_scores = high_scores;
+        self
+    }
+
+    /// Return the variant of the game that is played.
+    pub fn variant(&self) -> GameVariant {
+        self.variant
+    }
+
+    /// Return the players of the game.
+    pub fn players(&self) -> &[Player] {
+        &self.players
+    }
+
+    /// Return the current state of the ball of the first player.
     pub fn ball(&self) -> &Ball {
-        &self.ball
+        &self.players[0].ball
     }
 
-    /// Return the current score.
+    /// Return the current score of the first player.
     pub fn score(&self) -> i32 {
-        self.score
+        self.players[0].score
+    }
+
+    /// Return the score achieved so far in the current level by the first player.
+    pub fn level_score(&self) -> i32 {
+        self.score() - self.level_start_score
+    }
+
+    /// Return the index of the current level, starting at zero for the first level.
+    pub fn level(&self) -> usize {
+        self.level
+    }
+
+    /// Return the definition of the current level.
+    pub fn current_level(&self) -> &'static Level {
+        &self.config.levels[self.level]
+    }
+
+    /// Return the configuration the game is played with.
+    pub fn config(&self) -> &GameConfig<N, E> {
+        &self.config
     }
 
     /// Return the lowest score achieved in a completed game.
@@ -125,296 +546,3099 @@ impl State {
         self.low_score
     }
 
+    /// Return the best scores of single player games.
+    pub fn high_scores(&self) -> &HighScoreTable {
+        &self.high_scores
+    }
+
+    /// Return the initials entered for a new high score.
+    pub fn initials(&self) -> &InitialsEntry {
+        &self.initials
+    }
+
+    /// Return the splits of the first player in the current game, or in the last game until
+    /// the next game starts, compared with the run of the low score before the game.
+    pub fn run(&self) -> &RunStats {
+        &self.run
+    }
+
     /// Return the current game mode.
     pub fn mode(&self) -> &Mode {
         &self.mode
     }
 
-    /// Return the default initial ball state.
-    fn initial_ball() -> Ball {
-        Ball::new(BALL_LOCATION)
+    /// Return the time in milliseconds spent in the current game mode.
+    pub fn mode_ms(&self) -> u32 {
+        self.mode_ms
     }
 
-    /// Return the default initial goal states.
-    fn initial_goals() -> Vec<Goal, 4> {
-        let mut goals = Vec::new();
-        for location in GOAL_LOCATIONS {
-            goals.push(Goal::new(location)).unwrap();
-        }
-        goals
+    /// Return the average tilt of the mpu sensor of each player measured by the current or
+    /// the last calibration, level if nothing has been measured. The tilt is the offset to
+    /// take off the readings of the sensor for the player to be level.
+    pub fn offsets(&self) -> [Tilt; MAX_PLAYERS] {
+        core::array::from_fn(|player| {
+            let sum = self.calibration_sums[player];
+            let samples = self.calibration_samples[player].max(1) as f32;
+            Tilt::new(sum.pitch / samples, sum.roll / samples)
+        })
     }
 
-    /// Update the state of the game based on the latest pitch and roll input from the mpu.
-    /// # Arguments
-    /// * `pitch` - the pitch reading from the mpu sensor
-    /// * `roll` - the roll reading from the mpu sensor
-    pub fn update(&mut self, pitch: &f32, roll: &f32) {
-        self.update_ball(pitch, roll);
-        self.update_score();
-        self.update_mode();
-        self.update_goals();
+    /// Return the events raised during the last update, in the order they happened.
+    pub fn events(&self) -> &[GameEvent] {
+        &self.events
     }
 
-    // Update the game mode.
-    fn update_mode(&mut self) {
-        match self.mode {
-            Mode::Intro => self.mode = Mode::Play,
-            Mode::Play => {
-                if self.goals.iter().all(|goal| !goal.alive) {
-                    self.mode = Mode::Over;
-                    if self.score < self.low_score {
-                        self.low_score = self.score;
-                    }
-                }
-            }
-            Mode::Over => {
-                self.mode = Mode::Play;
-                self.score = 0;
-                self.ball = State::initial_ball();
-                self.goals = State::initial_goals();
-            }
-        }
+    /// Return the obstacles of the current level.
+    pub fn obstacles(&self) -> &[Obstacle] {
+        &self.obstacles
     }
 
-    /// Update the game score.
-    fn update_score(&mut self) {
-        // score is based on time so the longer it takes to reach each goal,
-        // the higher your score. Lower scores are better.
-        self.score += 1;
+    /// Return the holes of the current level.
+    pub fn hazards(&self) -> &[Hazard] {
+        &self.hazards
     }
 
-    /// Update the ball state based on mpu pitch and roll input.
+    /// Return the initial players of the game, each with their ball at the initial ball
+    /// location of the given level.
     /// # Arguments
-    /// * `pitch` - the pitch reading from the mpu sensor
-    /// * `roll` - the roll reading from the mpu sensor
-    fn update_ball(&mut self, pitch: &f32, roll: &f32) {
-        let mut x = self.ball.location.x;
-        let mut y = self.ball.location.y;
-
-        if *pitch > ANGLE_THRESHOLD && y > Y_MIN {
-            // if the sensor is pitched down then the ball moves up the screen until it hits the top boundary
-            y -= BALL_DELTA;
-        } else if *pitch < -ANGLE_THRESHOLD && y < Y_MAX {
-            // if the sensor is pitched up then the ball moves down the screen until it hits the bottom boundary
-            y += BALL_DELTA;
-        }
-
-        if *roll > ANGLE_THRESHOLD && x < X_MAX {
-            // if the sensor is rolled up then the ball moves right on the screen until it hits the right boundary
-            x += BALL_DELTA;
-        } else if *roll < -ANGLE_THRESHOLD && x > X_MIN {
-            // if the sensor is rolled down then the ball moves left on the screen until it hits the left boundary
-            x -= BALL_DELTA;
-        }
-
-        self.ball = Ball::new(Point::new(x, y));
-    }
-
-    /// Update the goal states based on whether or not they have been newly visited by the ball.
-    /// Once visited the goal is dead.
-    fn update_goals(&mut self) {
-        for goal in self.goals.iter_mut() {
-            if goal.alive
-                && intersects(
-                    goal.location,
-                    goal.size(),
-                    self.ball.location,
-                    self.ball.size(),
-                )
-            {
-                goal.alive = false;
-            }
+    /// * `players` - the number of players, limited to between one and MAX_PLAYERS
+    /// * `level` - the level that is starting
+    /// * `config` - the configuration of the game
+    fn initial_players(
+        players: usize,
+        level: &Level,
+        config: &GameConfig<N, E>,
+    ) -> Vec<Player, MAX_PLAYERS> {
+        let mut initial_players = Vec::new();
+        for _ in 0..players.clamp(1, MAX_PLAYERS) {
+            // there is room for the clamped number of players
+            initial_players
+                .push(Player::new(Self::initial_ball(level, config)))
+                .unwrap();
         }
+        initial_players
     }
 
-    /// Return the top left point that defines the screen outline rectangle.
-    pub fn screen_outline_top_left(&self) -> Point {
-        SCREEN_OUTLINE_TOP_LET
+    /// Return the initial ball state for the given level.
+    /// # Arguments
+    /// * `level` - the level that is starting
+    /// * `config` - the configuration of the game
+    fn initial_ball(level: &Level, config: &GameConfig<N, E>) -> Ball {
+        let size = config.ball_size;
+        Ball::new(config.scale_location(level.ball(), size), size)
     }
 
-    /// Return the size of the screen outline rectangle.
-    pub fn screen_outline_size(&self) -> Size {
-        SCREEN_OUTLINE_SIZE
+    /// Return the obstacles of the given level scaled to the play area.
+    /// # Arguments
+    /// * `level` - the level that is starting
+    /// * `config` - the configuration of the game
+    fn initial_obstacles(level: &Level, config: &GameConfig<N, E>) -> Vec<Obstacle, MAX_OBSTACLES> {
+        level
+            .obstacles()
+            .iter()
+            .map(|obstacle| config.scale_obstacle(obstacle))
+            .collect()
     }
 
-    /// Return the vector of goals that are still alive.
-    pub fn goals_alive(&self) -> Vec<&Goal, 4> {
-        let mut goals_alive = Vec::new();
+    /// Return the holes of the given level scaled to the play area.
+    /// # Arguments
+    /// * `level` - the level that is starting
+    /// * `config` - the configuration of the game
+    fn initial_hazards(level: &Level, config: &GameConfig<N, E>) -> Vec<Hazard, MAX_HAZARDS> {
+        level
+            .hazards()
+            .iter()
+            .map(|hazard| config.scale_hazard(hazard))
+            .collect()
+    }
 
-        for goal in self.goals.iter() {
-            if goal.alive {
-                goals_alive.push(goal).unwrap();
+    /// Return the initial goal states for the given level. Without a random number
+    /// generator the goals are placed where the level defines them, otherwise the level
+    /// only defines the number of goals and they are placed at random locations. A goal
+    /// that cannot be placed at random is placed at the first free location from where the
+    /// level defines it, and if the goals placed so far leave no room for it at all, every
+    /// goal is placed where the level defines them. The goals are placed in the level area
+    /// and scaled to the play area.
+    /// # Arguments
+    /// * `level` - the level that is starting
+    /// * `config` - the configuration of the game
+    /// * `rng` - the random number generator used to place the goals, if any
+    fn initial_goals(
+        level: &Level,
+        config: &GameConfig<N, E>,
+        rng: Option<&mut Rng>,
+    ) -> Vec<Goal, N> {
+        let size = config.goal_size;
+        let defined = level
+            .goals()
+            .iter()
+            .map(|location| config.scale_location(*location, size));
+        // there is room for every goal, the configuration has been checked
+        let mut locations: Vec<Point, N> = Vec::new();
+        match rng {
+            None => locations.extend(defined),
+            Some(rng) => {
+                for fallback in level.goals() {
+                    let location = Self::random_goal_location(level, config, &locations, rng)
+                        .or_else(|| {
+                            Self::fallback_goal_location(level, config, &locations, *fallback)
+                        });
+                    match location {
+                        Some(location) => locations.push(location).unwrap(),
+                        None => {
+                            // the configuration has been checked for the goals to not overlap
+                            // where the level defines them
+                            locations.clear();
+                            locations.extend(defined);
+                            break;
+                        }
+                    }
+                }
             }
         }
-        goals_alive
+        locations
+            .iter()
+            .enumerate()
+            .map(|(index, location)| Goal::new(*location, index + 1, size))
+            .collect()
     }
-}
 
-impl Default for State {
-    fn default() -> Self {
-        Self::new()
+    /// Return a random goal location in the play area that does not overlap the given
+    /// goals, the obstacles, the holes or the initial ball location of the level. The
+    /// location is drawn in the level area and checked once it is scaled to the play area.
+    /// Return None if no such location was found in a reasonable number of attempts.
+    /// # Arguments
+    /// * `level` - the level that is starting
+    /// * `config` - the configuration of the game
+    /// * `goals` - the locations of the goals that are already placed in the play area
+    /// * `rng` - the random number generator used to place the goal
+    fn random_goal_location(
+        level: &Level,
+        config: &GameConfig<N, E>,
+        goals: &[Point],
+        rng: &mut Rng,
+    ) -> Option<Point> {
+        let area = config.level_area;
+        let size = config.goal_size;
+        for _ in 0..GOAL_PLACEMENT_ATTEMPTS {
+            let location = Point::new(
+                rng.range(
+                    area.top_left.x,
+                    area.top_left.x + (area.size.width - size) as i32,
+                ),
+                rng.range(
+                    area.top_left.y,
+                    area.top_left.y + (area.size.height - size) as i32,
+                ),
+            );
+            let location = config.scale_location(location, size);
+            if !config.goal_overlaps(level, goals, location) {
+                return Some(location);
+            }
+        }
+        None
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::State;
-    use crate::{
-        config::{ANGLE_THRESHOLD, BALL_DELTA, SCREEN_OUTLINE_SIZE, SCREEN_OUTLINE_TOP_LET},
-        smallball::Mode,
-    };
-    use embedded_graphics::prelude::Point;
-
-    #[test]
-    fn transition_from_intro_to_play_test() {
-        // GIVEN game state in intro mode
-        let mut state = State::new();
-        assert_eq!(*state.mode(), Mode::Intro);
-        // WHEN update is called
-        state.update(&0.0, &0.0);
-        // THEN game transitions to play mode
-        assert_eq!(*state.mode(), Mode::Play);
+    /// Return the location in the play area of the given goal location of the level if it
+    /// does not overlap the given goals, the obstacles, the holes or the initial ball
+    /// location of the level, otherwise the first location that does not, scanning the play
+    /// area row by row from its top left. Return None if there is no such location.
+    /// # Arguments
+    /// * `level` - the level that is starting
+    /// * `config` - the configuration of the game
+    /// * `goals` - the locations of the goals that are already placed in the play area
+    /// * `fallback` - the location of the goal in the level area where the level defines it
+    fn fallback_goal_location(
+        level: &Level,
+        config: &GameConfig<N, E>,
+        goals: &[Point],
+        fallback: Point,
+    ) -> Option<Point> {
+        let area = config.play_area;
+        let size = config.goal_size;
+        let xs = area.top_left.x..=area.top_left.x + (area.size.width - size) as i32;
+        let ys = area.top_left.y..=area.top_left.y + (area.size.height - size) as i32;
+        let scan = ys.flat_map(|y| xs.clone().map(move |x| Point::new(x, y)));
+        core::iter::once(config.scale_location(fallback, size))
+            .chain(scan)
+            .find(|location| !config.goal_overlaps(level, goals, *location))
     }
 
-    #[test]
-    fn transition_from_play_to_over_test() {
-        // GIVEN game state in play mode
-        let mut state = game_state_in_play_mode();
-
-        // GIVEN all goals dead
-        for goal in state.goals.iter_mut() {
-            goal.alive = false;
+    /// Reset the balls and the goals to start the given level.
+    /// # Arguments
+    /// * `level` - the index of the level to start
+    fn start_level(&mut self, level: usize) {
+        self.level = level;
+        self.level_start_score = self.score();
+        let definition = &self.config.levels[level];
+        for player in self.players.iter_mut() {
+            player.ball = Self::initial_ball(definition, &self.config);
+            player.hazard = None;
         }
-
-        // WHEN update is called
-        state.update(&0.0, &0.0);
-
-        // THEN game transitions to over mode and the low score is updated
-        assert_eq!(*state.mode(), Mode::Over);
-        assert_eq!(state.low_score(), 2);
+        self.goals = Self::initial_goals(definition, &self.config, self.rng.as_mut());
+        self.obstacles = Self::initial_obstacles(definition, &self.config);
+        self.hazards = Self::initial_hazards(definition, &self.config);
+        self.physics_ms = 0;
     }
 
-    #[test]
-    fn transition_from_over_to_play_test() {
-        // GIVEN game state in over mode
-        let mut state = game_state_in_play_mode();
-        for goal in state.goals.iter_mut() {
-            goal.alive = false;
-        }
-        state.update(&0.0, &0.0);
-        assert_eq!(*state.mode(), Mode::Over);
+    /// Update the state of a single player game based on the time that has passed since
+    /// the last update and the latest pitch and roll input from the mpu.
+    /// # Arguments
+    /// * `elapsed_ms` - the time in milliseconds since the last update
+    /// * `pitch` - the pitch reading from the mpu sensor
+    /// * `roll` - the roll reading from the mpu sensor
+    pub fn update(&mut self, elapsed_ms: u32, pitch: &f32, roll: &f32) {
+        self.update_players(elapsed_ms, &[Tilt::new(*pitch, *roll)]);
+    }
 
-        // WHEN update is called
-        state.update(&0.0, &0.0);
+    /// Update the state of the game based on the time that has passed since the last update
+    /// and the latest tilt of the mpu of each player. The intro, next level and game over
+    /// screens last until their time is over or until they are skipped by a strong tilt, the
+    /// intro and the high score table take turns until the game is started by a strong tilt.
+    /// The initials of a new high score are entered with the tilt of the first player.
+    /// # Arguments
+    /// * `elapsed_ms` - the time in milliseconds since the last update
+    /// * `tilts` - the tilt of the mpu of each player in player order, the ball of a
+    ///   player without a tilt is not accelerated
+    pub fn update_players(&mut self, elapsed_ms: u32, tilts: &[Tilt]) {
+        let inputs: Vec<ControlInput, MAX_PLAYERS> = tilts
+            .iter()
+            .take(MAX_PLAYERS)
+            .map(|tilt| ControlInput::new(*tilt, 0))
+            .collect();
+        self.update_controls(elapsed_ms, &inputs);
+    }
 
-        // THEN game transitions to play mode
-        assert_eq!(*state.mode(), Mode::Play);
+    /// Update the state of the game based on the time that has passed since the last update
+    /// and the latest control input of each player, as update_players does. The ball of a
+    /// player whose input is invalid is not accelerated nor measured by a calibration, and
+    /// the start button of any player skips a screen like a strong tilt does.
+    /// # Arguments
+    /// * `elapsed_ms` - the time in milliseconds since the last update
+    /// * `inputs` - the control input of each player in player order
+    pub fn update_inputs(&mut self, elapsed_ms: u32, inputs: &[ControlInput]) {
+        self.update_controls(elapsed_ms, inputs);
     }
 
-    #[test]
-    fn ball_moves_right_test() {
-        // GIVEN game state in play mode
-        let mut state = game_state_in_play_mode();
+    /// Start a calibration of the mpu sensors, which measures their tilt while the players
+    /// hold them in a neutral pose and then goes back to the intro. A game that is being
+    /// played is abandoned.
+    pub fn calibrate(&mut self) {
+        self.gesture_ms = 0;
+        self.calibration_sums = [Tilt::default(); MAX_PLAYERS];
+        self.calibration_samples = [0; MAX_PLAYERS];
+        self.set_mode(Mode::Calibrate);
+    }
 
-        // WHEN the roll is positive and above threshold
-        state.update(&0.0, &(ANGLE_THRESHOLD + 0.1));
+    /// Update the state of the game based on the time that has passed since the last update
+    /// and the latest controls of the players.
+    /// # Arguments
+    /// * `elapsed_ms` - the time in milliseconds since the last update
+    /// * `inputs` - the control input of each player in player order
+    fn update_controls(&mut self, elapsed_ms: u32, inputs: &[ControlInput]) {
+        let tilts: Vec<Tilt, MAX_PLAYERS> = inputs
+            .iter()
+            .take(MAX_PLAYERS)
+            .map(|input| input.tilt())
+            .collect();
+        let start = inputs.iter().any(|input| input.is_pressed(BUTTON_START));
+        self.events.clear();
+        match self.mode {
+            Mode::Play => {
+                self.update_score(elapsed_ms);
+                self.update_physics(elapsed_ms, &tilts);
+            }
+            Mode::EnterInitials => {
+                let tilt = tilts.first().copied().unwrap_or_default();
+                let (angle, repeat_ms) =
+                    (self.config.initials_angle, self.config.initials_repeat_ms);
+                self.initials.update(elapsed_ms, tilt, angle, repeat_ms);
+            }
+            Mode::Intro => {
+                // the gesture is a tilt that is held between the calibrate and skip angles
+                let (angle, skip_angle) = (self.config.calibrate_angle, self.config.skip_angle);
+                let held = tilts
+                    .iter()
+                    .any(|tilt| tilt.exceeds(angle) && !tilt.exceeds(skip_angle));
+                self.gesture_ms = if held {
+                    self.gesture_ms.saturating_add(elapsed_ms)
+                } else {
+                    0
+                };
+            }
+            Mode::Calibrate if self.mode_ms >= self.config.calibrate_settle_ms => {
+                // an input that could not be read is not a measurement of the pose
+                let measured = self
+                    .calibration_sums
+                    .iter_mut()
+                    .zip(self.calibration_samples.iter_mut())
+                    .zip(inputs);
+                for ((sum, samples), input) in measured.filter(|(_, input)| input.is_valid()) {
+                    sum.pitch += input.tilt().pitch;
+                    sum.roll += input.tilt().roll;
+                    *samples += 1;
+                }
+            }
+            _ => {}
+        }
+        self.mode_ms = self.mode_ms.saturating_add(elapsed_ms);
+        self.update_mode(&tilts, start);
+    }
 
-        // THEN the ball moves to the right
-        assert_eq!(state.ball().location(), ball_location_delta(BALL_DELTA, 0));
+    /// Advance the ball physics by the time that has passed, in fixed steps. Time that
+    /// is shorter than a step is kept for the next update. The goals are checked after
+    /// every step, so that no goal is missed however much time has passed.
+    /// # Arguments
+    /// * `elapsed_ms` - the time in milliseconds since the last update
+    /// * `tilts` - the tilt of the mpu of each player in player order
+    fn update_physics(&mut self, elapsed_ms: u32, tilts: &[Tilt]) {
+        self.physics_ms += elapsed_ms;
+        while self.physics_ms >= self.config.physics_step_ms {
+            self.physics_ms -= self.config.physics_step_ms;
+            for player in 0..self.players.len() {
+                let tilt = tilts.get(player).copied().unwrap_or_default();
+                let from = self.players[player].ball.location();
+                self.update_ball(player, tilt);
+                self.update_goals(player, from);
+                self.update_hazards(player);
+            }
+        }
     }
 
-    #[test]
-    fn ball_moves_left_test() {
-        // GIVEN game state in play mode
-        let mut state = game_state_in_play_mode();
+    /// Return the time in milliseconds the current game mode lasts, or None if the mode
+    /// lasts until the goals of the level have been visited.
+    fn mode_duration(&self) -> Option<u32> {
+        match self.mode {
+            Mode::Intro => Some(self.config.intro_ms),
+            Mode::Play => None,
+            Mode::NextLevel => Some(self.config.next_level_ms),
+            Mode::Over => Some(self.config.over_ms),
+            Mode::EnterInitials => Some(self.config.initials_ms),
+            Mode::HighScores => Some(self.config.high_scores_ms),
+            Mode::Calibrate => Some(self.config.calibrate_ms),
+        }
+    }
 
-        // WHEN the roll is negative and above threshold
-        state.update(&0.0, &-(ANGLE_THRESHOLD + 0.1));
+    /// Switch to the given game mode.
+    /// # Arguments
+    /// * `mode` - the new game mode
+    fn set_mode(&mut self, mode: Mode) {
+        self.mode = mode;
+        self.mode_ms = 0;
+        self.raise(GameEvent::ModeChanged { mode });
+    }
 
-        // THEN the ball moves to the left
-        assert_eq!(state.ball().location(), ball_location_delta(-BALL_DELTA, 0));
+    /// Raise an event of the current update. An event that has already been raised during
+    /// the update is not raised again.
+    /// # Arguments
+    /// * `event` - the event to raise
+    fn raise(&mut self, event: GameEvent) {
+        if !self.events.contains(&event) {
+            // there is room for every distinct event of an update, the event capacity has
+            // been checked
+            self.events.push(event).unwrap();
+        }
     }
 
-    #[test]
+    /// Update the game mode.
+    /// # Arguments
+    /// * `tilts` - the tilt of the mpu of each player in player order
+    /// * `start` - whether the start button of any player is pressed
+    fn update_mode(&mut self, tilts: &[Tilt], start: bool) {
+        // a screen can only be skipped after a while, so that a tilt that is still held
+        // from the previous mode does not skip it right away
+        let timed_out = self
+            .mode_duration()
+            .is_some_and(|duration| self.mode_ms >= duration);
+        let skipped = self.mode_ms >= self.config.skip_min_ms
+            && (start
+                || tilts
+                    .iter()
+                    .any(|tilt| tilt.exceeds(self.config.skip_angle)));
+        let done = timed_out || skipped;
+        match self.mode {
+            Mode::Intro => {
+                // the game starts by itself until there are high scores to show, and the
+                // intro waits while the calibration gesture is held
+                let timed_out = timed_out && self.gesture_ms == 0;
+                if self.gesture_ms >= self.config.calibrate_hold_ms {
+                    self.calibrate();
+                } else if skipped || (timed_out && self.high_scores.entries().is_empty()) {
+                    self.set_mode(Mode::Play);
+                } else if timed_out {
+                    self.set_mode(Mode::HighScores);
+                }
+            }
+            Mode::HighScores => {
+                if skipped {
+                    self.set_mode(Mode::Play);
+                } else if timed_out {
+                    self.set_mode(Mode::Intro);
+                }
+            }
+            Mode::Calibrate => {
+                if timed_out {
+                    // a calibration that measured nothing for a player would level their
+                    // offset, so it is dropped rather than kept
+                    let samples = &self.calibration_samples[..self.players.len()];
+                    if samples.iter().all(|samples| *samples > 0) {
+                        self.raise(GameEvent::Calibrated);
+                    }
+                    self.set_mode(Mode::Intro);
+                }
+            }
+            Mode::NextLevel => {
+                if done {
+                    self.set_mode(Mode::Play);
+                }
+            }
+            Mode::Play => {
+                if self.goals.iter().all(|goal| !goal.alive) {
+                    if self.level + 1 < self.config.levels.len() {
+                        self.set_mode(Mode::NextLevel);
+                        self.start_level(self.level + 1);
+                    } else {
+                        self.set_mode(Mode::Over);
+                        // the low score is only kept for single player games
+                        let score = self.score();
+                        let new_record = self.players.len() == 1 && score < self.low_score;
+                        if new_record {
+                            self.low_score = score;
+                            self.best_splits = Vec::from_slice(self.run.splits()).unwrap();
+                        }
+                        self.raise(GameEvent::GameOver { score, new_record });
+                    }
+                }
+            }
+            Mode::Over => {
+                if done {
+                    // only the score of a single player game can be a high score
+                    let single = self.players.len() == 1;
+                    if single && self.high_scores.rank(self.score()).is_some() {
+                        self.set_mode(Mode::EnterInitials);
+                        self.initials = InitialsEntry::new();
+                    } else {
+                        self.set_mode(Mode::Play);
+                        self.restart();
+                    }
+                }
+            }
+            Mode::EnterInitials => {
+                // the initials are taken as they are when the time to enter them is over
+                if self.initials.is_done() || timed_out {
+                    self.set_mode(Mode::HighScores);
+                    let initials = self.initials.initials();
+                    if let Some(rank) = self.high_scores.insert(initials, self.score()) {
+                        self.raise(GameEvent::HighScoreEntered { rank });
+                    }
+                    self.restart();
+                }
+            }
+        }
+    }
+
+    /// Reset the results of every player and start the first level, for a new game.
+    fn restart(&mut self) {
+        self.run = RunStats::new(&self.best_splits);
+        for player in self.players.iter_mut() {
+            player.score = 0;
+            player.goals = 0;
+            player.time = 0;
+        }
+        self.start_level(0);
+    }
+
+    /// Update the game score.
+    /// # Arguments
+    /// * `elapsed_ms` - the time in milliseconds since the last update
+    fn update_score(&mut self, elapsed_ms: u32) {
+        // score is the time played so the longer it takes to reach each goal,
+        // the higher your score. Lower scores are better.
+        for player in self.players.iter_mut() {
+            player.score += elapsed_ms as i32;
+        }
+    }
+
+    /// Advance the state of the ball of a player by one physics step based on the tilt of
+    /// their mpu. The tilt of the sensor accelerates the ball, friction slows it down, the
+    /// ball keeps its velocity between steps and it bounces off the walls of the game space
+    /// and the obstacles.
+    /// # Arguments
+    /// * `player` - the index of the player whose ball is moved
+    /// * `tilt` - the tilt of the mpu of the player
+    fn update_ball(&mut self, player: usize, tilt: Tilt) {
+        let ball = &self.players[player].ball;
+        let size = ball.size();
+
+        // if the sensor is pitched down then the ball accelerates up the screen and if the
+        // sensor is rolled up then the ball accelerates right on the screen, unless the axis
+        // is inverted by the input shaping
+        let response = self.config.input_shaping.shape(tilt);
+        let mut velocity = Vector::new(
+            Self::axis_velocity(&self.config, ball.velocity.x, response.roll),
+            Self::axis_velocity(&self.config, ball.velocity.y, -response.pitch),
+        );
+        let mut position = ball.position + velocity;
+
+        // the ball bounces when it reaches a boundary of the game space
+        let previous = ball.position;
+        let (x_min, x_max) = (self.config.x_min(), self.config.x_max());
+        let (y_min, y_max) = (self.config.y_min(), self.config.y_max());
+        if position.x < x_min as f32 {
+            self.hit_wall(
+                player,
+                &mut position.x,
+                &mut velocity.x,
+                previous.x,
+                x_min,
+                Wall::Left,
+            );
+        } else if position.x > x_max as f32 {
+            self.hit_wall(
+                player,
+                &mut position.x,
+                &mut velocity.x,
+                previous.x,
+                x_max,
+                Wall::Right,
+            );
+        }
+        if position.y < y_min as f32 {
+            self.hit_wall(
+                player,
+                &mut position.y,
+                &mut velocity.y,
+                previous.y,
+                y_min,
+                Wall::Top,
+            );
+        } else if position.y > y_max as f32 {
+            self.hit_wall(
+                player,
+                &mut position.y,
+                &mut velocity.y,
+                previous.y,
+                y_max,
+                Wall::Bottom,
+            );
+        }
+
+        // the ball bounces off the side of the first obstacle it runs into on its way, even
+        // a thin obstacle it would pass through within a single step, and keeps sliding
+        // along that side
+        for _ in 0..self.obstacles.len() {
+            let contact = self
+                .obstacles
+                .iter()
+                .filter_map(|obstacle| {
+                    sweep(previous, position, size, obstacle.location, obstacle.size)
+                        .map(|(time, axis)| (time, axis, *obstacle))
+                })
+                .fold(
+                    None,
+                    |first: Option<(f32, Axis, Obstacle)>, contact| match first {
+                        Some(first) if first.0 <= contact.0 => Some(first),
+                        _ => Some(contact),
+                    },
+                );
+            let (axis, obstacle) = match contact {
+                Some((_, axis, obstacle)) => (axis, obstacle),
+                None => break,
+            };
+            match axis {
+                Axis::X => {
+                    let (boundary, wall) = if position.x > previous.x {
+                        (obstacle.location.x - size as i32, Wall::Right)
+                    } else {
+                        let width = obstacle.size.width as i32;
+                        (obstacle.location.x + width, Wall::Left)
+                    };
+                    self.hit_wall(
+                        player,
+                        &mut position.x,
+                        &mut velocity.x,
+                        previous.x,
+                        boundary,
+                        wall,
+                    );
+                }
+                Axis::Y => {
+                    let (boundary, wall) = if position.y > previous.y {
+                        (obstacle.location.y - size as i32, Wall::Bottom)
+                    } else {
+                        let height = obstacle.size.height as i32;
+                        (obstacle.location.y + height, Wall::Top)
+                    };
+                    self.hit_wall(
+                        player,
+                        &mut position.y,
+                        &mut velocity.y,
+                        previous.y,
+                        boundary,
+                        wall,
+                    );
+                }
+            }
+        }
+
+        // a ball that already overlaps an obstacle is pushed out of it the shortest way
+        for index in 0..self.obstacles.len() {
+            let obstacle = self.obstacles[index];
+            if let Some(push) = separation(position, size, obstacle.location, obstacle.size) {
+                if push.x != 0.0 {
+                    let wall = if push.x < 0.0 {
+                        Wall::Right
+                    } else {
+                        Wall::Left
+                    };
+                    let boundary = round(position.x + push.x);
+                    self.hit_wall(
+                        player,
+                        &mut position.x,
+                        &mut velocity.x,
+                        previous.x,
+                        boundary,
+                        wall,
+                    );
+                } else {
+                    let wall = if push.y < 0.0 {
+                        Wall::Bottom
+                    } else {
+                        Wall::Top
+                    };
+                    let boundary = round(position.y + push.y);
+                    self.hit_wall(
+                        player,
+                        &mut position.y,
+                        &mut velocity.y,
+                        previous.y,
+                        boundary,
+                        wall,
+                    );
+                }
+            }
+        }
+
+        let ball = &mut self.players[player].ball;
+        ball.position = position;
+        ball.velocity = velocity;
+    }
+
+    /// Move the ball back onto the wall it has crossed and reflect its velocity away from
+    /// the wall. The wall is raised as hit if the ball bounces. A ball that is too slow
+    /// to bounce, or that is already resting on the wall and is pushed into it by the tilt,
+    /// comes to rest on the wall instead.
+    /// # Arguments
+    /// * `player` - the index of the player whose ball crossed the wall
+    /// * `position` - the position of the ball along the axis perpendicular to the wall
+    /// * `velocity` - the velocity of the ball along the axis perpendicular to the wall
+    /// * `previous` - the position of the ball along the same axis before this update
+    /// * `boundary` - the position of the wall
+    /// * `wall` - the wall that was crossed
+    fn hit_wall(
+        &mut self,
+        player: usize,
+        position: &mut f32,
+        velocity: &mut f32,
+        previous: f32,
+        boundary: i32,
+        wall: Wall,
+    ) {
+        *position = boundary as f32;
+        let stop_speed = self.config.ball_stop_speed;
+        *velocity = -*velocity * self.config.wall_restitution;
+        if previous == *position || (*velocity > -stop_speed && *velocity < stop_speed) {
+            *velocity = 0.0;
+        } else {
+            self.raise(GameEvent::WallHit { player, wall });
+        }
+    }
+
+    /// Return the new velocity of the ball along one axis.
+    /// # Arguments
+    /// * `config` - the configuration of the game
+    /// * `velocity` - the current velocity of the ball along the axis
+    /// * `response` - the shaped tilt of the sensor along the axis (see shaping.rs), positive
+    ///   responses accelerate the ball in the positive direction
+    fn axis_velocity(config: &GameConfig<N, E>, velocity: f32, response: f32) -> f32 {
+        let mut velocity = velocity * (1.0 - config.ball_friction);
+
+        if response != 0.0 {
+            velocity += response * config.ball_acceleration;
+        } else if velocity > -config.ball_stop_speed && velocity < config.ball_stop_speed {
+            // without any tilt a slow ball comes to rest
+            velocity = 0.0;
+        }
+
+        velocity.clamp(-config.ball_max_speed, config.ball_max_speed)
+    }
+
+    /// Check whether the ball of a player has fallen into a hole. The effect of the hole
+    /// applies once when the centre of the ball enters the hole.
+    /// # Arguments
+    /// * `index` - the index of the player whose ball is checked
+    fn update_hazards(&mut self, index: usize) {
+        let level = self.current_level();
+        let player = &mut self.players[index];
+        // the centre of the ball as a circle of no size, or of one pixel if the size of the
+        // ball is odd
+        let size = player.ball.size();
+        let centre = player.ball.location() + Point::new_equal(size as i32 / 2);
+        let hazard = self
+            .hazards
+            .iter()
+            .position(|hazard| circles_intersect(centre, size % 2, hazard.location, hazard.size));
+
+        let entered = hazard.filter(|_| hazard != player.hazard);
+        if let Some(hazard) = entered {
+            match self.hazards[hazard].effect {
+                HazardEffect::Reset => player.ball = Self::initial_ball(level, &self.config),
+                HazardEffect::Penalty(penalty) => player.score += penalty,
+            }
+        }
+        player.hazard = hazard;
+        if let Some(hazard) = entered {
+            self.raise(GameEvent::FellIntoHole {
+                player: index,
+                hazard: self.hazards[hazard],
+            });
+        }
+    }
+
+    /// Update the goal states based on whether or not they have been newly visited by the
+    /// ball of a player during its last physics step. A goal is visited when the round ball
+    /// touches it anywhere on its way, not just its bounding square where it ends up. Once
+    /// visited the goal is dead and credited to the player, the players are moved in order
+    /// so the first player wins a tie. In the sequence variant only the target goal can be
+    /// visited.
+    /// # Arguments
+    /// * `player` - the index of the player whose ball moved
+    /// * `from` - the location of the ball before its last physics step
+    fn update_goals(&mut self, player: usize, from: Point) {
+        let target = self.target_goal().map(|goal| goal.number);
+        let ball = &self.players[player].ball;
+        let (to, size) = (ball.location(), ball.size());
+        for index in 0..self.goals.len() {
+            let goal = &self.goals[index];
+            if !goal.alive || (target.is_some() && target != Some(goal.number)) {
+                continue;
+            }
+            if swept_circle_intersects_rectangle(
+                from,
+                to,
+                size,
+                goal.location,
+                Size::new_equal(goal.size()),
+            ) {
+                // the goal is reached during the physics step that has just been simulated,
+                // before the time that is not yet simulated
+                let time = self.players[player].score - self.physics_ms as i32;
+                self.goals[index].alive = false;
+                self.goals[index].time = Some(time);
+                if player == 0 {
+                    self.run.split(time);
+                }
+                let visitor = &mut self.players[player];
+                visitor.goals += 1;
+                visitor.time = time;
+                self.raise(GameEvent::GoalReached { index, player });
+            }
+        }
+    }
+
+    /// Return the top left point that defines the screen outline rectangle.
+    pub fn screen_outline_top_left(&self) -> Point {
+        self.config.screen_outline.top_left
+    }
+
+    /// Return the size of the screen outline rectangle.
+    pub fn screen_outline_size(&self) -> Size {
+        self.config.screen_outline.size
+    }
+
+    /// Return the goal that has to be visited next in the sequence variant of the game, or
+    /// None if the goals can be visited in any order or all goals have been visited.
+    pub fn target_goal(&self) -> Option<&Goal> {
+        match self.variant {
+            GameVariant::Free => None,
+            GameVariant::Sequence => self.goals.iter().find(|goal| goal.alive),
+        }
+    }
+
+    /// Write a snapshot of the game into the given buffer and return the size of the save in
+    /// bytes. The save covers the mode, the scores, the balls, the goals, the level, the high
+    /// score table, the initials being entered and the splits, but not the configuration,
+    /// the game is restored with the same configuration. A calibration is not part of the
+    /// game, a game that is being calibrated is saved at the start of the intro.
+    /// # Arguments
+    /// * `buffer` - the buffer the save is written into, see save_size for its size
+    pub fn serialize_into(&self, buffer: &mut [u8]) -> Result<usize, SaveError> {
+        let mut writer = Writer::new(buffer);
+        writer.put_u8(match self.mode {
+            Mode::Intro | Mode::Calibrate => 0,
+            Mode::Play => 1,
+            Mode::NextLevel => 2,
+            Mode::Over => 3,
+            Mode::EnterInitials => 4,
+            Mode::HighScores => 5,
+        })?;
+        writer.put_u32(match self.mode {
+            Mode::Calibrate => 0,
+            _ => self.mode_ms,
+        })?;
+        writer.put_u8(match self.variant {
+            GameVariant::Free => 0,
+            GameVariant::Sequence => 1,
+        })?;
+        writer.put_u8(self.level as u8)?;
+        writer.put_i32(self.level_start_score)?;
+        writer.put_i32(self.low_score)?;
+        writer.put_u32(self.physics_ms)?;
+        writer.put_u8(self.rng.is_some() as u8)?;
+        writer.put_u32(self.rng.as_ref().map_or(0, |rng| rng.state()))?;
+        writer.put_u8(self.players.len() as u8)?;
+        for player in self.players.iter() {
+            writer.put_i32(player.score)?;
+            writer.put_u8(player.goals as u8)?;
+            writer.put_i32(player.time)?;
+            writer.put_u8(player.hazard.map_or(u8::MAX, |hazard| hazard as u8))?;
+            writer.put_f32(player.ball.position.x)?;
+            writer.put_f32(player.ball.position.y)?;
+            writer.put_f32(player.ball.velocity.x)?;
+            writer.put_f32(player.ball.velocity.y)?;
+        }
+        writer.put_u8(self.goals.len() as u8)?;
+        for goal in self.goals.iter() {
+            writer.put_i16(goal.location.x as i16)?;
+            writer.put_i16(goal.location.y as i16)?;
+            writer.put_u8(goal.number as u8)?;
+            writer.put_u8(goal.alive as u8)?;
+        }
+        writer.put_u8(self.high_scores.entries().len() as u8)?;
+        for entry in self.high_scores.entries() {
+            for letter in entry.initials().letters() {
+                writer.put_u8(letter)?;
+            }
+            writer.put_i32(entry.score())?;
+        }
+        for letter in self.initials.initials().letters() {
+            writer.put_u8(letter)?;
+        }
+        writer.put_u8(self.initials.position() as u8)?;
+        for goal in self.goals.iter() {
+            writer.put_i32(goal.time.unwrap_or(-1))?;
+        }
+        for splits in [self.run.splits(), self.run.best(), &self.best_splits] {
+            writer.put_u8(splits.len() as u8)?;
+            for split in splits {
+                writer.put_i32(*split)?;
+            }
+        }
+        writer.finish()
+    }
+
+    /// Return the game restored from the given save, played with the given configuration.
+    /// A save written in a version of the format this version of the game does not know is
+    /// rejected rather than misread.
+    /// # Arguments
+    /// * `config` - the configuration the saved game was played with
+    /// * `bytes` - the save written by serialize_into
+    pub fn deserialize_with_config(
+        config: GameConfig<N, E>,
+        bytes: &[u8],
+    ) -> Result<Self, SaveError> {
+        let (mut reader, version) = Reader::new(bytes)?;
+        match version {
+            1..=3 => Self::read_payload(config, &mut reader, version),
+            version => Err(SaveError::UnsupportedVersion { version }),
+        }
+    }
+
+    /// Return the game restored from the payload of a save in version 1, 2 or 3 of the
+    /// format. Version 2 adds the high score modes, the high score table and the initials
+    /// being entered, a game restored from version 1 has an empty high score table. Version 3
+    /// adds the time each goal was reached and the splits, a game restored from an earlier
+    /// version has no splits. A payload that is longer than its version says is rejected.
+    /// # Arguments
+    /// * `config` - the configuration the saved game was played with
+    /// * `reader` - the reader of the payload
+    /// * `version` - the version of the format of the save
+    fn read_payload(
+        config: GameConfig<N, E>,
+        reader: &mut Reader,
+        version: u8,
+    ) -> Result<Self, SaveError> {
+        let mode = match (reader.u8()?, version) {
+            (0, _) => Mode::Intro,
+            (1, _) => Mode::Play,
+            (2, _) => Mode::NextLevel,
+            (3, _) => Mode::Over,
+            (4, 2..) => Mode::EnterInitials,
+            (5, 2..) => Mode::HighScores,
+            _ => return Err(SaveError::InvalidData),
+        };
+        let mode_ms = reader.u32()?;
+        let variant = match reader.u8()? {
+            0 => GameVariant::Free,
+            1 => GameVariant::Sequence,
+            _ => return Err(SaveError::InvalidData),
+        };
+        let level = reader.u8()? as usize;
+        let definition = config.levels.get(level).ok_or(SaveError::InvalidData)?;
+        let mut state = Self::new_with_config(config).with_variant(variant);
+        state.mode = mode;
+        state.mode_ms = mode_ms;
+        state.level = level;
+        state.obstacles = Self::initial_obstacles(definition, &config);
+        state.hazards = Self::initial_hazards(definition, &config);
+        state.level_start_score = reader.i32()?;
+        state.low_score = reader.i32()?;
+        // the time not yet simulated is always shorter than a physics step
+        state.physics_ms = reader.u32()?;
+        if state.physics_ms >= config.physics_step_ms {
+            return Err(SaveError::InvalidData);
+        }
+        let seeded = reader.u8()? != 0;
+        let seed = reader.u32()?;
+        state.rng = if seeded { Some(Rng::new(seed)) } else { None };
+
+        let players = reader.u8()? as usize;
+        if !(1..=MAX_PLAYERS).contains(&players) {
+            return Err(SaveError::InvalidData);
+        }
+        state.players.clear();
+        for _ in 0..players {
+            let ball = Self::initial_ball(definition, &config);
+            let mut player = Player::new(ball);
+            player.score = reader.i32()?;
+            player.goals = reader.u8()? as usize;
+            player.time = reader.i32()?;
+            player.hazard = match reader.u8()? {
+                u8::MAX => None,
+                hazard if (hazard as usize) < state.hazards.len() => Some(hazard as usize),
+                _ => return Err(SaveError::InvalidData),
+            };
+            player.ball.position = Vector::new(reader.f32()?, reader.f32()?);
+            player.ball.velocity = Vector::new(reader.f32()?, reader.f32()?);
+            let (position, velocity) = (player.ball.position, player.ball.velocity);
+            if ![position.x, position.y, velocity.x, velocity.y]
+                .iter()
+                .all(|value| value.is_finite())
+            {
+                return Err(SaveError::InvalidData);
+            }
+            // there is room for every player, the number of players has been checked
+            state.players.push(player).unwrap();
+        }
+
+        let goals = reader.u8()? as usize;
+        if goals > N {
+            return Err(SaveError::InvalidData);
+        }
+        state.goals.clear();
+        for _ in 0..goals {
+            let location = Point::new(reader.i16()? as i32, reader.i16()? as i32);
+            let mut goal = Goal::new(location, reader.u8()? as usize, config.goal_size);
+            goal.alive = reader.u8()? != 0;
+            // there is room for every goal, the number of goals has been checked
+            state.goals.push(goal).unwrap();
+        }
+        if version == 1 {
+            return reader.finish().map(|_| state);
+        }
+
+        let entries = reader.u8()? as usize;
+        if entries > HIGH_SCORES {
+            return Err(SaveError::InvalidData);
+        }
+        for _ in 0..entries {
+            let initials = Self::read_initials(reader)?;
+            let score = reader.i32()?;
+            // the scores were saved from best to worst, so each one ranks below the ones
+            // before it
+            if state.high_scores.rank(score) != Some(state.high_scores.entries().len()) {
+                return Err(SaveError::InvalidData);
+            }
+            state.high_scores.insert(initials, score);
+        }
+        let initials = Self::read_initials(reader)?;
+        let position = reader.u8()? as usize;
+        state.initials =
+            InitialsEntry::restore(initials, position).ok_or(SaveError::InvalidData)?;
+        if version == 2 {
+            return reader.finish().map(|_| state);
+        }
+
+        for goal in state.goals.iter_mut() {
+            goal.time = match reader.i32()? {
+                -1 => None,
+                time => Some(time),
+            };
+        }
+        let splits = Self::read_splits(reader)?;
+        state.run = RunStats::new(&Self::read_splits(reader)?);
+        for split in splits {
+            state.run.split(split);
+        }
+        state.best_splits = Self::read_splits(reader)?;
+        reader.finish().map(|_| state)
+    }
+
+    /// Return the splits read from a save.
+    /// # Arguments
+    /// * `reader` - the reader of the payload
+    fn read_splits(reader: &mut Reader) -> Result<Vec<i32, MAX_SPLITS>, SaveError> {
+        let count = reader.u8()? as usize;
+        if count > MAX_SPLITS {
+            return Err(SaveError::InvalidData);
+        }
+        let mut splits = Vec::new();
+        for _ in 0..count {
+            // there is room for every split, the number of splits has been checked
+            splits.push(reader.i32()?).unwrap();
+        }
+        Ok(splits)
+    }
+
+    /// Return the initials read from a save.
+    /// # Arguments
+    /// * `reader` - the reader of the payload
+    fn read_initials(reader: &mut Reader) -> Result<Initials, SaveError> {
+        let mut letters = [0; INITIALS];
+        for letter in letters.iter_mut() {
+            *letter = reader.u8()?;
+        }
+        Initials::new(letters).ok_or(SaveError::InvalidData)
+    }
+
+    /// Return the vector of goals that are still alive.
+    pub fn goals_alive(&self) -> Vec<&Goal, N> {
+        let mut goals_alive = Vec::new();
+
+        for goal in self.goals.iter() {
+            if goal.alive {
+                goals_alive.push(goal).unwrap();
+            }
+        }
+        goals_alive
+    }
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{GameEvent, GameVariant, Hazard, HazardEffect, State, Tilt, Wall};
+    use crate::{
+        config::{
+            max_events, GameConfig, GameConfigBuilder, ANGLE_THRESHOLD, BALL_FRICTION,
+            BALL_MAX_SPEED, BALL_SIZE, CALIBRATE_ANGLE, CALIBRATE_HOLD_MS, CALIBRATE_MS,
+            CALIBRATE_SETTLE_MS, GOAL_SIZE, HAZARD_PENALTY, HIGH_SCORES, HIGH_SCORES_MS,
+            INITIALS_ANGLE, INTRO_MS, LEVELS, MAX_PLAYERS, MAX_SPLITS, NEXT_LEVEL_MS, OVER_MS,
+            PHYSICS_STEP_MS, SCREEN_OUTLINE_SIZE, SCREEN_OUTLINE_TOP_LET, SKIP_ANGLE, SKIP_MIN_MS,
+            WALL_RESTITUTION, X_MAX, X_MIN, Y_MAX, Y_MIN,
+        },
+        highscores::{HighScoreTable, Initials},
+        input::{ControlInput, InputSource, ScriptedInput, BUTTON_START},
+        layout::Layout,
+        level::{total_goals, Level},
+        math::{intersects, intersects_rectangle, Vector},
+        rng::Rng,
+        save::{crc32, save_size, SaveError},
+        shaping::InputPreset,
+        smallball::Mode,
+        stats::RunStats,
+    };
+    use embedded_graphics::{
+        prelude::{Point, Size},
+        primitives::Rectangle,
+    };
+    use heapless::Vec;
+
+    #[test]
+    fn transition_from_intro_to_play_test() {
+        // GIVEN game state in intro mode
+        let mut state = State::new();
+        assert_eq!(*state.mode(), Mode::Intro);
+        // WHEN update is called before the intro is over
+        state.update(INTRO_MS - 1, &0.0, &0.0);
+        // THEN game stays in intro mode
+        assert_eq!(*state.mode(), Mode::Intro);
+        // WHEN the intro is over
+        state.update(1, &0.0, &0.0);
+        // THEN game transitions to play mode
+        assert_eq!(*state.mode(), Mode::Play);
+    }
+
+    #[test]
+    fn strong_tilt_skips_intro_test() {
+        // GIVEN game state in intro mode
+        let mut state = State::new();
+
+        // WHEN the mpu is strongly tilted as soon as the intro starts
+        state.update(SKIP_MIN_MS - 1, &(SKIP_ANGLE + 0.1), &0.0);
+
+        // THEN the intro is not skipped yet
+        assert_eq!(*state.mode(), Mode::Intro);
+
+        // WHEN the mpu is tilted less than the skip angle
+        state.update(1, &0.0, &(SKIP_ANGLE - 0.1));
+
+        // THEN the intro is not skipped
+        assert_eq!(*state.mode(), Mode::Intro);
+
+        // WHEN the mpu is strongly tilted after the minimum time
+        state.update(0, &0.0, &-(SKIP_ANGLE + 0.1));
+
+        // THEN the intro is skipped
+        assert_eq!(*state.mode(), Mode::Play);
+        assert_eq!(state.score(), 0);
+    }
+
+    #[test]
+    fn start_button_skips_intro_test() {
+        // GIVEN game state in intro mode and a controller that presses the start button,
+        // except when it cannot be read
+        let mut state = State::new();
+        let start = ControlInput::new(Tilt::default(), BUTTON_START);
+        let inputs = [start, ControlInput::invalid(), start];
+        let mut source = ScriptedInput::new(&inputs);
+
+        // WHEN the start button is pressed as soon as the intro starts
+        state.update_inputs(SKIP_MIN_MS - 1, &[source.read()]);
+
+        // THEN the intro is not skipped yet
+        assert_eq!(*state.mode(), Mode::Intro);
+
+        // WHEN the controller cannot be read after the minimum time
+        state.update_inputs(1, &[source.read()]);
+
+        // THEN the intro is not skipped
+        assert_eq!(*state.mode(), Mode::Intro);
+
+        // WHEN the start button is pressed after the minimum time
+        state.update_inputs(0, &[source.read()]);
+
+        // THEN the intro is skipped
+        assert_eq!(*state.mode(), Mode::Play);
+    }
+
+    #[test]
+    fn calibration_gesture_test() {
+        // GIVEN game state in intro mode
+        let mut state = State::new();
+        let gesture = CALIBRATE_ANGLE + 0.1;
+
+        // WHEN the calibration gesture is held past the end of the intro
+        state.update(INTRO_MS - 1000, &0.0, &0.0);
+        state.update(990, &0.0, &gesture);
+        state.update(20, &-gesture, &0.0);
+
+        // THEN the intro waits for the gesture
+        assert_eq!(*state.mode(), Mode::Intro);
+
+        // WHEN the gesture is let go before it has been held long enough
+        state.update(10, &0.0, &0.0);
+
+        // THEN the intro is over
+        assert_eq!(*state.mode(), Mode::Play);
+
+        // WHEN the gesture is held long enough in the next intro
+        let mut state = State::new();
+        state.update(CALIBRATE_HOLD_MS - 1, &gesture, &0.0);
+        assert_eq!(*state.mode(), Mode::Intro);
+        state.update(1, &gesture, &0.0);
+
+        // THEN the game is calibrated
+        assert_eq!(*state.mode(), Mode::Calibrate);
+        assert_eq!(
+            state.events(),
+            &[GameEvent::ModeChanged {
+                mode: Mode::Calibrate
+            }]
+        );
+
+        // GIVEN a configuration with another gesture and calibration times
+        let config = GameConfig::builder()
+            .with_calibration_gesture(0.2, 500)
+            .with_calibration_times(1000, 200)
+            .build()
+            .unwrap();
+        let mut state = State::new_with_config(config);
+
+        // WHEN the gesture is held
+        state.update(500, &0.3, &0.0);
+
+        // THEN the game is calibrated for the configured time
+        assert_eq!(*state.mode(), Mode::Calibrate);
+        state.update(999, &0.0, &0.0);
+        assert_eq!(*state.mode(), Mode::Calibrate);
+        state.update(1, &0.0, &0.0);
+        assert_eq!(*state.mode(), Mode::Intro);
+    }
+
+    #[test]
+    fn calibration_test() {
+        // GIVEN a two player game that is calibrated
+        let mut state = State::new().with_players(2);
+        state.calibrate();
+        assert_eq!(*state.mode(), Mode::Calibrate);
+
+        // WHEN the mpu sensors are strongly tilted while they settle
+        state.update_players(CALIBRATE_SETTLE_MS, &[Tilt::new(SKIP_ANGLE + 0.1, 0.0); 2]);
+
+        // THEN the calibration is not skipped
+        assert_eq!(*state.mode(), Mode::Calibrate);
+
+        // WHEN the mpu sensors are held at rest for the rest of the time
+        let tilts = [Tilt::new(0.05, -0.02), Tilt::new(-0.1, 0.04)];
+        for _ in 0..(CALIBRATE_MS - CALIBRATE_SETTLE_MS) / 100 {
+            assert_eq!(*state.mode(), Mode::Calibrate);
+            state.update_players(100, &tilts);
+        }
+
+        // THEN the offsets are the average tilt measured after the sensors settled
+        for (offset, tilt) in state.offsets().iter().zip(tilts) {
+            assert!((offset.pitch - tilt.pitch).abs() < 1e-6);
+            assert!((offset.roll - tilt.roll).abs() < 1e-6);
+        }
+
+        // THEN the game goes back to the intro
+        assert_eq!(*state.mode(), Mode::Intro);
+        assert_eq!(
+            state.events(),
+            &[
+                GameEvent::Calibrated,
+                GameEvent::ModeChanged { mode: Mode::Intro }
+            ]
+        );
+
+        // THEN a game being calibrated is saved at the start of the intro
+        state.calibrate();
+        state.update(100, &0.0, &0.0);
+        let mut buffer = [0; save_size(4, 2)];
+        let size = state.serialize_into(&mut buffer).unwrap();
+        let restored = State::deserialize(&buffer[..size]).unwrap();
+        assert_eq!((*restored.mode(), restored.mode_ms()), (Mode::Intro, 0));
+        assert_eq!(State::new().offsets(), [Tilt::default(); 2]);
+    }
+
+    #[test]
+    fn calibration_skips_invalid_input_test() {
+        // GIVEN a two player game that is calibrated after the sensors settled
+        let mut state = State::new().with_players(2);
+        state.calibrate();
+        state.update_players(CALIBRATE_SETTLE_MS, &[Tilt::default(); 2]);
+
+        // WHEN the first controller cannot be read for some of the measurements
+        let tilts = [Tilt::new(0.05, -0.02), Tilt::new(-0.1, 0.04)];
+        let valid = [
+            ControlInput::new(tilts[0], 0),
+            ControlInput::new(tilts[1], 0),
+        ];
+        let invalid = [ControlInput::invalid(), ControlInput::new(tilts[1], 0)];
+        for step in 0..(CALIBRATE_MS - CALIBRATE_SETTLE_MS) / 100 {
+            let inputs = if step % 3 == 0 { invalid } else { valid };
+            state.update_inputs(100, &inputs);
+        }
+        assert_eq!(*state.mode(), Mode::Intro);
+
+        // THEN the offset of each player is the average of their valid measurements
+        for (offset, tilt) in state.offsets().iter().zip(tilts) {
+            assert!((offset.pitch - tilt.pitch).abs() < 1e-6);
+            assert!((offset.roll - tilt.roll).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn calibration_without_measurements_test() {
+        // GIVEN a two player game that is calibrated after the sensors settled
+        let mut state = State::new().with_players(2);
+        state.calibrate();
+        state.update_players(CALIBRATE_SETTLE_MS, &[Tilt::default(); 2]);
+
+        // WHEN the second controller cannot be read for the whole calibration
+        let inputs = [
+            ControlInput::new(Tilt::new(0.05, -0.02), 0),
+            ControlInput::invalid(),
+        ];
+        state.update_inputs(CALIBRATE_MS - CALIBRATE_SETTLE_MS, &inputs);
+
+        // THEN the game goes back to the intro without a calibration to keep
+        assert_eq!(*state.mode(), Mode::Intro);
+        assert_eq!(
+            state.events(),
+            &[GameEvent::ModeChanged { mode: Mode::Intro }]
+        );
+    }
+
+    #[test]
+    fn invalid_input_does_not_move_ball_test() {
+        // GIVEN a two player game whose first controller cannot be read
+        let mut state = State::new().with_players(2);
+        state.update(INTRO_MS, &0.0, &0.0);
+        let tilt = Tilt::new(0.0, 1.0);
+        let inputs = [ControlInput::invalid(), ControlInput::new(tilt, 0)];
+
+        // WHEN the game is updated with the inputs
+        state.update_inputs(PHYSICS_STEP_MS, &inputs);
+
+        // THEN only the ball of the second player moves
+        assert_eq!(state.players()[0].ball().velocity, Vector::new(0.0, 0.0));
+        assert_ne!(state.players()[1].ball().velocity, Vector::new(0.0, 0.0));
+    }
+
+    #[test]
+    fn transition_from_play_to_over_test() {
+        // GIVEN game state in play mode on the last level
+        let mut state = game_state_in_play_mode();
+        state.start_level(LEVELS.len() - 1);
+
+        // GIVEN all goals dead
+        for goal in state.goals.iter_mut() {
+            goal.alive = false;
+        }
+
+        // WHEN update is called
+        state.update(PHYSICS_STEP_MS, &0.0, &0.0);
+
+        // THEN game transitions to over mode and the low score is updated
+        assert_eq!(*state.mode(), Mode::Over);
+        assert_eq!(state.low_score(), PHYSICS_STEP_MS as i32);
+    }
+
+    #[test]
+    fn transition_from_over_to_play_test() {
+        // GIVEN game state in over mode with a score that is not a high score
+        let mut state = game_state_in_play_mode();
+        state.high_scores = full_high_scores();
+        state.start_level(LEVELS.len() - 1);
+        for goal in state.goals.iter_mut() {
+            goal.alive = false;
+        }
+        state.update(PHYSICS_STEP_MS, &0.0, &0.0);
+        assert_eq!(*state.mode(), Mode::Over);
+
+        // WHEN update is called before the game over screen is over
+        state.update(OVER_MS - 1, &0.0, &0.0);
+
+        // THEN game stays in over mode
+        assert_eq!(*state.mode(), Mode::Over);
+
+        // WHEN the game over screen is over
+        state.update(1, &0.0, &0.0);
+
+        // THEN game transitions to play mode on the first level
+        assert_eq!(*state.mode(), Mode::Play);
+        assert_eq!(state.level(), 0);
+        assert_eq!(state.goals_alive().len(), LEVELS[0].goals().len());
+    }
+
+    #[test]
+    fn transition_from_play_to_next_level_test() {
+        // GIVEN game state in play mode on the first level
+        let mut state = game_state_in_play_mode();
+        assert_eq!(state.level(), 0);
+        state.update(PHYSICS_STEP_MS, &0.0, &0.0);
+        assert_eq!(state.level_score(), PHYSICS_STEP_MS as i32);
+
+        // GIVEN all goals dead
+        for goal in state.goals.iter_mut() {
+            goal.alive = false;
+        }
+
+        // WHEN update is called
+        state.update(PHYSICS_STEP_MS, &0.0, &0.0);
+
+        // THEN game transitions to the next level with its goals and ball location
+        assert_eq!(*state.mode(), Mode::NextLevel);
+        assert_eq!(state.level(), 1);
+        assert_eq!(state.current_level().par(), LEVELS[1].par());
+        assert_eq!(state.ball().location(), LEVELS[1].ball());
+        let goals: Vec<Point, 4> = state.goals_alive().iter().map(|g| g.location()).collect();
+        assert_eq!(goals, LEVELS[1].goals());
+
+        // THEN the score carries over and the level score restarts
+        assert_eq!(state.score(), 2 * PHYSICS_STEP_MS as i32);
+        assert_eq!(state.level_score(), 0);
+
+        // WHEN the next level screen is over
+        state.update(NEXT_LEVEL_MS, &0.0, &0.0);
+
+        // THEN game transitions to play mode
+        assert_eq!(*state.mode(), Mode::Play);
+        assert_eq!(state.level(), 1);
+    }
+
+    #[test]
+    fn game_over_after_last_level_test() {
+        // GIVEN game state in play mode
+        let mut state = game_state_in_play_mode();
+
+        // WHEN the goals of every level are visited
+        for level in 0..LEVELS.len() {
+            assert_eq!(state.level(), level);
+            for goal in state.goals.iter_mut() {
+                goal.alive = false;
+            }
+            state.update(PHYSICS_STEP_MS, &0.0, &0.0);
+            if *state.mode() == Mode::NextLevel {
+                state.update(NEXT_LEVEL_MS, &0.0, &0.0);
+            }
+        }
+
+        // THEN the game is over with the total score of all levels as the low score
+        assert_eq!(*state.mode(), Mode::Over);
+        assert_eq!(
+            state.score(),
+            (LEVELS.len() as u32 * PHYSICS_STEP_MS) as i32
+        );
+        assert_eq!(state.low_score(), state.score());
+    }
+
+    #[test]
+    fn kept_low_score_test() {
+        // GIVEN a game with the low score of an earlier game, on the last level with all
+        // goals dead
+        let mut state = State::new().with_low_score(PHYSICS_STEP_MS as i32);
+        state.update(INTRO_MS, &0.0, &0.0);
+        state.start_level(LEVELS.len() - 1);
+        state.players[0].score = 1;
+        for goal in state.goals.iter_mut() {
+            goal.alive = false;
+        }
+
+        // WHEN the game is completed with a higher score
+        state.update(PHYSICS_STEP_MS, &0.0, &0.0);
+
+        // THEN the low score of the earlier game is kept
+        assert_eq!(*state.mode(), Mode::Over);
+        assert_eq!(state.score(), PHYSICS_STEP_MS as i32 + 1);
+        assert_eq!(state.low_score(), PHYSICS_STEP_MS as i32);
+    }
+
+    #[test]
+    fn split_times_test() {
+        // GIVEN a game in play mode
+        let mut state = game_state_in_play_mode();
+        state.high_scores = full_high_scores();
+        let step = PHYSICS_STEP_MS as i32;
+        // the splits of a game that reaches every goal the given time after the one before
+        let splits = |time: i32| -> Vec<i32, MAX_SPLITS> {
+            (1..=total_goals(&LEVELS) as i32)
+                .map(|goal| goal * time)
+                .collect()
+        };
+
+        // WHEN every goal is reached two steps after the one before
+        visit_every_goal(&mut state, PHYSICS_STEP_MS);
+
+        // THEN the time each goal was reached is kept as a split without deltas
+        assert_eq!(*state.mode(), Mode::Over);
+        assert_eq!(state.run().splits(), &splits(2 * step));
+        assert_eq!(state.run().delta(0), None);
+        let last = state.goals.last().unwrap();
+        assert_eq!(last.time(), splits(2 * step).last().copied());
+        assert_eq!(Some(state.players()[0].time()), last.time());
+
+        // WHEN the next game reaches every goal one step after the one before
+        state.update(OVER_MS, &0.0, &0.0);
+        assert_eq!(*state.mode(), Mode::Play);
+        assert!(state.run().splits().is_empty());
+        assert!(state.goals.iter().all(|goal| goal.time().is_none()));
+        visit_every_goal(&mut state, 0);
+
+        // THEN the splits are compared with the splits of the low score
+        assert_eq!(state.run().best(), &splits(2 * step));
+        assert_eq!(state.run().delta(0), Some(-step));
+        assert_eq!(state.run().delta(3), Some(-4 * step));
+
+        // THEN a game that does not beat the low score leaves the splits compared with
+        state.update(OVER_MS, &0.0, &0.0);
+        visit_every_goal(&mut state, 2 * PHYSICS_STEP_MS);
+        state.update(OVER_MS, &0.0, &0.0);
+        assert_eq!(state.run().best(), &splits(step));
+
+        // WHEN a goal is reached with time left over that is shorter than a physics step
+        assert_eq!(*state.mode(), Mode::Play);
+        state.players[0].ball.position = Vector::from(state.goals[0].location);
+        state.update(PHYSICS_STEP_MS + 7, &0.0, &0.0);
+
+        // THEN the player, the goal and the split have the time the goal was reached, without
+        // the time left over
+        let time = state.score() - 7;
+        assert_eq!(state.players()[0].time(), time);
+        assert_eq!(state.goals[0].time(), Some(time));
+        assert_eq!(state.run().splits(), &[time]);
+    }
+
+    #[test]
+    fn ball_moves_right_test() {
+        // GIVEN game state in play mode
+        let mut state = game_state_in_play_mode();
+
+        // WHEN the roll is positive and above threshold
+        state.update(PHYSICS_STEP_MS, &0.0, &(ANGLE_THRESHOLD + 0.1));
+
+        // THEN the ball moves to the right
+        let delta = ball_position_delta(&state);
+        assert!(delta.x > 0.0);
+        assert_eq!(delta.y, 0.0);
+    }
+
+    #[test]
+    fn ball_moves_left_test() {
+        // GIVEN game state in play mode
+        let mut state = game_state_in_play_mode();
+
+        // WHEN the roll is negative and above threshold
+        state.update(PHYSICS_STEP_MS, &0.0, &-(ANGLE_THRESHOLD + 0.1));
+
+        // THEN the ball moves to the left
+        let delta = ball_position_delta(&state);
+        assert!(delta.x < 0.0);
+        assert_eq!(delta.y, 0.0);
+    }
+
+    #[test]
     fn ball_moves_up_test() {
         // GIVEN game state in play mode
         let mut state = game_state_in_play_mode();
 
-        // WHEN the pitch is positive and above threshold
-        state.update(&(ANGLE_THRESHOLD + 0.1), &0.0);
+        // WHEN the pitch is positive and above threshold
+        state.update(PHYSICS_STEP_MS, &(ANGLE_THRESHOLD + 0.1), &0.0);
+
+        // THEN the ball moves to up
+        let delta = ball_position_delta(&state);
+        assert_eq!(delta.x, 0.0);
+        assert!(delta.y < 0.0);
+    }
+
+    #[test]
+    fn ball_moves_down_test() {
+        // GIVEN game state in play mode
+        let mut state = game_state_in_play_mode();
+
+        // WHEN the pitch is negative and above threshold
+        state.update(PHYSICS_STEP_MS, &-(ANGLE_THRESHOLD + 0.1), &0.0);
+
+        // THEN the ball moves to down
+        let delta = ball_position_delta(&state);
+        assert_eq!(delta.x, 0.0);
+        assert!(delta.y > 0.0);
+    }
+
+    #[test]
+    fn ball_moves_diagonally_test() {
+        // GIVEN game state in play mode
+        let mut state = game_state_in_play_mode();
+
+        // WHEN the pitch and roll are both positive and above threshold
+        state.update(
+            PHYSICS_STEP_MS,
+            &(ANGLE_THRESHOLD + 0.1),
+            &(ANGLE_THRESHOLD + 0.1),
+        );
+
+        // THEN the ball moves to diagonally
+        let delta = ball_position_delta(&state);
+        assert!(delta.x > 0.0);
+        assert!(delta.y < 0.0);
+    }
+
+    #[test]
+    fn inverted_input_shaping_test() {
+        // GIVEN a game in play mode with both axes inverted
+        let shaping = InputPreset::Classic.shaping().with_inversion(true, true);
+        let config = GameConfig::builder()
+            .with_input_shaping(shaping)
+            .build()
+            .unwrap();
+        let mut state = State::new_with_config(config);
+        state.update(INTRO_MS, &0.0, &0.0);
+
+        // WHEN the pitch and roll are both positive and above threshold
+        state.update(
+            PHYSICS_STEP_MS,
+            &(ANGLE_THRESHOLD + 0.1),
+            &(ANGLE_THRESHOLD + 0.1),
+        );
+
+        // THEN the ball moves down and to the left
+        let delta = ball_position_delta(&state);
+        assert!(delta.x < 0.0);
+        assert!(delta.y > 0.0);
+    }
+
+    #[test]
+    fn ball_stays_put_test() {
+        // GIVEN game state in play mode
+        let mut state = game_state_in_play_mode();
+
+        // WHEN the pitch and roll are both positive and below threshold
+        state.update(
+            PHYSICS_STEP_MS,
+            &(ANGLE_THRESHOLD - 0.05),
+            &(ANGLE_THRESHOLD - 0.05),
+        );
+
+        // THEN the ball stays put
+        assert_eq!(
+            *state.ball(),
+            State::initial_ball(&LEVELS[0], &GameConfig::default())
+        );
+    }
+
+    #[test]
+    fn ball_accelerates_test() {
+        // GIVEN game state in play mode
+        let mut state = game_state_in_play_mode();
+
+        // WHEN the roll is held above threshold
+        state.update(PHYSICS_STEP_MS, &0.0, &0.5);
+        let speed1 = state.players[0].ball.velocity.x;
+        state.update(PHYSICS_STEP_MS, &0.0, &0.5);
+        let speed2 = state.players[0].ball.velocity.x;
+
+        // THEN the ball speeds up
+        assert!(speed1 > 0.0);
+        assert!(speed2 > speed1);
+
+        // WHEN a steeper roll is held
+        let mut steeper = game_state_in_play_mode();
+        steeper.update(PHYSICS_STEP_MS, &0.0, &1.0);
+
+        // THEN the ball accelerates faster
+        assert!(steeper.players[0].ball.velocity.x > speed1);
+    }
+
+    #[test]
+    fn ball_speed_is_limited_test() {
+        // GIVEN game state in play mode
+        let mut state = game_state_in_play_mode();
+        state.players[0].ball.position = Vector::new(X_MIN as f32, Y_MIN as f32);
+
+        // WHEN a steep roll is held for a long time
+        for _ in 0..20 {
+            state.update(PHYSICS_STEP_MS, &0.0, &1.5);
+        }
+
+        // THEN the ball does not exceed the maximum speed
+        assert_eq!(state.players[0].ball.velocity.x, BALL_MAX_SPEED);
+    }
+
+    #[test]
+    fn ball_coasts_test() {
+        // GIVEN a ball that has been accelerated to the right
+        let mut state = game_state_in_play_mode();
+        for _ in 0..3 {
+            state.update(PHYSICS_STEP_MS, &0.0, &1.0);
+        }
+        let speed = state.players[0].ball.velocity.x;
+        let x = state.players[0].ball.position.x;
+
+        // WHEN the sensor is level
+        state.update(PHYSICS_STEP_MS, &0.0, &0.0);
+
+        // THEN the ball keeps moving to the right but slows down due to friction
+        assert!(state.players[0].ball.position.x > x);
+        assert!(state.players[0].ball.velocity.x > 0.0);
+        assert!(state.players[0].ball.velocity.x < speed);
+    }
+
+    #[test]
+    fn ball_stops_test() {
+        // GIVEN a ball that has been accelerated to the left
+        let mut state = game_state_in_play_mode();
+        for _ in 0..3 {
+            state.update(PHYSICS_STEP_MS, &0.0, &-1.0);
+        }
+
+        // WHEN the sensor is level for a while
+        for _ in 0..300 {
+            state.update(PHYSICS_STEP_MS, &0.0, &0.0);
+        }
+
+        // THEN friction brings the ball to rest
+        assert_eq!(state.players[0].ball.velocity, Vector::default());
+        let location = state.ball().location();
+        state.update(PHYSICS_STEP_MS, &0.0, &0.0);
+        assert_eq!(state.ball().location(), location);
+        assert!(
+            location.x
+                < State::initial_ball(&LEVELS[0], &GameConfig::default())
+                    .location()
+                    .x
+        );
+    }
+
+    #[test]
+    fn ball_location_rounds_position_test() {
+        // GIVEN a ball at a sub-pixel position
+        let mut state = game_state_in_play_mode();
+        state.players[0].ball.position = Vector::new(10.4, 20.6);
+
+        // THEN the location is the nearest pixel
+        assert_eq!(state.ball().location(), Point::new(10, 21));
+    }
+
+    #[test]
+    fn ball_bounces_off_walls_test() {
+        // GIVEN a ball moving quickly towards the bottom right corner
+        let mut state = game_state_in_play_mode();
+        state.players[0].ball.position = Vector::new(X_MAX as f32 - 1.0, Y_MAX as f32 - 1.0);
+        state.players[0].ball.velocity = Vector::new(BALL_MAX_SPEED, BALL_MAX_SPEED);
+
+        // WHEN update is called
+        state.update(PHYSICS_STEP_MS, &0.0, &0.0);
+
+        // THEN the ball is clamped to the corner and bounces back with reduced speed
+        assert_eq!(state.ball().location(), Point::new(X_MAX, Y_MAX));
+        let speed = BALL_MAX_SPEED * (1.0 - BALL_FRICTION) * WALL_RESTITUTION;
+        assert_eq!(state.players[0].ball.velocity, Vector::new(-speed, -speed));
+        assert_eq!(wall_hits(&state), &[Wall::Right, Wall::Bottom]);
+
+        // GIVEN a ball moving quickly towards the top left corner
+        state.players[0].ball.position = Vector::new(X_MIN as f32 + 1.0, Y_MIN as f32 + 1.0);
+        state.players[0].ball.velocity = Vector::new(-BALL_MAX_SPEED, -BALL_MAX_SPEED);
+
+        // WHEN update is called
+        state.update(PHYSICS_STEP_MS, &0.0, &0.0);
+
+        // THEN the ball is clamped to the corner and bounces back with reduced speed
+        assert_eq!(state.ball().location(), Point::new(X_MIN, Y_MIN));
+        assert_eq!(state.players[0].ball.velocity, Vector::new(speed, speed));
+        assert_eq!(wall_hits(&state), &[Wall::Left, Wall::Top]);
+
+        // WHEN the ball moves away from the walls
+        state.update(PHYSICS_STEP_MS, &0.0, &0.0);
+
+        // THEN no wall is hit
+        assert!(wall_hits(&state).is_empty());
+        assert!(state.players[0].ball.position.x > X_MIN as f32);
+        assert!(state.players[0].ball.position.y > Y_MIN as f32);
+    }
+
+    #[test]
+    fn ball_stops_exactly_on_boundary_test() {
+        // GIVEN a ball moving right with a speed that does not evenly divide the distance
+        // to the wall
+        let mut state = game_state_in_play_mode();
+        state.players[0].ball.position = Vector::new(X_MAX as f32 - 3.0, Y_MIN as f32);
+        state.players[0].ball.velocity = Vector::new(2.0, 0.0);
+
+        // WHEN the ball is rolled into the wall until it settles
+        for _ in 0..50 {
+            state.update(PHYSICS_STEP_MS, &0.0, &1.0);
+        }
+
+        // THEN the ball rests exactly on the boundary and no longer hits the wall
+        assert_eq!(state.players[0].ball.position.x, X_MAX as f32);
+        assert_eq!(state.ball().location(), Point::new(X_MAX, Y_MIN));
+        assert!(wall_hits(&state).is_empty());
+    }
+
+    #[test]
+    fn slow_ball_does_not_bounce_test() {
+        // GIVEN a ball creeping towards the left wall
+        let mut state = game_state_in_play_mode();
+        state.players[0].ball.position = Vector::new(X_MIN as f32 + 0.01, Y_MAX as f32);
+        state.players[0].ball.velocity = Vector::new(-0.03, 0.0);
+
+        // WHEN update is called
+        state.update(PHYSICS_STEP_MS, &0.0, &0.0);
+
+        // THEN the ball comes to rest on the wall without a wall hit
+        assert_eq!(state.players[0].ball.position.x, X_MIN as f32);
+        assert_eq!(state.players[0].ball.velocity, Vector::default());
+        assert!(wall_hits(&state).is_empty());
+    }
+
+    #[test]
+    fn ball_bounces_off_obstacle_test() {
+        // GIVEN a ball moving right towards the left side of an obstacle
+        let mut state = game_state_in_play_mode();
+        state.start_level(1);
+        let obstacle = state.obstacles()[0];
+        let boundary = obstacle.location().x - BALL_SIZE as i32;
+        state.players[0].ball.position =
+            Vector::new(boundary as f32 - 1.0, obstacle.location().y as f32);
+        state.players[0].ball.velocity = Vector::new(BALL_MAX_SPEED, 0.0);
+
+        // WHEN update is called
+        state.update(PHYSICS_STEP_MS, &0.0, &0.0);
+
+        // THEN the ball is stopped by the obstacle and bounces back with reduced speed
+        assert_eq!(state.players[0].ball.position.x, boundary as f32);
+        let speed = BALL_MAX_SPEED * (1.0 - BALL_FRICTION) * WALL_RESTITUTION;
+        assert_eq!(state.players[0].ball.velocity, Vector::new(-speed, 0.0));
+        assert_eq!(wall_hits(&state), &[Wall::Right]);
+    }
+
+    #[test]
+    fn fast_ball_does_not_pass_through_obstacle_test() {
+        // GIVEN a ball fast enough to cross a thin obstacle within a single step
+        let config = GameConfig::builder()
+            .with_ball_max_speed(40.0)
+            .build()
+            .unwrap();
+        let mut state = State::new_with_config(config);
+        state.update(INTRO_MS, &0.0, &0.0);
+        state.start_level(1);
+        let obstacle = state.obstacles()[0];
+        let boundary = obstacle.location().x - BALL_SIZE as i32;
+        state.players[0].ball.position =
+            Vector::new(boundary as f32 - 10.0, obstacle.location().y as f32);
+        state.players[0].ball.velocity = Vector::new(40.0, 0.0);
+
+        // WHEN update is called
+        state.update(PHYSICS_STEP_MS, &0.0, &0.0);
+
+        // THEN the ball is stopped by the obstacle and bounces back
+        assert_eq!(state.players[0].ball.position.x, boundary as f32);
+        assert!(state.players[0].ball.velocity.x < 0.0);
+        assert_eq!(wall_hits(&state), &[Wall::Right]);
+    }
+
+    #[test]
+    fn ball_slides_along_obstacle_test() {
+        // GIVEN a ball moving down and left into the right side of an obstacle
+        let mut state = game_state_in_play_mode();
+        state.start_level(1);
+        let obstacle = state.obstacles()[0];
+        let boundary = obstacle.location().x + obstacle.size().width as i32;
+        state.players[0].ball.position = Vector::new(boundary as f32, obstacle.location().y as f32);
+        state.players[0].ball.velocity = Vector::new(-1.0, 1.0);
+
+        // WHEN the ball is rolled down and left for a while
+        for _ in 0..10 {
+            state.update(PHYSICS_STEP_MS, &-1.0, &-1.0);
+
+            // THEN the ball stays against the obstacle
+            assert_eq!(state.players[0].ball.position.x, boundary as f32);
+        }
+
+        // THEN the ball slides down along the obstacle without bouncing
+        assert!(state.players[0].ball.position.y > obstacle.location().y as f32 + 5.0);
+        assert!(state.players[0].ball.velocity.y > 0.0);
+        assert!(wall_hits(&state).is_empty());
+    }
+
+    #[test]
+    fn ball_bounces_off_obstacle_top_and_bottom_test() {
+        // GIVEN a ball moving up into the bottom side of an obstacle
+        let mut state = game_state_in_play_mode();
+        state.start_level(2);
+        let obstacle = state.obstacles()[0];
+        let bottom = obstacle.location().y + obstacle.size().height as i32;
+        state.players[0].ball.position =
+            Vector::new(obstacle.location().x as f32, bottom as f32 + 1.0);
+        state.players[0].ball.velocity = Vector::new(0.0, -BALL_MAX_SPEED);
+
+        // WHEN update is called
+        state.update(PHYSICS_STEP_MS, &0.0, &0.0);
+
+        // THEN the ball bounces off the bottom of the obstacle
+        assert_eq!(state.players[0].ball.position.y, bottom as f32);
+        assert!(state.players[0].ball.velocity.y > 0.0);
+        assert_eq!(wall_hits(&state), &[Wall::Top]);
+
+        // GIVEN a ball moving down into the top side of an obstacle
+        let top = obstacle.location().y - BALL_SIZE as i32;
+        state.players[0].ball.position =
+            Vector::new(obstacle.location().x as f32, top as f32 - 1.0);
+        state.players[0].ball.velocity = Vector::new(0.0, BALL_MAX_SPEED);
+
+        // WHEN update is called
+        state.update(PHYSICS_STEP_MS, &0.0, &0.0);
+
+        // THEN the ball bounces off the top of the obstacle
+        assert_eq!(state.players[0].ball.position.y, top as f32);
+        assert!(state.players[0].ball.velocity.y < 0.0);
+        assert_eq!(wall_hits(&state), &[Wall::Bottom]);
+    }
+
+    #[test]
+    fn ball_falls_into_reset_hole_test() {
+        // GIVEN a ball rolling towards a hole that resets the ball
+        let mut state = game_state_in_play_mode();
+        state.start_level(1);
+        let hazard = state.hazards()[0];
+        assert_eq!(hazard.effect(), HazardEffect::Reset);
+        state.players[0].ball.position = Vector::from(hazard.location()) + Vector::new(-8.0, 0.0);
+        state.players[0].ball.velocity = Vector::new(1.0, 0.0);
+
+        // WHEN the edge of the ball covers the hole
+        state.update(PHYSICS_STEP_MS, &0.0, &0.0);
+
+        // THEN the ball does not fall in
+        assert!(state.ball().location().x > hazard.location().x - BALL_SIZE as i32);
+        assert!(fallen(&state).is_none());
+
+        // WHEN the centre of the ball reaches the hole
+        let mut updates = 0;
+        while fallen(&state).is_none() && updates < 10 {
+            state.update(PHYSICS_STEP_MS, &0.0, &0.0);
+            updates += 1;
+        }
+
+        // THEN the ball falls in and is sent back to its initial location
+        assert_eq!(fallen(&state).unwrap().location(), hazard.location());
+        assert_eq!(
+            state.ball(),
+            &State::initial_ball(&LEVELS[1], &GameConfig::default())
+        );
+
+        // WHEN update is called
+        state.update(PHYSICS_STEP_MS, &0.0, &0.0);
+
+        // THEN the fall is over
+        assert!(fallen(&state).is_none());
+    }
+
+    #[test]
+    fn ball_falls_into_penalty_hole_test() {
+        // GIVEN a ball resting on a hole that costs points
+        let mut state = game_state_in_play_mode();
+        state.start_level(2);
+        let hazard = state.hazards()[0];
+        assert_eq!(hazard.effect(), HazardEffect::Penalty(HAZARD_PENALTY));
+        state.players[0].ball.position = Vector::from(hazard.location());
+        let score = state.score();
+        let step = PHYSICS_STEP_MS as i32;
+
+        // WHEN update is called
+        state.update(PHYSICS_STEP_MS, &0.0, &0.0);
+
+        // THEN the ball falls in and the penalty is added to the score
+        assert_eq!(fallen(&state).unwrap().location(), hazard.location());
+        assert_eq!(state.score(), score + step + HAZARD_PENALTY);
+        assert_eq!(state.ball().location(), hazard.location());
+
+        // WHEN the ball stays in the hole
+        state.update(PHYSICS_STEP_MS, &0.0, &0.0);
+
+        // THEN the penalty is not added again
+        assert!(fallen(&state).is_none());
+        assert_eq!(state.score(), score + 2 * step + HAZARD_PENALTY);
+
+        // WHEN the ball leaves and falls into the hole again
+        state.players[0].ball.position = Vector::new(X_MIN as f32, Y_MAX as f32);
+        state.update(PHYSICS_STEP_MS, &0.0, &0.0);
+        state.players[0].ball.position = Vector::from(hazard.location());
+        state.update(PHYSICS_STEP_MS, &0.0, &0.0);
+
+        // THEN the penalty is added again
+        assert!(fallen(&state).is_some());
+        assert_eq!(state.score(), score + 4 * step + 2 * HAZARD_PENALTY);
+    }
+
+    #[test]
+    fn ball_visits_goal_test() {
+        // GIVEN game state in play mode with first goal alive
+        let mut state = game_state_in_play_mode();
+        assert!(state.goals[0].alive);
+        assert_eq!(state.goals_alive().len(), LEVELS[0].goals().len());
+
+        // WHEN the ball moves to visit the goal
+        state.players[0].ball.position = Vector::from(LEVELS[0].goals()[0]);
+        state.update(PHYSICS_STEP_MS, &0.0, &0.0);
+
+        // THEN the goal is dead
+        assert!(!state.goals[0].alive);
+        assert_eq!(state.goals_alive().len(), LEVELS[0].goals().len() - 1);
+    }
+
+    #[test]
+    fn fast_ball_visits_goal_on_its_way_test() {
+        // GIVEN a ball fast enough to pass over a goal within a single step
+        const GOALS: [Point; 1] = [Point::new(60, 30)];
+        const LEVEL: [Level; 1] = [Level::new(&GOALS, Point::new(30, 30), None)];
+        let config = GameConfig::builder()
+            .with_levels(&LEVEL)
+            .with_ball_max_speed(40.0)
+            .build()
+            .unwrap();
+        let mut state = State::new_with_config(config);
+        state.update(INTRO_MS, &0.0, &0.0);
+        state.players[0].ball.velocity = Vector::new(40.0, 0.0);
+
+        // WHEN update is called
+        state.update(PHYSICS_STEP_MS, &0.0, &0.0);
+
+        // THEN the ball ends up past the goal but has visited it
+        assert!(!intersects(
+            state.ball().location(),
+            BALL_SIZE,
+            GOALS[0],
+            GOAL_SIZE
+        ));
+        assert!(state.events().contains(&GameEvent::GoalReached {
+            index: 0,
+            player: 0
+        }));
+        assert_eq!(*state.mode(), Mode::Over);
+    }
+
+    #[test]
+    fn ball_corner_does_not_visit_goal_test() {
+        // GIVEN a ball diagonally next to a goal, its bounding square overlapping the goal
+        let mut state = game_state_in_play_mode();
+        let goal = LEVELS[0].goals()[0];
+        let offset = (BALL_SIZE + GOAL_SIZE) as f32 / 2.0 - 1.0;
+        state.players[0].ball.position = Vector::from(goal) + Vector::new(offset, offset);
+
+        // WHEN update is called
+        state.update(PHYSICS_STEP_MS, &0.0, &0.0);
+
+        // THEN the round ball has not touched the goal
+        assert!(intersects(
+            state.ball().location(),
+            BALL_SIZE,
+            goal,
+            GOAL_SIZE
+        ));
+        assert!(state.goals[0].alive);
+
+        // WHEN the ball moves closer
+        state.players[0].ball.position = Vector::from(goal) + Vector::new(offset, 2.0);
+        state.update(PHYSICS_STEP_MS, &0.0, &0.0);
+
+        // THEN the goal is visited
+        assert!(!state.goals[0].alive);
+    }
+
+    #[test]
+    fn update_raises_events_test() {
+        // GIVEN game state in intro mode
+        let mut state = State::new();
+
+        // WHEN the intro is over
+        state.update(INTRO_MS, &0.0, &0.0);
+
+        // THEN the mode change is raised
+        assert_eq!(
+            state.events(),
+            &[GameEvent::ModeChanged { mode: Mode::Play }]
+        );
+
+        // WHEN the ball visits the second goal
+        state.players[0].ball.position = Vector::from(LEVELS[0].goals()[1]);
+        state.update(PHYSICS_STEP_MS, &0.0, &0.0);
+
+        // THEN the goal is raised as reached by the first player
+        assert_eq!(
+            state.events(),
+            &[GameEvent::GoalReached {
+                index: 1,
+                player: 0
+            }]
+        );
+
+        // WHEN nothing happens
+        state.update(PHYSICS_STEP_MS, &0.0, &0.0);
+
+        // THEN no event is raised
+        assert!(state.events().is_empty());
+    }
+
+    #[test]
+    fn game_over_event_test() {
+        // GIVEN game state in play mode on the last level with all goals dead
+        let mut state = game_state_in_play_mode();
+        state.high_scores = full_high_scores();
+        state.start_level(LEVELS.len() - 1);
+        for goal in state.goals.iter_mut() {
+            goal.alive = false;
+        }
+
+        // WHEN update is called
+        state.update(PHYSICS_STEP_MS, &0.0, &0.0);
+
+        // THEN the game over is raised with a new record
+        let score = PHYSICS_STEP_MS as i32;
+        assert_eq!(
+            state.events(),
+            &[
+                GameEvent::ModeChanged { mode: Mode::Over },
+                GameEvent::GameOver {
+                    score,
+                    new_record: true
+                }
+            ]
+        );
+
+        // WHEN the next game is completed with a higher score
+        state.update(OVER_MS, &0.0, &0.0);
+        state.start_level(LEVELS.len() - 1);
+        state.players[0].score = score + 1;
+        for goal in state.goals.iter_mut() {
+            goal.alive = false;
+        }
+        state.update(0, &0.0, &0.0);
 
-        // THEN the ball moves to up
-        assert_eq!(state.ball().location(), ball_location_delta(0, -BALL_DELTA));
+        // THEN the game over is raised without a new record
+        assert_eq!(
+            state.events()[1],
+            GameEvent::GameOver {
+                score: score + 1,
+                new_record: false
+            }
+        );
+        assert_eq!(state.low_score(), score);
     }
 
     #[test]
-    fn ball_moves_down_test() {
-        // GIVEN game state in play mode
-        let mut state = game_state_in_play_mode();
+    fn seeded_goals_are_reproducible_test() {
+        // GIVEN two games with the same seed
+        let state1 = State::new_with_seed(1234);
+        let state2 = State::new_with_seed(1234);
 
-        // WHEN the pitch is negative and above threshold
-        state.update(&-(ANGLE_THRESHOLD + 0.1), &0.0);
+        // THEN the goal layouts are the same
+        assert_eq!(goal_locations(&state1), goal_locations(&state2));
 
-        // THEN the ball moves to down
-        assert_eq!(state.ball().location(), ball_location_delta(0, BALL_DELTA));
+        // GIVEN a game with a different seed
+        let state3 = State::new_with_seed(4321);
+
+        // THEN the goal layout is different
+        assert_ne!(goal_locations(&state1), goal_locations(&state3));
+        assert_ne!(goal_locations(&state1).as_slice(), LEVELS[0].goals());
     }
 
     #[test]
-    fn ball_moves_diagonally_test() {
-        // GIVEN game state in play mode
+    fn seeded_goals_are_valid_test() {
+        for seed in 0..200 {
+            // GIVEN a game with a seed
+            let mut state = State::new_with_seed(seed);
+
+            for (index, level) in LEVELS.iter().enumerate() {
+                // WHEN the level starts
+                state.start_level(index);
+
+                // THEN the level has the defined number of goals
+                let goals = goal_locations(&state);
+                assert_eq!(goals.len(), level.goals().len());
+
+                for (i, goal) in goals.iter().enumerate() {
+                    // THEN the goals are inside the game space
+                    assert!(goal.x >= X_MIN && goal.x <= X_MAX);
+                    assert!(goal.y >= Y_MIN && goal.y <= Y_MAX);
+
+                    // THEN the goals are not on top of the ball
+                    assert!(!intersects(*goal, GOAL_SIZE, level.ball(), BALL_SIZE));
+
+                    // THEN the goals are not on top of the obstacles
+                    for obstacle in level.obstacles() {
+                        assert!(!intersects_rectangle(
+                            *goal,
+                            Size::new_equal(GOAL_SIZE),
+                            obstacle.location(),
+                            obstacle.size()
+                        ));
+                    }
+
+                    // THEN the goals are not on top of the holes
+                    for hazard in level.hazards() {
+                        assert!(!intersects(
+                            *goal,
+                            GOAL_SIZE,
+                            hazard.location(),
+                            hazard.size()
+                        ));
+                    }
+
+                    // THEN the goals do not overlap each other
+                    for other in goals.iter().skip(i + 1) {
+                        assert!(!intersects(*goal, GOAL_SIZE, *other, GOAL_SIZE));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn seeded_goals_change_every_game_test() {
+        // GIVEN a seeded game in over mode
+        let mut state = State::new_with_seed(99);
+        state.update(INTRO_MS, &0.0, &0.0);
+        state.high_scores = full_high_scores();
+        let first_game = goal_locations(&state);
+        state.start_level(LEVELS.len() - 1);
+        for goal in state.goals.iter_mut() {
+            goal.alive = false;
+        }
+        state.update(PHYSICS_STEP_MS, &0.0, &0.0);
+        assert_eq!(*state.mode(), Mode::Over);
+
+        // WHEN the next game starts
+        state.update(OVER_MS, &0.0, &0.0);
+
+        // THEN the goals are placed differently
+        assert_eq!(*state.mode(), Mode::Play);
+        assert_ne!(goal_locations(&state), first_game);
+    }
+
+    #[test]
+    fn goal_placement_gives_up_when_full_test() {
+        // GIVEN a game space that is entirely covered by goals
+        let mut goals = std::vec::Vec::new();
+        for x in (X_MIN..=X_MAX).step_by(GOAL_SIZE as usize) {
+            for y in (Y_MIN..=Y_MAX).step_by(GOAL_SIZE as usize) {
+                goals.push(Point::new(x, y));
+            }
+        }
+
+        // WHEN a random goal location is requested
+        let mut rng = Rng::new(1);
+        let location =
+            State::random_goal_location(&LEVELS[0], &GameConfig::default(), &goals, &mut rng);
+
+        // THEN no location is found
+        assert_eq!(location, None);
+
+        // WHEN the game space is empty
+        let location =
+            State::random_goal_location(&LEVELS[0], &GameConfig::default(), &[], &mut rng);
+
+        // THEN a location is found
+        assert!(location.is_some());
+    }
+
+    #[test]
+    fn goal_placement_falls_back_when_random_fails_test() {
+        // GIVEN a level area with just enough room for the ball and the goals where the level
+        // defines them, where most random locations of the first goal leave no room for the
+        // second
+        const GOALS: [Point; 2] = [Point::new(9, 10), Point::new(18, 10)];
+        const LEVEL: [Level; 1] = [Level::new(&GOALS, Point::new(0, 10), None)];
+        let area = Rectangle::new(Point::new(0, 10), Size::new(27, GOAL_SIZE));
+        let config = GameConfig::builder()
+            .with_levels(&LEVEL)
+            .with_level_area(area)
+            .with_play_area(area)
+            .build()
+            .unwrap();
+
+        for seed in 0..100 {
+            // WHEN the goals are placed from a seed
+            let state = State::new_with_config(config).with_seed(seed);
+
+            // THEN every goal is placed, off the ball and off the other goal
+            let goals = goal_locations(&state);
+            assert_eq!(goals.len(), GOALS.len());
+            for goal in goals.iter() {
+                assert!(!intersects(*goal, GOAL_SIZE, LEVEL[0].ball(), BALL_SIZE));
+            }
+            assert!(!intersects(goals[0], GOAL_SIZE, goals[1], GOAL_SIZE));
+        }
+
+        // WHEN the location the level defines overlaps a goal placed before it
+        let location =
+            State::fallback_goal_location(&LEVEL[0], &config, &[Point::new(10, 10)], GOALS[1]);
+
+        // THEN the first free location is used instead
+        assert_eq!(location, Some(Point::new(19, 10)));
+
+        // WHEN there is no free location left
+        let location =
+            State::fallback_goal_location(&LEVEL[0], &config, &[Point::new(12, 10)], GOALS[1]);
+
+        // THEN no location is found
+        assert_eq!(location, None);
+
+        // WHEN the location the level defines is free
+        let location = State::fallback_goal_location(&LEVEL[0], &config, &[], GOALS[1]);
+
+        // THEN it is used
+        assert_eq!(location, Some(GOALS[1]));
+    }
+
+    #[test]
+    fn free_variant_has_no_target_test() {
+        // GIVEN game state in play mode of the free variant
+        let state = game_state_in_play_mode();
+        assert_eq!(state.variant(), GameVariant::Free);
+
+        // THEN there is no target goal
+        assert!(state.target_goal().is_none());
+    }
+
+    #[test]
+    fn sequence_variant_visits_goals_in_order_test() {
+        // GIVEN game state in play mode of the sequence variant
+        let mut state = State::new().with_variant(GameVariant::Sequence);
+        state.update(INTRO_MS, &0.0, &0.0);
+        assert_eq!(state.variant(), GameVariant::Sequence);
+        assert_eq!(state.target_goal().unwrap().number(), 1);
+
+        // WHEN the ball visits a goal out of order
+        state.players[0].ball.position = Vector::from(LEVELS[0].goals()[1]);
+        state.update(PHYSICS_STEP_MS, &0.0, &0.0);
+
+        // THEN nothing happens
+        assert!(state.goals[1].alive);
+        assert_eq!(state.goals_alive().len(), LEVELS[0].goals().len());
+        assert_eq!(state.target_goal().unwrap().number(), 1);
+
+        // WHEN the ball visits the goals in order
+        let goals = LEVELS[0].goals().len();
+        for (index, location) in LEVELS[0].goals().iter().enumerate() {
+            assert_eq!(state.target_goal().unwrap().number(), index + 1);
+            state.players[0].ball.position = Vector::from(*location);
+            state.update(PHYSICS_STEP_MS, &0.0, &0.0);
+
+            // THEN each goal is reached in turn
+            let reached = GameEvent::GoalReached { index, player: 0 };
+            assert!(state.events().contains(&reached));
+            if index + 1 < goals {
+                assert!(!state.goals[index].alive);
+                assert_eq!(state.goals_alive().len(), goals - index - 1);
+            }
+        }
+
+        // THEN the level is complete as soon as the last goal is reached
+        assert_eq!(*state.mode(), Mode::NextLevel);
+        assert_eq!(state.level(), 1);
+    }
+
+    #[test]
+    fn players_move_independently_test() {
+        // GIVEN game state in play mode with two players
+        let mut state = State::new().with_players(2);
+        state.update(INTRO_MS, &0.0, &0.0);
+        assert_eq!(state.players().len(), 2);
+        for player in state.players() {
+            assert_eq!(
+                *player.ball(),
+                State::initial_ball(&LEVELS[0], &GameConfig::default())
+            );
+        }
+
+        // WHEN only the mpu of the second player is rolled up
+        let tilts = [Tilt::new(0.0, 0.0), Tilt::new(0.0, 1.0)];
+        state.update_players(PHYSICS_STEP_MS, &tilts);
+
+        // THEN only the ball of the second player moves to the right
+        let start = State::initial_ball(&LEVELS[0], &GameConfig::default()).position;
+        assert_eq!(state.players[0].ball.position, start);
+        assert!(state.players[1].ball.position.x > start.x);
+
+        // WHEN only the tilt of the first player is given
+        let position = state.players[1].ball.position;
+        state.update_players(PHYSICS_STEP_MS, &[Tilt::new(1.0, 0.0)]);
+
+        // THEN the ball of the second player is not accelerated but keeps moving
+        assert!(state.players[0].ball.position.y < start.y);
+        assert!(state.players[1].ball.position.x > position.x);
+        assert!(state.players[1].ball.velocity.x < BALL_MAX_SPEED);
+    }
+
+    #[test]
+    fn number_of_players_is_limited_test() {
+        assert_eq!(State::new().players().len(), 1);
+        assert_eq!(State::new().with_players(0).players().len(), 1);
+        assert_eq!(State::new().with_players(5).players().len(), MAX_PLAYERS);
+    }
+
+    #[test]
+    fn goals_are_credited_to_first_visitor_test() {
+        // GIVEN game state in play mode with two players
+        let mut state = State::new().with_players(2);
+        state.update(INTRO_MS, &0.0, &0.0);
+
+        // WHEN the ball of the second player visits a goal
+        state.players[1].ball.position = Vector::from(LEVELS[0].goals()[0]);
+        state.update(PHYSICS_STEP_MS, &0.0, &0.0);
+
+        // THEN the goal is credited to the second player at their current score
+        assert!(!state.goals[0].alive);
+        assert_eq!(state.players()[0].goals(), 0);
+        assert_eq!(state.players()[1].goals(), 1);
+        assert_eq!(state.players()[1].time(), PHYSICS_STEP_MS as i32);
+
+        // WHEN both balls visit the same goal at the same time
+        for player in state.players.iter_mut() {
+            player.ball.position = Vector::from(LEVELS[0].goals()[1]);
+        }
+        state.update(PHYSICS_STEP_MS, &0.0, &0.0);
+
+        // THEN the goal is credited to the first player only
+        assert!(!state.goals[1].alive);
+        assert_eq!(state.players()[0].goals(), 1);
+        assert_eq!(state.players()[0].time(), 2 * PHYSICS_STEP_MS as i32);
+        assert_eq!(state.players()[1].goals(), 1);
+        assert_eq!(state.players()[1].time(), PHYSICS_STEP_MS as i32);
+    }
+
+    #[test]
+    fn high_score_entry_test() {
+        // GIVEN a game that is over with a score that is good enough for the high scores
         let mut state = game_state_in_play_mode();
+        complete_game(&mut state, 5000);
+
+        // WHEN the game over screen is over
+        state.update(OVER_MS, &0.0, &0.0);
+
+        // THEN the player enters their initials
+        assert_eq!(*state.mode(), Mode::EnterInitials);
+
+        // WHEN the first letter is moved on and every letter is confirmed
+        let tilt = INITIALS_ANGLE + 0.1;
+        for (pitch, roll) in [
+            (0.0, 0.0),
+            (0.0, tilt),
+            (tilt, 0.0),
+            (0.0, 0.0),
+            (-tilt, 0.0),
+            (0.0, 0.0),
+        ] {
+            state.update(10, &pitch, &roll);
+            assert_eq!(*state.mode(), Mode::EnterInitials);
+        }
+        state.update(10, &tilt, &0.0);
+
+        // THEN the score is added to the high scores and a new game is ready
+        assert_eq!(*state.mode(), Mode::HighScores);
+        assert!(state
+            .events()
+            .contains(&GameEvent::HighScoreEntered { rank: 0 }));
+        let entry = state.high_scores().entries()[0];
+        assert_eq!((entry.initials().as_str(), entry.score()), ("BAA", 5000));
+        assert_eq!(state.score(), 0);
+        assert_eq!(state.level(), 0);
 
-        // WHEN the pitch and roll are both positive and above threshold
-        state.update(&(ANGLE_THRESHOLD + 0.1), &(ANGLE_THRESHOLD + 0.1));
+        // WHEN the high scores have been shown
+        state.update(HIGH_SCORES_MS, &0.0, &0.0);
 
-        // THEN the ball moves to diagonally
+        // THEN the intro and the high scores take turns
+        assert_eq!(*state.mode(), Mode::Intro);
+        state.update(INTRO_MS, &0.0, &0.0);
+        assert_eq!(*state.mode(), Mode::HighScores);
+
+        // WHEN the mpu is strongly tilted
+        state.update(SKIP_MIN_MS, &(SKIP_ANGLE + 0.1), &0.0);
+
+        // THEN the game starts
+        assert_eq!(*state.mode(), Mode::Play);
+    }
+
+    #[test]
+    fn initials_time_out_test() {
+        // GIVEN a configuration with other high score times and initials input
+        let config = GameConfig::builder()
+            .with_high_score_times(200, 500)
+            .with_initials_input(0.5, 50)
+            .build()
+            .unwrap();
+        let mut state = State::new_with_config(config);
+        state.update(INTRO_MS, &0.0, &0.0);
+        complete_game(&mut state, 5000);
+        state.update(OVER_MS, &0.0, &0.0);
+        assert_eq!(*state.mode(), Mode::EnterInitials);
+
+        // WHEN the mpu is rolled less and then more than the angle
+        state.update(10, &0.0, &0.0);
+        state.update(10, &0.0, &0.4);
+        state.update(10, &0.0, &0.6);
+
+        // THEN only the stronger roll moves the letter on
+        assert_eq!(state.initials().initials().as_str(), "BAA");
+
+        // WHEN the initials are not confirmed in time
+        state.update(469, &0.0, &0.0);
+        assert_eq!(*state.mode(), Mode::EnterInitials);
+        state.update(1, &0.0, &0.0);
+
+        // THEN the initials are taken as they are
+        assert_eq!(*state.mode(), Mode::HighScores);
+        let entry = state.high_scores().entries()[0];
+        assert_eq!(entry.initials().as_str(), "BAA");
+
+        // THEN the high scores are shown for the configured time
+        state.update(199, &0.0, &0.0);
+        assert_eq!(*state.mode(), Mode::HighScores);
+        state.update(1, &0.0, &0.0);
+        assert_eq!(*state.mode(), Mode::Intro);
+    }
+
+    #[test]
+    fn race_keeps_no_low_score_test() {
+        // GIVEN a race in play mode on the last level with all goals dead
+        let mut state = State::new().with_players(2);
+        state.update(INTRO_MS, &0.0, &0.0);
+        state.start_level(LEVELS.len() - 1);
+        for goal in state.goals.iter_mut() {
+            goal.alive = false;
+        }
+
+        // WHEN update is called
+        state.update(PHYSICS_STEP_MS, &0.0, &0.0);
+
+        // THEN the game is over without a low score
+        assert_eq!(*state.mode(), Mode::Over);
+        assert_eq!(state.low_score(), i32::MAX);
+
+        // WHEN a new race starts
+        state.players[1].goals = 3;
+        state.update(OVER_MS, &0.0, &0.0);
+
+        // THEN no initials are entered and the results of every player are reset
+        assert_eq!(*state.mode(), Mode::Play);
+        for player in state.players() {
+            assert_eq!(player.score(), 0);
+            assert_eq!(player.goals(), 0);
+            assert_eq!(player.time(), 0);
+        }
+    }
+
+    #[test]
+    fn state_with_goal_capacity_test() {
+        // GIVEN a configuration for a level with more goals than MAX_GOALS
+        const GOALS: [Point; 6] = [
+            Point::new(10, 12),
+            Point::new(30, 12),
+            Point::new(50, 12),
+            Point::new(70, 12),
+            Point::new(90, 12),
+            Point::new(110, 12),
+        ];
+        const LEVEL: [Level; 1] = [Level::new(&GOALS, Point::new(60, 40), None)];
+        let config = GameConfigBuilder::<6, { max_events(6) }>::new()
+            .with_levels(&LEVEL)
+            .build()
+            .unwrap();
+
+        // WHEN a game is played with it
+        let mut state = State::new_with_config(config).with_seed(1234);
+        state.update(INTRO_MS, &0.0, &0.0);
+
+        // THEN every goal of the level is placed
+        assert_eq!(state.goals_alive().len(), GOALS.len());
+
+        // WHEN the ball visits every goal
+        for index in 0..GOALS.len() {
+            let location = state.goals[index].location();
+            state.players[0].ball.position = Vector::from(location);
+            state.update(PHYSICS_STEP_MS, &0.0, &0.0);
+        }
+
+        // THEN the game is over
+        assert_eq!(*state.mode(), Mode::Over);
+        assert_eq!(state.players()[0].goals(), GOALS.len());
+    }
+
+    #[test]
+    fn events_fit_goal_capacity_test() {
+        // GIVEN a configuration for a level with more goals than MAX_EVENTS
+        const GOALS: [Point; 24] = goal_grid();
+        const LEVEL: [Level; 1] = [Level::new(&GOALS, Point::new(60, 40), None)];
+        let config = GameConfigBuilder::<24, { max_events(24) }>::new()
+            .with_levels(&LEVEL)
+            .build()
+            .unwrap();
+        let mut state = State::new_with_config(config);
+        state.update(INTRO_MS, &0.0, &0.0);
+
+        // WHEN the ball visits every goal in a single update
+        let location = state.ball().location();
+        for goal in state.goals.iter_mut() {
+            goal.location = location;
+        }
+        state.update(PHYSICS_STEP_MS, &0.0, &0.0);
+
+        // THEN every goal is raised as reached and the end of the game is raised after them
+        let events = state.events();
+        assert_eq!(events.len(), GOALS.len() + 2);
+        for (index, event) in events.iter().take(GOALS.len()).enumerate() {
+            assert_eq!(*event, GameEvent::GoalReached { index, player: 0 });
+        }
         assert_eq!(
-            state.ball().location(),
-            ball_location_delta(BALL_DELTA, -BALL_DELTA)
+            events[GOALS.len()..],
+            [
+                GameEvent::ModeChanged { mode: Mode::Over },
+                GameEvent::GameOver {
+                    score: PHYSICS_STEP_MS as i32,
+                    new_record: true
+                }
+            ]
         );
     }
 
     #[test]
-    fn ball_stays_put_test() {
-        // GIVEN game state in play mode
-        let mut state = game_state_in_play_mode();
+    fn save_and_restore_test() {
+        // GIVEN a two player game of the sequence variant played for a while
+        let mut state = State::new_with_seed(1234)
+            .with_variant(GameVariant::Sequence)
+            .with_players(2);
+        state.update(INTRO_MS, &0.0, &0.0);
+        state.start_level(2);
+        state.goals[0].alive = false;
+        state.goals[0].time = Some(1200);
+        state.run = RunStats::new(&[1000, 2500]);
+        state.run.split(1200);
+        state.best_splits = Vec::from_slice(&[900, 2000, 3100]).unwrap();
+        state.players[1].hazard = Some(0);
+        let tilts = [Tilt::new(0.5, 0.3), Tilt::new(-0.2, 0.4)];
+        state.update_players(PHYSICS_STEP_MS * 3 + 7, &tilts);
+
+        // WHEN the game is saved and restored
+        let mut buffer = [0; save_size(4, MAX_PLAYERS)];
+        let size = state.serialize_into(&mut buffer).unwrap();
+        let mut restored = State::deserialize(&buffer[..size]).unwrap();
+
+        // THEN the restored game is the same game
+        assert_eq!(restored.mode(), state.mode());
+        assert_eq!(restored.mode_ms, state.mode_ms);
+        assert_eq!(restored.variant(), state.variant());
+        assert_eq!(restored.level(), 2);
+        assert_eq!(restored.level_score(), state.level_score());
+        assert_eq!(restored.low_score(), state.low_score());
+        assert_eq!(restored.physics_ms, state.physics_ms);
+        assert_eq!(restored.rng, state.rng);
+        assert_eq!(restored.goals, state.goals);
+        assert_eq!(restored.run(), state.run());
+        assert_eq!(restored.best_splits, state.best_splits);
+        assert_eq!(restored.obstacles().len(), state.obstacles().len());
+        assert_eq!(restored.hazards().len(), state.hazards().len());
+        for (restored, player) in restored.players().iter().zip(state.players()) {
+            assert_eq!(restored.ball(), player.ball());
+            assert_eq!(restored.score(), player.score());
+            assert_eq!(restored.goals(), player.goals());
+            assert_eq!(restored.time(), player.time());
+            assert_eq!(restored.hazard, player.hazard);
+        }
+        let mut again = [0; save_size(4, MAX_PLAYERS)];
+        assert_eq!(restored.serialize_into(&mut again), Ok(size));
+        assert_eq!(again, buffer);
+
+        // THEN the restored game plays on the same way
+        state.update_players(PHYSICS_STEP_MS * 5, &tilts);
+        restored.update_players(PHYSICS_STEP_MS * 5, &tilts);
+        assert_eq!(restored.ball(), state.ball());
+        assert_eq!(restored.players()[1].ball(), state.players()[1].ball());
+    }
 
-        // WHEN the pitch and roll are both positive and below threshold
-        state.update(&(ANGLE_THRESHOLD - 0.1), &(ANGLE_THRESHOLD - 0.1));
+    #[test]
+    fn save_and_restore_high_scores_test() {
+        // GIVEN a game with high scores in the middle of entering initials
+        let mut high_scores = HighScoreTable::new();
+        high_scores.insert(Initials::new(*b"ABC").unwrap(), 3000);
+        high_scores.insert(Initials::new(*b"XYZ").unwrap(), 4000);
+        let mut state = game_state_in_play_mode().with_high_scores(high_scores);
+        complete_game(&mut state, 3500);
+        state.update(OVER_MS, &0.0, &0.0);
+        let tilt = INITIALS_ANGLE + 0.1;
+        state.update(10, &0.0, &0.0);
+        state.update(10, &0.0, &-tilt);
+        state.update(10, &tilt, &0.0);
+
+        // WHEN the game is saved and restored
+        let mut buffer = [0; save_size(4, 1)];
+        let size = state.serialize_into(&mut buffer).unwrap();
+        let mut restored = State::deserialize(&buffer[..size]).unwrap();
+
+        // THEN the high scores and the initials are restored
+        assert_eq!(*restored.mode(), Mode::EnterInitials);
+        assert_eq!(restored.high_scores(), state.high_scores());
+        assert_eq!(restored.initials().initials().as_str(), "ZAA");
+        assert_eq!(restored.initials().position(), 1);
+
+        // THEN the entry goes on once the mpu is level
+        restored.update(10, &tilt, &0.0);
+        assert_eq!(restored.initials().position(), 1);
+        restored.update(10, &0.0, &0.0);
+        restored.update(10, &tilt, &0.0);
+        assert_eq!(restored.initials().position(), 2);
+    }
 
-        // THEN the ball stays put
-        assert_eq!(state.ball().location(), State::initial_ball().location());
+    // a save in version 1 of the format of a game in play with two goals, one of them reached
+    const SAVE_V1: [u8; 73] = [
+        83, 66, 1, 64, 0, 1, 71, 6, 0, 0, 0, 0, 0, 0, 0, 0, 104, 16, 0, 0, 7, 0, 0, 0, 1, 10, 33,
+        143, 20, 1, 71, 6, 0, 0, 1, 0, 0, 0, 0, 255, 0, 0, 176, 66, 0, 0, 160, 65, 0, 0, 0, 63, 0,
+        0, 128, 190, 2, 7, 0, 11, 0, 1, 0, 118, 0, 13, 0, 2, 1, 109, 8, 46, 32,
+    ];
+
+    // the save of the same game in version 2 of the format, with a high score
+    const SAVE_V2: [u8; 85] = [
+        83, 66, 2, 76, 0, 1, 71, 6, 0, 0, 0, 0, 0, 0, 0, 0, 104, 16, 0, 0, 7, 0, 0, 0, 1, 10, 33,
+        143, 20, 1, 71, 6, 0, 0, 1, 0, 0, 0, 0, 255, 0, 0, 176, 66, 0, 0, 160, 65, 0, 0, 0, 63, 0,
+        0, 128, 190, 2, 7, 0, 11, 0, 1, 0, 118, 0, 13, 0, 2, 1, 1, 65, 66, 67, 104, 16, 0, 0, 65,
+        65, 65, 0, 241, 125, 70, 150,
+    ];
+
+    // the save of the same game in version 3 of the format, with the splits
+    const SAVE_V3: [u8; 108] = [
+        83, 66, 3, 99, 0, 1, 71, 6, 0, 0, 0, 0, 0, 0, 0, 0, 104, 16, 0, 0, 7, 0, 0, 0, 1, 10, 33,
+        143, 20, 1, 71, 6, 0, 0, 1, 0, 0, 0, 0, 255, 0, 0, 176, 66, 0, 0, 160, 65, 0, 0, 0, 63, 0,
+        0, 128, 190, 2, 7, 0, 11, 0, 1, 0, 118, 0, 13, 0, 2, 1, 1, 65, 66, 67, 104, 16, 0, 0, 65,
+        65, 65, 0, 220, 5, 0, 0, 255, 255, 255, 255, 1, 220, 5, 0, 0, 1, 120, 5, 0, 0, 1, 120, 5,
+        0, 0, 162, 58, 203, 31,
+    ];
+
+    #[test]
+    fn restore_every_save_version_test() {
+        for save in [&SAVE_V1[..], &SAVE_V2, &SAVE_V3] {
+            // WHEN a save of any version is restored
+            let restored = State::deserialize(save).unwrap();
+
+            // THEN the game in play is restored
+            assert_eq!(*restored.mode(), Mode::Play);
+            assert_eq!(restored.mode_ms(), 1607);
+            assert_eq!(restored.variant(), GameVariant::Free);
+            assert_eq!(restored.level(), 0);
+            assert_eq!(restored.level_score(), 1607);
+            assert_eq!(restored.low_score(), 4200);
+            assert_eq!(restored.physics_ms, 7);
+            assert_eq!(restored.rng, Some(Rng::new(344_924_426)));
+            assert_eq!(restored.players().len(), 1);
+            let player = &restored.players()[0];
+            assert_eq!(
+                (player.score(), player.goals(), player.time()),
+                (1607, 1, 0)
+            );
+            assert_eq!(player.hazard, None);
+            assert_eq!(player.ball().position, Vector::new(88.0, 20.0));
+            assert_eq!(player.ball().velocity, Vector::new(0.5, -0.25));
+            let goals: Vec<_, 4> = restored
+                .goals
+                .iter()
+                .map(|goal| (goal.location, goal.number, goal.alive))
+                .collect();
+            assert_eq!(
+                goals,
+                [
+                    (Point::new(7, 11), 1, false),
+                    (Point::new(118, 13), 2, true)
+                ]
+            );
+        }
+
+        // THEN a save of version 1 has no high scores
+        let restored = State::deserialize(&SAVE_V1).unwrap();
+        assert!(restored.high_scores().entries().is_empty());
+
+        // THEN a save of version 2 adds the high scores and the initials being entered
+        for save in [&SAVE_V2[..], &SAVE_V3] {
+            let restored = State::deserialize(save).unwrap();
+            let entries = restored.high_scores().entries();
+            assert_eq!(entries.len(), 1);
+            assert_eq!(entries[0].initials().as_str(), "ABC");
+            assert_eq!(entries[0].score(), 4200);
+            assert_eq!(restored.initials().initials().as_str(), "AAA");
+            assert_eq!(restored.initials().position(), 0);
+        }
+
+        // THEN a save of an earlier version than 3 has no splits
+        for save in [&SAVE_V1[..], &SAVE_V2] {
+            let restored = State::deserialize(save).unwrap();
+            assert!(restored.goals.iter().all(|goal| goal.time().is_none()));
+            assert!(restored.run().splits().is_empty());
+            assert!(restored.best_splits.is_empty());
+        }
+
+        // THEN a save of version 3 adds the time each goal was reached and the splits
+        let restored = State::deserialize(&SAVE_V3).unwrap();
+        let times: Vec<_, 4> = restored.goals.iter().map(|goal| goal.time()).collect();
+        assert_eq!(times, [Some(1500), None]);
+        assert_eq!(restored.run().splits(), &[1500]);
+        assert_eq!(restored.run().best(), &[1400]);
+        assert_eq!(restored.best_splits, [1400]);
+
+        // THEN the save of version 3 is written again byte for byte
+        let mut buffer = [0; save_size(4, 1)];
+        let size = restored.serialize_into(&mut buffer).unwrap();
+        assert_eq!(&buffer[..size], &SAVE_V3);
+
+        // THEN a save with a byte left over after the payload of its version is rejected
+        let mut longer = [0; SAVE_V1.len() + 1];
+        longer[..SAVE_V1.len() - 4].copy_from_slice(&SAVE_V1[..SAVE_V1.len() - 4]);
+        longer[3] += 1;
+        let crc = crc32(&longer[..SAVE_V1.len() - 3]);
+        longer[SAVE_V1.len() - 3..].copy_from_slice(&crc.to_le_bytes());
+        assert_eq!(
+            State::deserialize(&longer).err(),
+            Some(SaveError::InvalidData)
+        );
     }
 
     #[test]
-    fn ball_visits_goal_test() {
-        // GIVEN game state in play mode with first goal alive
-        let mut state = game_state_in_play_mode();
-        assert!(state.goals[0].alive);
-        assert_eq!(state.goals_alive().len(), State::initial_goals().len());
+    fn restore_broken_save_test() {
+        // GIVEN a saved game
+        let state = game_state_in_play_mode();
+        let mut buffer = [0; save_size(4, 1)];
+        let size = state.serialize_into(&mut buffer).unwrap();
+        let save = &buffer[..size];
+        // change a byte of the save and update its CRC
+        let changed = |index: usize, value: u8| {
+            let mut bytes = buffer;
+            bytes[index] = value;
+            let crc = crc32(&bytes[..size - 4]);
+            bytes[size - 4..size].copy_from_slice(&crc.to_le_bytes());
+            bytes
+        };
+
+        // THEN a buffer that is too small is rejected
+        let mut small = [0; 16];
+        assert_eq!(
+            state.serialize_into(&mut small),
+            Err(SaveError::BufferTooSmall)
+        );
 
-        // WHEN the ball moves to visit the goal
-        state.ball.location = State::initial_goals()[0].location();
-        state.update(&0.0, &0.0);
+        // THEN a save of another version of the format is rejected
+        assert_eq!(
+            State::deserialize(&changed(2, 4)[..size]).err(),
+            Some(SaveError::UnsupportedVersion { version: 4 })
+        );
 
-        // THEN the goal is dead
-        assert!(!state.goals[0].alive);
-        assert_eq!(state.goals_alive().len(), State::initial_goals().len() - 1);
+        // THEN a save with more payload than its version of the format has is rejected
+        for version in [1, 2] {
+            assert_eq!(
+                State::deserialize(&changed(2, version)[..size]).err(),
+                Some(SaveError::InvalidData)
+            );
+        }
+
+        // THEN a save of a level or a mode that does not exist is rejected
+        assert_eq!(
+            State::deserialize(&changed(11, 9)[..size]).err(),
+            Some(SaveError::InvalidData)
+        );
+        assert_eq!(
+            State::deserialize(&changed(5, 6)[..size]).err(),
+            Some(SaveError::InvalidData)
+        );
+
+        // THEN a save of too many high scores, of initials that cannot be entered or of too
+        // many splits is rejected
+        let splits = size - 4 - 3;
+        let high_scores = splits - 4 * state.goals.len() - 5;
+        for (index, value) in [
+            (high_scores, 11),
+            (high_scores + 1, b'a'),
+            (high_scores + 4, 4),
+            (splits, 16),
+        ] {
+            assert_eq!(
+                State::deserialize(&changed(index, value)[..size]).err(),
+                Some(SaveError::InvalidData)
+            );
+        }
+
+        // THEN a save with a damaged or missing byte is rejected
+        let mut damaged = buffer;
+        damaged[20] ^= 0x10;
+        assert_eq!(
+            State::deserialize(&damaged[..size]).err(),
+            Some(SaveError::BadCrc)
+        );
+        assert_eq!(
+            State::deserialize(&save[..size - 1]).err(),
+            Some(SaveError::Truncated)
+        );
+
+        // THEN a save of more time than a physics step not yet simulated, or of a ball that
+        // is not at a finite place or speed, is rejected
+        let mut broken = [
+            game_state_in_play_mode(),
+            game_state_in_play_mode(),
+            game_state_in_play_mode(),
+        ];
+        broken[0].physics_ms = u32::MAX - 5;
+        broken[1].players[0].ball.position = Vector::new(f32::INFINITY, 20.0);
+        broken[2].players[0].ball.velocity = Vector::new(0.5, f32::NAN);
+        for state in broken {
+            let mut bytes = [0; save_size(4, 1)];
+            let size = state.serialize_into(&mut bytes).unwrap();
+            assert_eq!(
+                State::deserialize(&bytes[..size]).err(),
+                Some(SaveError::InvalidData)
+            );
+        }
+
+        // THEN a save for a configuration with fewer levels is rejected
+        let level = changed(11, 2);
+        let config = GameConfig::builder()
+            .with_levels(&LEVELS[..1])
+            .build()
+            .unwrap();
+        assert_eq!(
+            State::deserialize_with_config(config, &level[..size]).err(),
+            Some(SaveError::InvalidData)
+        );
+        assert!(State::deserialize(&level[..size]).is_ok());
+    }
+
+    #[test]
+    fn state_with_config_test() {
+        // GIVEN a configuration with a single level, a small ball and a slow physics step
+        const GOALS: [Point; 1] = [Point::new(100, 50)];
+        const LEVEL: [Level; 1] = [Level::new(&GOALS, Point::new(20, 20), None)];
+        let config = GameConfig::builder()
+            .with_levels(&LEVEL)
+            .with_ball_size(4)
+            .with_goal_size(6)
+            .with_physics_step_ms(2 * PHYSICS_STEP_MS)
+            .with_screen_times(0, 0, 1)
+            .with_over_page_ms(0)
+            .build()
+            .unwrap();
+
+        // WHEN a game is played with it
+        let mut state = State::new_with_config(config);
+        state.update(0, &0.0, &0.0);
+        assert_eq!(*state.mode(), Mode::Play);
+        assert_eq!(state.ball().location(), Point::new(20, 20));
+        assert_eq!(state.ball().size(), 4);
+        assert_eq!(state.goals_alive()[0].size(), 6);
+
+        // THEN the ball only moves once a whole physics step has passed
+        state.update(PHYSICS_STEP_MS, &0.0, &1.0);
+        assert_eq!(state.players[0].ball.position, Vector::new(20.0, 20.0));
+        state.update(PHYSICS_STEP_MS, &0.0, &1.0);
+        assert!(state.players[0].ball.position.x > 20.0);
+
+        // THEN the game is over after the only level
+        state.players[0].ball.position = Vector::from(GOALS[0]);
+        state.update(2 * PHYSICS_STEP_MS, &0.0, &0.0);
+        assert_eq!(*state.mode(), Mode::Over);
+    }
+
+    #[test]
+    fn state_on_other_display_test() {
+        // GIVEN a game on a square display
+        let layout = Layout::new(Size::new(128, 128));
+        let config = GameConfig::builder().with_layout(layout).build().unwrap();
+        let mut state = State::new_with_config(config);
+        state.update(INTRO_MS, &0.0, &0.0);
+
+        // THEN the outline and the initial ball location of the level are scaled to the display
+        assert_eq!(state.screen_outline_top_left(), SCREEN_OUTLINE_TOP_LET);
+        assert_eq!(state.screen_outline_size(), Size::new(127, 119));
+        assert_eq!(state.ball().location(), Point::new(88, 33));
+
+        // WHEN the ball is rolled down for a while
+        for _ in 0..200 {
+            state.update(PHYSICS_STEP_MS, &-1.0, &0.0);
+        }
+
+        // THEN the ball comes to rest on the bottom of the larger game space
+        assert_eq!(state.ball().location().y, 120);
+    }
+
+    #[test]
+    fn goals_do_not_overlap_on_short_display_test() {
+        // GIVEN the default levels squeezed onto a short display
+        let layout = Layout::new(Size::new(128, 32));
+        let config = GameConfig::builder().with_layout(layout).build().unwrap();
+        let seeds = [None, Some(1), Some(2), Some(3), Some(42), Some(1234)];
+        for seed in seeds.into_iter().chain((0..200).map(Some)) {
+            // GIVEN a game with the goals where the levels define them or placed from a seed
+            let mut state = State::new_with_config(config);
+            if let Some(seed) = seed {
+                state = state.with_seed(seed);
+            }
+
+            for (index, level) in LEVELS.iter().enumerate() {
+                // WHEN the level starts
+                state.start_level(index);
+
+                // THEN no goal overlaps the ball, an obstacle, a hole or another goal on the
+                // display
+                let ball = State::initial_ball(level, &config);
+                let goals = goal_locations(&state);
+                for (i, goal) in goals.iter().enumerate() {
+                    assert!(!intersects(*goal, GOAL_SIZE, ball.location(), BALL_SIZE));
+                    for obstacle in state.obstacles() {
+                        assert!(!intersects_rectangle(
+                            *goal,
+                            Size::new_equal(GOAL_SIZE),
+                            obstacle.location(),
+                            obstacle.size()
+                        ));
+                    }
+                    for hazard in state.hazards() {
+                        assert!(!intersects(
+                            *goal,
+                            GOAL_SIZE,
+                            hazard.location(),
+                            hazard.size()
+                        ));
+                    }
+                    for other in goals.iter().skip(i + 1) {
+                        assert!(!intersects(*goal, GOAL_SIZE, *other, GOAL_SIZE));
+                    }
+                }
+            }
+        }
     }
 
     #[test]
     fn update_score_test() {
         // GIVEN game state in play mode
         let mut state = game_state_in_play_mode();
-        assert_eq!(state.score(), 1);
+        assert_eq!(state.score(), 0);
         // WHEN update is called
-        state.update(&0.0, &0.0);
-        // THEN the score increments
-        assert_eq!(state.score(), 2);
+        state.update(7, &0.0, &0.0);
+        // THEN the elapsed time is added to the score
+        assert_eq!(state.score(), 7);
         // WHEN update is called
-        state.update(&0.0, &0.0);
-        // THEN the score increments
-        assert_eq!(state.score(), 3);
+        state.update(13, &0.0, &0.0);
+        // THEN the elapsed time is added to the score
+        assert_eq!(state.score(), 20);
+    }
+
+    #[test]
+    fn score_only_counts_play_time_test() {
+        // GIVEN game state in intro mode
+        let mut state = State::new();
+
+        // WHEN a long time passes in intro mode
+        state.update(INTRO_MS, &0.0, &0.0);
+
+        // THEN the score does not change
+        assert_eq!(*state.mode(), Mode::Play);
+        assert_eq!(state.score(), 0);
+    }
+
+    #[test]
+    fn physics_runs_in_fixed_steps_test() {
+        // GIVEN two games in play mode
+        let mut state1 = game_state_in_play_mode();
+        let mut state2 = game_state_in_play_mode();
+
+        // WHEN less time than a physics step passes
+        state1.update(PHYSICS_STEP_MS - 1, &0.0, &1.0);
+
+        // THEN the ball does not move yet
+        assert_eq!(
+            *state1.ball(),
+            State::initial_ball(&LEVELS[0], &GameConfig::default())
+        );
+
+        // WHEN the rest of the step passes
+        state1.update(1, &0.0, &1.0);
+
+        // THEN the ball moves
+        assert!(
+            state1.players[0].ball.position.x
+                > State::initial_ball(&LEVELS[0], &GameConfig::default())
+                    .position
+                    .x
+        );
+
+        // WHEN the first game is updated once more with three steps and the other four times
+        // with one step, so that both games have taken four steps
+        state1.update(3 * PHYSICS_STEP_MS, &0.0, &1.0);
+        for _ in 0..4 {
+            state2.update(PHYSICS_STEP_MS, &0.0, &1.0);
+        }
+
+        // THEN the ball moves the same
+        assert_eq!(state1.ball(), state2.ball());
     }
 
     #[test]
@@ -424,20 +3648,104 @@ mod tests {
         assert_eq!(state.screen_outline_size(), SCREEN_OUTLINE_SIZE);
     }
 
-    fn ball_location_delta(delta_x: i32, delta_y: i32) -> Point {
-        Point::new(
-            State::initial_ball().location.x + delta_x,
-            State::initial_ball().location.y + delta_y,
+    /// Return the locations of two rows of goals along the top of the game space.
+    const fn goal_grid<const L: usize>() -> [Point; L] {
+        let mut goals = [Point::zero(); L];
+        let mut index = 0;
+        while index < L {
+            goals[index] = Point::new((index % 12) as i32 * 10, 10 + (index / 12) as i32 * 10);
+            index += 1;
+        }
+        goals
+    }
+
+    fn goal_locations(state: &State) -> Vec<Point, 4> {
+        state.goals.iter().map(|goal| goal.location()).collect()
+    }
+
+    fn ball_position_delta(state: &State) -> Vector {
+        Vector::new(
+            state.players[0].ball.position.x
+                - State::initial_ball(&LEVELS[0], &GameConfig::default())
+                    .position
+                    .x,
+            state.players[0].ball.position.y
+                - State::initial_ball(&LEVELS[0], &GameConfig::default())
+                    .position
+                    .y,
         )
     }
 
+    /// Return the walls the ball of the first player bounced off during the last update.
+    fn wall_hits(state: &State) -> Vec<Wall, 4> {
+        state
+            .events()
+            .iter()
+            .filter_map(|event| match event {
+                GameEvent::WallHit { player: 0, wall } => Some(*wall),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Return the hole the ball of the first player fell into during the last update.
+    fn fallen(state: &State) -> Option<Hazard> {
+        state.events().iter().find_map(|event| match event {
+            GameEvent::FellIntoHole { player: 0, hazard } => Some(*hazard),
+            _ => None,
+        })
+    }
+
+    /// Return a full high score table of scores that no game of the tests beats.
+    fn full_high_scores() -> HighScoreTable {
+        let mut table = HighScoreTable::new();
+        for _ in 0..HIGH_SCORES {
+            table.insert(Initials::default(), 1);
+        }
+        table
+    }
+
+    /// Play a single player game to the end by moving the ball onto one goal after another.
+    /// # Arguments
+    /// * `state` - the game, in play mode on the first level
+    /// * `wait_ms` - the time to wait before moving the ball onto each goal
+    fn visit_every_goal(state: &mut State, wait_ms: u32) {
+        while *state.mode() != Mode::Over {
+            for index in 0..state.goals.len() {
+                state.update(wait_ms, &0.0, &0.0);
+                state.players[0].ball.position = Vector::from(state.goals[index].location);
+                state.update(PHYSICS_STEP_MS, &0.0, &0.0);
+            }
+            if *state.mode() == Mode::NextLevel {
+                state.update(NEXT_LEVEL_MS, &0.0, &0.0);
+            }
+        }
+    }
+
+    /// Complete a single player game with the given score.
+    /// # Arguments
+    /// * `state` - the game, in play mode
+    /// * `score` - the score of the game
+    fn complete_game(state: &mut State, score: i32) {
+        state.start_level(LEVELS.len() - 1);
+        state.players[0].score = score;
+        for goal in state.goals.iter_mut() {
+            goal.alive = false;
+        }
+        state.update(0, &0.0, &0.0);
+        assert_eq!(*state.mode(), Mode::Over);
+    }
+
     fn game_state_in_play_mode() -> State {
         let mut state = State::default();
         assert_eq!(state.score(), 0);
         assert_eq!(*state.mode(), Mode::Intro);
-        state.update(&0.0, &0.0);
-        assert_eq!(*state.ball(), State::initial_ball());
-        assert_eq!(state.score(), 1);
+        state.update(INTRO_MS, &0.0, &0.0);
+        assert_eq!(
+            *state.ball(),
+            State::initial_ball(&LEVELS[0], &GameConfig::default())
+        );
+        assert_eq!(state.score(), 0);
         state
     }
 }
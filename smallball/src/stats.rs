@@ -0,0 +1,99 @@
+//!
+//! This file defines the statistics of a run of the SmallBall game, a run being a game from
+//! its first level to the end of its last level. The statistics are the splits of the run,
+//! the time into the game at which each goal was reached, compared with the splits of the
+//! best run, the run of the low score, as speedrunners do.
+//!
+
+use crate::config::MAX_SPLITS;
+use heapless::Vec;
+
+/// The splits of a run and of the best run it is compared with.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RunStats {
+    /// the time in milliseconds into the game at which each goal was reached, in the order
+    /// the goals were reached over all levels
+    splits: Vec<i32, MAX_SPLITS>,
+    /// the splits of the best run before this run, empty if there was none
+    best: Vec<i32, MAX_SPLITS>,
+}
+
+impl RunStats {
+    /// Return the statistics of a new run without splits.
+    /// # Arguments
+    /// * `best` - the splits of the best run before this run, empty if there was none
+    pub fn new(best: &[i32]) -> Self {
+        RunStats {
+            splits: Vec::new(),
+            best: best.iter().copied().take(MAX_SPLITS).collect(),
+        }
+    }
+
+    /// Return the time in milliseconds into the game at which each goal was reached, in the
+    /// order the goals were reached over all levels.
+    pub fn splits(&self) -> &[i32] {
+        &self.splits
+    }
+
+    /// Return the splits of the best run before this run, empty if there was none.
+    pub fn best(&self) -> &[i32] {
+        &self.best
+    }
+
+    /// Return how much later in milliseconds the goal with the given index was reached than
+    /// in the best run, negative if it was reached earlier, or None if either run did not
+    /// reach it.
+    /// # Arguments
+    /// * `index` - the index of the split, starting at zero for the first goal reached
+    pub fn delta(&self, index: usize) -> Option<i32> {
+        Some(self.splits.get(index)? - self.best.get(index)?)
+    }
+
+    /// Record that a goal was reached. Splits beyond MAX_SPLITS are not recorded.
+    /// # Arguments
+    /// * `time` - the time in milliseconds into the game at which the goal was reached
+    pub(crate) fn split(&mut self, time: i32) {
+        self.splits.push(time).ok();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RunStats;
+    use crate::config::MAX_SPLITS;
+
+    #[test]
+    fn run_stats_test() {
+        // GIVEN a run without a best run
+        let mut run = RunStats::new(&[]);
+        run.split(1200);
+        run.split(2500);
+
+        // THEN the splits are recorded without deltas
+        assert_eq!(run.splits(), &[1200, 2500]);
+        assert!(run.best().is_empty());
+        assert_eq!(run.delta(0), None);
+
+        // GIVEN a run compared with that run
+        let mut next = RunStats::new(run.splits());
+        next.split(1000);
+        next.split(2920);
+        next.split(4000);
+
+        // THEN the deltas are against the splits of the best run
+        assert_eq!(next.best(), &[1200, 2500]);
+        assert_eq!(next.delta(0), Some(-200));
+        assert_eq!(next.delta(1), Some(420));
+        assert_eq!(next.delta(2), None);
+    }
+
+    #[test]
+    fn run_stats_capacity_test() {
+        let mut run = RunStats::default();
+        for split in 0..=MAX_SPLITS as i32 {
+            run.split(split);
+        }
+        assert_eq!(run.splits().len(), MAX_SPLITS);
+        assert_eq!(RunStats::new(run.splits()).best(), run.splits());
+    }
+}
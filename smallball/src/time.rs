@@ -0,0 +1,73 @@
+//!
+//! Formatting of the times shown by the game, such as the score and the splits.
+//!
+
+use core::fmt::{Display, Formatter, Result};
+
+/// A time in milliseconds that is displayed as minutes, seconds and hundredths of a
+/// second, for example `01:02.34`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Time(pub i32);
+
+impl Display for Time {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let sign = if self.0 < 0 { "-" } else { "" };
+        let ms = self.0.unsigned_abs();
+        write!(
+            f,
+            "{}{:02}:{:02}.{:02}",
+            sign,
+            ms / 60_000,
+            ms / 1000 % 60,
+            ms / 10 % 100
+        )
+    }
+}
+
+/// A time in milliseconds that is displayed as seconds and hundredths of a second, for
+/// example `4.20`. The sign is always displayed with the `+` flag, so that `{:+}` displays
+/// a difference between two times such as `+0.42` or `-1.05`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Seconds(pub i32);
+
+impl Display for Seconds {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        let sign = if self.0 < 0 {
+            "-"
+        } else if f.sign_plus() {
+            "+"
+        } else {
+            ""
+        };
+        let ms = self.0.unsigned_abs();
+        write!(f, "{}{}.{:02}", sign, ms / 1000, ms / 10 % 100)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Seconds, Time};
+
+    #[test]
+    fn time_display_test() {
+        assert_eq!(Time(0).to_string(), "00:00.00");
+        assert_eq!(Time(9).to_string(), "00:00.00");
+        assert_eq!(Time(10).to_string(), "00:00.01");
+        assert_eq!(Time(1234).to_string(), "00:01.23");
+        assert_eq!(Time(62_345).to_string(), "01:02.34");
+        assert_eq!(Time(599_999).to_string(), "09:59.99");
+        assert_eq!(Time(6_000_000).to_string(), "100:00.00");
+        assert_eq!(Time(-420).to_string(), "-00:00.42");
+    }
+
+    #[test]
+    fn seconds_display_test() {
+        assert_eq!(Seconds(0).to_string(), "0.00");
+        assert_eq!(Seconds(4209).to_string(), "4.20");
+        assert_eq!(Seconds(62_345).to_string(), "62.34");
+        assert_eq!(Seconds(-1050).to_string(), "-1.05");
+        assert_eq!(format!("{:+}", Seconds(420)), "+0.42");
+        assert_eq!(format!("{:+}", Seconds(0)), "+0.00");
+        assert_eq!(format!("{:+}", Seconds(-1050)), "-1.05");
+    }
+}